@@ -11,8 +11,36 @@ pub const JAVA_JRE_MANIFEST_URL: &str = "https://launchermeta.mojang.com/v1/prod
 /// Base URL for Minecraft assets.
 pub const MC_ASSETS_BASE_URL: &str = "https://resources.download.minecraft.net";
 
-#[cfg(windows)]
-pub const MC_MS_STORE_IDENTIFIER: &str = "Microsoft.4297127D64EC6_8wekyb3d8bbwe";
+/// URL prefixes of the official servers of the game files.
+pub const MC_OFFICIAL_URLS: &[&str] = &[
+    "https://piston-meta.mojang.com/",
+    "https://piston-data.mojang.com/",
+    "https://launchermeta.mojang.com/",
+    "https://launcher.mojang.com/",
+    "https://libraries.minecraft.net/",
+    "https://resources.download.minecraft.net/",
+];
+
+/// Base URL of the Fabric meta server.
+pub const FABRIC_META_URL: &str = "https://meta.fabricmc.net";
+
+/// Base URL of the Quilt meta server.
+pub const QUILT_META_URL: &str = "https://meta.quiltmc.org";
 
 /// Maximum files downloaded at the same time
 pub const MAX_PARALLEL_DOWNLOAD: usize = 50;
+
+/// Maximum legacy assets placed at the same time
+pub const MAX_PARALLEL_ASSET_PLACEMENT: usize = 16;
+
+/// Default port of a Minecraft server
+pub const MC_DEFAULT_SERVER_PORT: u16 = 25565;
+
+/// Base URL of the CurseForge API.
+pub const CURSEFORGE_API_URL: &str = "https://api.curseforge.com";
+
+/// Base URL of the Modrinth API.
+pub const MODRINTH_API_URL: &str = "https://api.modrinth.com";
+
+/// User agent sent to third party APIs.
+pub const USER_AGENT: &str = concat!("grindstone-rs/", env!("CARGO_PKG_VERSION"));
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 /// Custom Result type with [`GrindstoneError`](GrindstoneError) as Error type
 pub type GrindstoneResult<T> = Result<T, GrindstoneError>;
 
@@ -7,6 +9,11 @@ pub enum GrindstoneError {
     #[error("Invalid configuration `{0}`")]
     InvalidConfig(String),
 
+    /// The home directory of the user could not be determined.
+    /// It is needed to locate the folders of the official launcher.
+    #[error("Home directory could not be found")]
+    HomeDirectoryNotFound,
+
     /// Standard IO Error.
     #[error("{0}")]
     IO(std::io::Error),
@@ -25,6 +32,10 @@ pub enum GrindstoneError {
     #[error("Checksums do not match")]
     ChecksumMismatch,
 
+    /// A downloaded file does not have the expected size.
+    #[error("File size does not match: expected {0} bytes, got {1}")]
+    SizeMismatch(u64, u64),
+
     /// A checksum that is provided from an index has an invalid format.
     /// `Versiondata` and the `Assetindex` provide checksum for resources.
     #[error("Checksum does not have a valid format: {0}")]
@@ -39,6 +50,131 @@ pub enum GrindstoneError {
     /// Format: `<package>:<name>:<version>`
     #[error("Format of a library name is invalid and not supported")]
     LibraryNameFormat,
+
+    /// No instance exists with the given name.
+    #[error("Instance '{0}' does not exist")]
+    InstanceNotFound(String),
+
+    /// An instance with the given name already exists.
+    #[error("Instance '{0}' already exists")]
+    InstanceAlreadyExists(String),
+
+    /// The name cannot be used for an instance, as it is also its folder name.
+    #[error("Instance name '{0}' is invalid")]
+    InvalidInstanceName(String),
+
+    /// The instance is locked by another operation or process.
+    #[error("Instance '{0}' is locked")]
+    InstanceLocked(String),
+
+    /// An instance of another launcher could not be imported.
+    #[error("Instance could not be imported: {0}")]
+    InvalidImport(String),
+
+    /// No version of a mod is compatible with the game version and mod loader of the instance.
+    #[error("No version of mod '{0}' is compatible with the instance")]
+    NoCompatibleModVersion(String),
+
+    /// A mod cannot be installed alongside another mod, installed or being installed.
+    #[error("Mod '{0}' is incompatible with mod '{1}'")]
+    IncompatibleMod(String, String),
+
+    /// The version uses a mod loader that the updater cannot install.
+    /// Only vanilla versions can be installed for now.
+    #[error("Mod loader {0} cannot be installed")]
+    UnsupportedModLoader(String),
+
+    /// A mod is not installed in the instance.
+    #[error("Mod '{0}' is not installed")]
+    ModNotInstalled(String),
+
+    /// A resource pack or shader pack is missing required files.
+    #[error("Pack '{0}' is invalid: {1}")]
+    InvalidPack(String, String),
+
+    /// A server address could not be parsed.
+    /// Format: `<host>[:<port>]`
+    #[error("Server address '{0}' is invalid")]
+    InvalidServerAddress(String),
+
+    /// A Minecraft server answered with data that does not follow the protocol.
+    #[error("Invalid server response: {0}")]
+    InvalidServerResponse(String),
+
+    /// A network operation took longer than the allowed time.
+    #[error("Operation timed out")]
+    Timeout,
+
+    /// NBT data could not be read or written.
+    /// NBT is used by the game to store worlds and the server list.
+    #[error("Invalid NBT data: {0}")]
+    Nbt(String),
+
+    /// An archive does not have the expected content.
+    #[error("Invalid archive: {0}")]
+    InvalidArchive(String),
+
+    /// Problem while reading or writing a zip archive.
+    #[error("{0}")]
+    Zip(zip::result::ZipError),
+
+    /// Files could not be downloaded, even after retrying.
+    #[error("{} download(s) failed: {}", .0.len(), failed_files(.0))]
+    DownloadFailed(Vec<FailedDownload>),
+
+    /// Files needed by the game are missing and cannot be downloaded in offline mode.
+    #[error("{} file(s) missing for offline mode: {}", .0.len(), missing_files(.0))]
+    MissingFiles(Vec<PathBuf>),
+
+    /// A request to a server was needed in offline mode.
+    #[error("{0} cannot be fetched in offline mode")]
+    Offline(String),
+
+    /// No mirror serves a file of the official servers, and they must not be used.
+    #[error("No mirror serves {0} and the official servers are disabled")]
+    NoMirror(String),
+}
+
+/// A file that could not be downloaded.
+#[derive(Debug)]
+pub struct FailedDownload {
+    /// URL of the file.
+    pub url: String,
+    /// Path where the file should have been saved.
+    pub file: PathBuf,
+    /// Error of the last attempt.
+    pub error: Box<GrindstoneError>,
+}
+
+fn failed_files(failed: &[FailedDownload]) -> String {
+    failed
+        .iter()
+        .map(|f| format!("{} ({})", f.file.to_string_lossy(), f.error))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Maximum missing files listed in the error message.
+const LISTED_MISSING_FILES: usize = 10;
+
+fn missing_files(missing: &[PathBuf]) -> String {
+    let mut listed = missing
+        .iter()
+        .take(LISTED_MISSING_FILES)
+        .map(|f| f.to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+
+    if missing.len() > LISTED_MISSING_FILES {
+        listed.push(format!("and {} more", missing.len() - LISTED_MISSING_FILES));
+    }
+
+    listed.join(", ")
+}
+
+impl From<FailedDownload> for GrindstoneError {
+    fn from(failed: FailedDownload) -> Self {
+        Self::DownloadFailed(vec![failed])
+    }
 }
 
 impl From<std::io::Error> for GrindstoneError {
@@ -59,6 +195,12 @@ impl From<reqwest::Error> for GrindstoneError {
     }
 }
 
+impl From<zip::result::ZipError> for GrindstoneError {
+    fn from(err: zip::result::ZipError) -> Self {
+        Self::Zip(err)
+    }
+}
+
 impl From<hex::FromHexError> for GrindstoneError {
     fn from(err: hex::FromHexError) -> Self {
         Self::InvalidChecksum(err)
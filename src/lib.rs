@@ -1,6 +1,7 @@
 mod constants;
 pub mod errors;
-mod minecraft;
+pub mod minecraft;
+pub mod nbt;
 mod updater;
 mod utils;
 
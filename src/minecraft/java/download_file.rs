@@ -1,14 +1,17 @@
-use std::{collections::HashMap, fs, path::PathBuf};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
 
-use futures::{stream::FuturesUnordered, StreamExt};
 use log::{debug, trace};
 use serde::Deserialize;
 
 use crate::{
+    download::{Download, DownloadManager},
     errors::GrindstoneResult,
     event::{EventType, Progress},
     invoke_callback,
-    utils::download::download_file_check,
 };
 
 use super::{
@@ -26,8 +29,9 @@ pub struct DownloadFile {
 impl Java {
     pub async fn download_jre_files(
         &self,
-        dest: &PathBuf,
+        dest: &Path,
         files: HashMap<String, JreFile>,
+        downloads: &DownloadManager,
     ) -> GrindstoneResult<()> {
         debug!("No JRE found, downloading one");
 
@@ -52,9 +56,7 @@ impl Java {
             );
         };
 
-        let client = reqwest::Client::new();
-
-        let mut tasks = FuturesUnordered::new();
+        let mut batch = Vec::new();
 
         for (path, data) in files {
             match data.file_type {
@@ -72,13 +74,17 @@ impl Java {
                     let raw_data = data.downloads.unwrap().raw;
                     let sha = hex::decode(&raw_data.sha1)?;
 
-                    let a =
-                        download_file_check(&client, raw_data.url.clone(), path.clone(), Some(sha));
-                    tasks.push(a);
-
                     if data.executable == Some(true) {
-                        exec.push(path);
+                        exec.push(path.clone());
                     }
+
+                    batch.push(Download {
+                        url: raw_data.url,
+                        file: path,
+                        sha1: Some(sha),
+                        size: Some(raw_data.size as u64),
+                        alternate_urls: Vec::new(),
+                    });
                 }
                 FileType::Link => {
                     links.insert(path, data);
@@ -86,11 +92,13 @@ impl Java {
             }
         }
 
-        while let Some(res) = tasks.next().await {
-            count += 1;
-            let name = res?;
-            log_progress(count, max, format!("Downloaded file {}", name));
-        }
+        let folders = count;
+        count += batch.len() as u32;
+        downloads
+            .download(batch, |done, _, name| {
+                log_progress(folders + done, max, format!("Downloaded file {}", name))
+            })
+            .await?;
 
         trace!("Creating missing symbolic links");
         for (path, data) in links {
@@ -128,7 +136,7 @@ impl Java {
 
         #[cfg(unix)]
         {
-            use std::os::{linux::raw, unix::prelude::PermissionsExt};
+            use std::os::unix::prelude::PermissionsExt;
 
             trace!("Apply files permissions");
             for path in exec {
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use serde::Deserialize;
 use time::OffsetDateTime;
 
-use crate::{constants, errors::GrindstoneResult};
+use crate::{constants, download::DownloadManager, errors::GrindstoneResult};
 
 use super::download_file::DownloadFile;
 
@@ -34,15 +34,9 @@ pub struct JreManifest {
 
 impl JreManifest {
     /// Get the JRE manifest from Mojang servers.
-    pub async fn get() -> GrindstoneResult<Self> {
-        let response = reqwest::Client::new()
-            .get(constants::JAVA_JRE_MANIFEST_URL)
-            .send()
-            .await?
-            .error_for_status()?
-            .json::<JreManifest>()
-            .await?;
-
-        Ok(response)
+    pub async fn get(downloads: &DownloadManager) -> GrindstoneResult<Self> {
+        downloads
+            .fetch_cached_json(constants::JAVA_JRE_MANIFEST_URL)
+            .await
     }
 }
@@ -1,10 +1,11 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use log::{debug, trace};
 
 use crate::{
     config::Config,
-    errors::GrindstoneResult,
+    download::DownloadManager,
+    errors::{GrindstoneError, GrindstoneResult},
     event::{EventType, Progress},
     invoke_callback,
     minecraft::java::{jre_manifest::JreManifest, runtime_manifest::JreRuntimeManifest},
@@ -13,8 +14,8 @@ use crate::{
 use super::VersionData;
 
 mod download_file;
-mod jre_manifest;
-mod runtime_manifest;
+pub mod jre_manifest;
+pub mod runtime_manifest;
 mod search_jre;
 
 pub struct Java {
@@ -27,7 +28,11 @@ impl Java {
     }
     /// Get java executable from official minecraft JRE
     /// This will download the required JRE if it is not present
-    pub async fn install(&self, version_data: VersionData) -> GrindstoneResult<PathBuf> {
+    pub async fn install(
+        &self,
+        version_data: VersionData,
+        downloads: &DownloadManager,
+    ) -> GrindstoneResult<PathBuf> {
         let required_version = version_data.java_version;
 
         debug!(
@@ -49,13 +54,26 @@ impl Java {
         let java_runtime_path = match runtime_folder {
             Some(folder) => folder,
             None => {
-                let runtimes_path = self.runtime_path()?;
+                let runtimes_path = self.runtime_path();
                 runtimes_path.join(&required_version.component)
             }
         };
 
-        self.download_jre_data(required_version.component, java_runtime_path.clone())
-            .await?;
+        if self.config.offline {
+            let executable = java_executable(&java_runtime_path);
+            if !executable.is_file() {
+                return Err(GrindstoneError::MissingFiles(vec![executable]));
+            }
+
+            return Ok(java_runtime_path);
+        }
+
+        self.download_jre_data(
+            required_version.component,
+            java_runtime_path.clone(),
+            downloads,
+        )
+        .await?;
 
         Ok(java_runtime_path)
     }
@@ -64,36 +82,37 @@ impl Java {
         &self,
         name: String,
         java_runtime_path: PathBuf,
+        downloads: &DownloadManager,
     ) -> GrindstoneResult<()> {
         trace!("JRE component name: {}", name);
 
-        let manifest = JreManifest::get().await?;
+        let manifest = JreManifest::get(downloads).await?;
 
         cfg_if::cfg_if! {
             if #[cfg(target_os = "linux")]
             {
                 if let Some(data) = manifest.linux.get(&name) {
                     let data = data.first().unwrap();
-                    let man = JreRuntimeManifest::get(&data.manifest.url).await?;
+                    let man = JreRuntimeManifest::get(downloads, &data.manifest.url).await?;
                     invoke_callback!(self.config, EventType::DownloadJRE(Progress {current: 0, max: 0, message: "".to_string()}), "Downloading JRE");
-                    self.download_jre_files(&java_runtime_path, man.files).await?;
+                    self.download_jre_files(&java_runtime_path, man.files, downloads).await?;
                 }
             } else if #[cfg(target_os = "windows")]
             {
                 if let Some(data) = manifest.windows.get(&name) {
                     let data = data.first().unwrap();
-                    let man = JreRuntimeManifest::get(&data.manifest.url).await?;
+                    let man = JreRuntimeManifest::get(downloads, &data.manifest.url).await?;
                     invoke_callback!(self.config, EventType::DownloadJRE(Progress {current: 0, max: 0, message: "".to_string()}), "Downloading JRE");
-                    self.download_jre_files(&java_runtime_path, man.files).await?;
+                    self.download_jre_files(&java_runtime_path, man.files, downloads).await?;
                 }
             } else if #[cfg(target_os = "macos")]
             {
                 if let Some(data) = manifest.mac_os.get(&name) {
                     let data = data.first().unwrap();
-                    let man = JreRuntimeManifest::get(&data.manifest.url).await?;
+                    let man = JreRuntimeManifest::get(downloads, &data.manifest.url).await?;
 
                     invoke_callback!(self.config, EventType::DownloadJRE(Progress {current: 0, max: 0, message: "".to_string()}), "Downloading JRE");
-                    self.download_jre_files(&java_runtime_path, man.files).await?;
+                    self.download_jre_files(&java_runtime_path, man.files, downloads).await?;
                 }
             } else {
                 compile_error!("Unknown platform {}", env::consts::OS)
@@ -103,3 +122,47 @@ impl Java {
         Ok(())
     }
 }
+
+/// Path to the Java executable of a runtime folder.
+fn java_executable(java_runtime_path: &Path) -> PathBuf {
+    executable_for_os(java_runtime_path, std::env::consts::OS)
+}
+
+/// Path to the Java executable of a runtime folder laid out for `os`.
+///
+/// The macOS runtimes are application bundles, with the usual layout under `Contents/Home`.
+fn executable_for_os(java_runtime_path: &Path, os: &str) -> PathBuf {
+    match os {
+        "windows" => java_runtime_path.join("bin").join("java.exe"),
+        "macos" => java_runtime_path
+            .join("jre.bundle")
+            .join("Contents")
+            .join("Home")
+            .join("bin")
+            .join("java"),
+        _ => java_runtime_path.join("bin").join("java"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn executable_path() {
+        let runtime = Path::new("runtime");
+
+        assert_eq!(
+            executable_for_os(runtime, "linux"),
+            Path::new("runtime/bin/java")
+        );
+        assert_eq!(
+            executable_for_os(runtime, "windows"),
+            Path::new("runtime/bin/java.exe")
+        );
+        assert_eq!(
+            executable_for_os(runtime, "macos"),
+            Path::new("runtime/jre.bundle/Contents/Home/bin/java")
+        );
+    }
+}
@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use serde::Deserialize;
 
-use crate::errors::GrindstoneResult;
+use crate::{download::DownloadManager, errors::GrindstoneResult};
 
 use super::download_file::DownloadFile;
 
@@ -35,15 +35,7 @@ pub struct JreRuntimeManifest {
 
 impl JreRuntimeManifest {
     /// Get the JRE manifest from Mojang servers.
-    pub async fn get<S: Into<String>>(url: S) -> GrindstoneResult<Self> {
-        let response = reqwest::Client::new()
-            .get(url.into())
-            .send()
-            .await?
-            .error_for_status()?
-            .json::<JreRuntimeManifest>()
-            .await?;
-
-        Ok(response)
+    pub async fn get(downloads: &DownloadManager, url: &str) -> GrindstoneResult<Self> {
+        downloads.fetch_cached_json(url).await
     }
 }
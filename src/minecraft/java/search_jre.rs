@@ -1,45 +1,17 @@
 use std::path::PathBuf;
 
-#[cfg(windows)]
-use crate::constants::MC_MS_STORE_IDENTIFIER;
 use crate::errors::GrindstoneResult;
 
 use super::Java;
 
 impl Java {
-    #[cfg(windows)]
-    pub fn runtime_path(&self) -> GrindstoneResult<PathBuf> {
-        // Use MS store first if available
-        let mut path = dirs::data_local_dir()
-            .unwrap()
-            .join("Packages")
-            .join(MC_MS_STORE_IDENTIFIER);
-        if path.exists() {
-            path = path.join("LocalCache").join("Local");
-        } else if PathBuf::from("C:/Program Files (x86)/Minecraft").exists() {
-            path = PathBuf::from("C:/Program Files (x86)/Minecraft");
-        } else {
-            path = path.join("LocalCache").join("Local");
-        }
-        path.push("runtime");
-        Ok(path)
-    }
-
-    #[cfg(unix)]
-    pub fn runtime_path(&self) -> GrindstoneResult<PathBuf> {
-        let mut path = self.config.dot_minecraft_path();
-        path.push("runtime");
-        Ok(path)
-    }
-
-    #[cfg(target_os = "macos")]
-    /// Not yet implemented, please someone do it, i don't own a Mac OS !
-    pub fn runtime_path(&self) -> GrindstoneResult<PathBuf> {
-        unimplemented!("I don't own a Mac OS, i can't test my code or where the JRE is located.");
+    /// Folder holding the Java runtimes, in the configured game root.
+    pub fn runtime_path(&self) -> PathBuf {
+        self.config.runtimes_path()
     }
 
     pub fn search_jre(&self, name: &String) -> GrindstoneResult<Option<PathBuf>> {
-        let mut path = self.runtime_path()?;
+        let mut path = self.runtime_path();
         path.push(name);
 
         if !path.exists() {
@@ -1,8 +1,13 @@
 pub mod java;
+pub mod options;
+pub mod packs;
+pub mod server;
 mod vanilla;
+pub mod world;
 
 pub use vanilla::client::Client;
 pub use vanilla::models::version_data::{
-    asset_index_info::AssetIndexInfo, library::Library, logging_info::LoggingInfo, VersionData,
+    asset_index_info::AssetIndexInfo, library::Library, loader_profile::LoaderProfile,
+    logging_info::LoggingInfo, VersionData,
 };
 pub use vanilla::models::version_manifest::*;
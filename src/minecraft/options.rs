@@ -0,0 +1,251 @@
+use std::{
+    fmt::{Display, Formatter},
+    fs,
+    path::Path,
+};
+
+use log::trace;
+
+use crate::{config::Config, errors::GrindstoneResult, utils::fs::write_atomic};
+
+/// Key of the enabled resource packs.
+const RESOURCE_PACKS_KEY: &str = "resourcePacks";
+
+/// A line of `options.txt`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum OptionLine {
+    /// A `key:value` option.
+    Entry(String, String),
+    /// Any other line, kept as is.
+    Other(String),
+}
+
+/// Game settings stored in `options.txt`.
+///
+/// Unknown keys, unparsable lines and the order of the file are preserved.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GameOptions {
+    lines: Vec<OptionLine>,
+}
+
+impl GameOptions {
+    /// Parse the content of an `options.txt` file.
+    pub fn parse(content: &str) -> Self {
+        let lines = content
+            .lines()
+            .map(|line| match line.split_once(':') {
+                Some((key, value)) if !key.is_empty() => {
+                    OptionLine::Entry(key.to_string(), value.to_string())
+                }
+                _ => OptionLine::Other(line.to_string()),
+            })
+            .collect();
+
+        Self { lines }
+    }
+
+    /// Read an options file.
+    /// A missing file is treated as empty.
+    pub fn load(path: impl AsRef<Path>) -> GrindstoneResult<Self> {
+        if !path.as_ref().exists() {
+            return Ok(Self::default());
+        }
+
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    /// Write the options to a file.
+    pub fn save(&self, path: impl AsRef<Path>) -> GrindstoneResult<()> {
+        if let Some(parent) = path.as_ref().parent() {
+            trace!("Creating parent folder for options");
+            fs::create_dir_all(parent)?;
+        }
+
+        write_atomic(path, self.to_string())
+    }
+
+    /// Get the value of an option.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.lines.iter().find_map(|line| match line {
+            OptionLine::Entry(k, v) if k == key => Some(v.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Set the value of an option.
+    /// An existing option keeps its place in the file.
+    pub fn set<K: Into<String>, V: Into<String>>(&mut self, key: K, value: V) {
+        let key = key.into();
+        let value = value.into();
+
+        let existing = self.lines.iter_mut().find_map(|line| match line {
+            OptionLine::Entry(k, v) if *k == key => Some(v),
+            _ => None,
+        });
+
+        match existing {
+            Some(v) => *v = value,
+            None => self.lines.push(OptionLine::Entry(key, value)),
+        }
+    }
+
+    /// Set the value of an option only if it is not defined yet.
+    /// Returns `true` if the value was set.
+    pub fn set_default<K: Into<String>, V: Into<String>>(&mut self, key: K, value: V) -> bool {
+        let key = key.into();
+
+        if self.get(&key).is_some() {
+            return false;
+        }
+
+        self.lines.push(OptionLine::Entry(key, value.into()));
+        true
+    }
+
+    /// Remove an option.
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let index = self
+            .lines
+            .iter()
+            .position(|line| matches!(line, OptionLine::Entry(k, _) if k == key))?;
+
+        match self.lines.remove(index) {
+            OptionLine::Entry(_, value) => Some(value),
+            OptionLine::Other(_) => None,
+        }
+    }
+
+    /// Iterate over the options in file order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.lines.iter().filter_map(|line| match line {
+            OptionLine::Entry(k, v) => Some((k.as_str(), v.as_str())),
+            OptionLine::Other(_) => None,
+        })
+    }
+
+    /// Add default options without changing the ones already defined.
+    /// Returns the number of options that were added.
+    pub fn merge_defaults<'a>(
+        &mut self,
+        defaults: impl IntoIterator<Item = &'a (String, String)>,
+    ) -> usize {
+        defaults
+            .into_iter()
+            .filter(|(key, value)| self.set_default(key.as_str(), value.as_str()))
+            .count()
+    }
+
+    /// Enabled resource packs, from the lowest to the highest priority.
+    pub fn resource_packs(&self) -> Vec<String> {
+        self.get(RESOURCE_PACKS_KEY)
+            .and_then(|value| serde_json::from_str(value).ok())
+            .unwrap_or_default()
+    }
+
+    /// Set the enabled resource packs, from the lowest to the highest priority.
+    pub fn set_resource_packs(&mut self, packs: &[String]) -> GrindstoneResult<()> {
+        self.set(RESOURCE_PACKS_KEY, serde_json::to_string(packs)?);
+
+        Ok(())
+    }
+
+    /// Merge the default options of the configuration into the instance options file.
+    pub fn apply_defaults(config: &Config) -> GrindstoneResult<()> {
+        if config.default_options.is_empty() {
+            return Ok(());
+        }
+
+        let path = config.options_path();
+        let mut options = Self::load(&path)?;
+
+        let added = options.merge_defaults(&config.default_options);
+        if added > 0 {
+            trace!("Added {} default options", added);
+            options.save(&path)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for GameOptions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for line in &self.lines {
+            match line {
+                OptionLine::Entry(key, value) => writeln!(f, "{}:{}", key, value)?,
+                OptionLine::Other(raw) => writeln!(f, "{}", raw)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPTIONS: &str = "version:3120\nfov:0.0\n# comment\n:nokey\nlastServer:mc.example.com:25565\nresourcePacks:[\"vanilla\",\"file/pack.zip\"]\n";
+
+    #[test]
+    fn parse_and_write_back_unchanged() {
+        let options = GameOptions::parse(OPTIONS);
+
+        assert_eq!(options.get("fov"), Some("0.0"));
+        // Only the first colon separates the key
+        assert_eq!(options.get("lastServer"), Some("mc.example.com:25565"));
+        assert_eq!(options.get("# comment"), None);
+        assert_eq!(options.iter().count(), 4);
+        assert_eq!(options.to_string(), OPTIONS);
+    }
+
+    #[test]
+    fn set_keeps_position() {
+        let mut options = GameOptions::parse(OPTIONS);
+        options.set("fov", "1.0");
+        options.set("gamma", "0.5");
+
+        let keys = options.iter().map(|(k, _)| k).collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            ["version", "fov", "lastServer", "resourcePacks", "gamma"]
+        );
+        assert_eq!(options.get("fov"), Some("1.0"));
+    }
+
+    #[test]
+    fn defaults_do_not_override() {
+        let mut options = GameOptions::parse(OPTIONS);
+        let defaults = [
+            ("fov".to_string(), "1.0".to_string()),
+            ("lang".to_string(), "fr_fr".to_string()),
+        ];
+
+        assert_eq!(options.merge_defaults(&defaults), 1);
+        assert_eq!(options.get("fov"), Some("0.0"));
+        assert_eq!(options.get("lang"), Some("fr_fr"));
+    }
+
+    #[test]
+    fn remove_option() {
+        let mut options = GameOptions::parse(OPTIONS);
+
+        assert_eq!(options.remove("fov").as_deref(), Some("0.0"));
+        assert_eq!(options.remove("fov"), None);
+        assert!(!options.to_string().contains("fov"));
+    }
+
+    #[test]
+    fn resource_packs() {
+        let mut options = GameOptions::parse(OPTIONS);
+        assert_eq!(options.resource_packs(), ["vanilla", "file/pack.zip"]);
+
+        options
+            .set_resource_packs(&["vanilla".to_string()])
+            .unwrap();
+        assert_eq!(options.get("resourcePacks"), Some("[\"vanilla\"]"));
+
+        options.set("resourcePacks", "not json");
+        assert!(options.resource_packs().is_empty());
+    }
+}
@@ -0,0 +1,112 @@
+mod resource_pack;
+mod shader_pack;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::trace;
+
+use crate::{
+    errors::{GrindstoneError, GrindstoneResult},
+    utils::{
+        archive::read_entry,
+        fs::{copy_folder, temp_path},
+    },
+};
+
+pub use resource_pack::ResourcePack;
+pub use shader_pack::ShaderPack;
+
+/// Name of a pack, its file or folder name.
+fn pack_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Check that a pack name is a plain file or folder name of the packs folder.
+fn validate_pack_name(name: &str) -> GrindstoneResult<()> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        return Err(GrindstoneError::InvalidPack(
+            name.to_string(),
+            "not a file or folder name".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check if a path can be a pack: a folder or a zip archive.
+fn is_pack(path: &Path) -> bool {
+    path.is_dir()
+        || (path.is_file()
+            && path
+                .extension()
+                .map(|e| e.eq_ignore_ascii_case("zip"))
+                .unwrap_or_default())
+}
+
+/// List the packs of a folder, sorted by name.
+fn list_packs(folder: &Path) -> GrindstoneResult<Vec<PathBuf>> {
+    if !folder.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut packs = fs::read_dir(folder)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    packs.retain(|p| is_pack(p));
+    packs.sort();
+
+    Ok(packs)
+}
+
+/// Read a file of a pack, either a folder or a zip archive.
+fn read_pack_file(pack: &Path, name: &str) -> GrindstoneResult<Option<Vec<u8>>> {
+    if pack.is_dir() {
+        let path = pack.join(name);
+
+        return match path.is_file() {
+            true => Ok(Some(fs::read(path)?)),
+            false => Ok(None),
+        };
+    }
+
+    read_entry(pack, name)
+}
+
+/// Copy a pack into a packs folder.
+fn install_pack(source: &Path, folder: &Path) -> GrindstoneResult<PathBuf> {
+    let name = pack_name(source);
+    let dest = folder.join(&name);
+
+    if dest.exists() {
+        return Err(GrindstoneError::InvalidPack(
+            name,
+            "a pack with the same name is already installed".to_string(),
+        ));
+    }
+
+    trace!("Installing pack {}", name);
+    fs::create_dir_all(folder)?;
+
+    // Copied next to its final path first, a failed copy never leaves a partial pack
+    let temp = temp_path(&dest);
+    let res = match source.is_dir() {
+        true => copy_folder(source, &temp, |_| false),
+        false => fs::copy(source, &temp).map(|_| ()).map_err(Into::into),
+    }
+    .and_then(|_| Ok(fs::rename(&temp, &dest)?));
+
+    if let Err(err) = res {
+        let _ = match temp.is_dir() {
+            true => fs::remove_dir_all(&temp),
+            false => fs::remove_file(&temp),
+        };
+        return Err(err);
+    }
+
+    Ok(dest)
+}
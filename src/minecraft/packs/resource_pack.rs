@@ -0,0 +1,366 @@
+use std::path::{Path, PathBuf};
+
+use log::debug;
+use serde::Deserialize;
+
+use crate::{
+    config::Config,
+    errors::{GrindstoneError, GrindstoneResult},
+    minecraft::{options::GameOptions, server::ChatComponent},
+};
+
+use super::{install_pack, is_pack, list_packs, pack_name, read_pack_file, validate_pack_name};
+
+/// Metadata file of a resource pack.
+const PACK_MCMETA: &str = "pack.mcmeta";
+/// Icon of a resource pack.
+const PACK_PNG: &str = "pack.png";
+/// Prefix of the packs of the `resourcepacks` folder in `options.txt`.
+const FILE_PREFIX: &str = "file/";
+/// First release whose `options.txt` prefixes the pack names, 1.13.
+const FILE_PREFIX_MINOR: u32 = 13;
+/// First snapshot whose `options.txt` prefixes the pack names, 17w43a.
+const FILE_PREFIX_SNAPSHOT: (u32, u32) = (17, 43);
+/// Signature at the start of every PNG file.
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// A resource pack of an instance, either a folder or a zip archive.
+#[derive(Clone, Debug)]
+pub struct ResourcePack {
+    /// Path of the pack.
+    pub path: PathBuf,
+    /// File or folder name of the pack.
+    pub name: String,
+    /// Format of the pack, tied to the game versions it supports.
+    pub pack_format: i32,
+    /// Description displayed in game.
+    pub description: ChatComponent,
+    /// Content of `pack.png`, if it is a valid PNG image.
+    pub icon: Option<Vec<u8>>,
+    /// Whether the pack is enabled in `options.txt`.
+    pub enabled: bool,
+}
+
+#[derive(Deserialize)]
+struct PackMcmeta {
+    pack: PackSection,
+}
+
+#[derive(Deserialize)]
+struct PackSection {
+    pack_format: i32,
+    #[serde(default)]
+    description: ChatComponent,
+}
+
+impl ResourcePack {
+    /// List the resource packs of the configured instance.
+    ///
+    /// Invalid packs are skipped.
+    pub fn list_instance(config: &Config) -> GrindstoneResult<Vec<ResourcePack>> {
+        let options = GameOptions::load(config.options_path())?;
+
+        Self::list(config.resource_packs_path(), &options, &config.version.id)
+    }
+
+    /// List the resource packs of a folder, sorted by name.
+    /// * `options` - Options of the instance, used to know the enabled packs
+    /// * `version_id` - Game version of the instance, it changes how packs are enabled
+    pub fn list(
+        packs_path: impl AsRef<Path>,
+        options: &GameOptions,
+        version_id: &str,
+    ) -> GrindstoneResult<Vec<ResourcePack>> {
+        let enabled = options.resource_packs();
+
+        let packs = list_packs(packs_path.as_ref())?
+            .into_iter()
+            .filter_map(|path| match Self::read(&path) {
+                Ok(mut pack) => {
+                    pack.enabled = enabled.contains(&pack.option_name(version_id));
+                    Some(pack)
+                }
+                Err(err) => {
+                    debug!("Skipping resource pack {}: {}", path.to_string_lossy(), err);
+                    None
+                }
+            })
+            .collect();
+
+        Ok(packs)
+    }
+
+    /// Read and validate a resource pack.
+    pub fn read(path: impl AsRef<Path>) -> GrindstoneResult<ResourcePack> {
+        let path = path.as_ref();
+        let name = pack_name(path);
+
+        let invalid = |reason: &str| GrindstoneError::InvalidPack(name.clone(), reason.to_string());
+
+        if !is_pack(path) {
+            return Err(invalid("not a folder or a zip archive"));
+        }
+
+        let mcmeta = read_pack_file(path, PACK_MCMETA)?.ok_or_else(|| invalid("no pack.mcmeta"))?;
+        // Some packs start their metadata with a BOM
+        let mcmeta = String::from_utf8_lossy(&mcmeta);
+        let mcmeta = serde_json::from_str::<PackMcmeta>(mcmeta.trim_start_matches('\u{feff}'))
+            .map_err(|err| invalid(&format!("invalid pack.mcmeta: {}", err)))?;
+
+        let icon = read_pack_file(path, PACK_PNG)?.filter(|icon| icon.starts_with(PNG_SIGNATURE));
+
+        Ok(ResourcePack {
+            path: path.to_path_buf(),
+            name,
+            pack_format: mcmeta.pack.pack_format,
+            description: mcmeta.pack.description,
+            icon,
+            enabled: false,
+        })
+    }
+
+    /// Name of the pack in the `resourcePacks` option.
+    /// * `version_id` - Game version of the instance, versions older than 1.13 use the bare name
+    pub fn option_name(&self, version_id: &str) -> String {
+        match uses_bare_names(version_id) {
+            true => self.name.clone(),
+            false => format!("{}{}", FILE_PREFIX, self.name),
+        }
+    }
+
+    /// Copy a resource pack into the configured instance, after validating it.
+    pub fn install(config: &Config, source: impl AsRef<Path>) -> GrindstoneResult<ResourcePack> {
+        // Validated before copying anything
+        Self::read(&source)?;
+
+        let path = install_pack(source.as_ref(), &config.resource_packs_path())?;
+
+        Self::read(path)
+    }
+
+    /// Names of the enabled packs of the `resourcepacks` folder, from the lowest to the highest priority.
+    pub fn enabled(config: &Config) -> GrindstoneResult<Vec<String>> {
+        let options = GameOptions::load(config.options_path())?;
+        let packs = options.resource_packs();
+
+        if uses_bare_names(&config.version.id) {
+            return Ok(packs);
+        }
+
+        Ok(packs
+            .iter()
+            .filter_map(|p| p.strip_prefix(FILE_PREFIX))
+            .map(String::from)
+            .collect())
+    }
+
+    /// Enable a pack with the highest priority.
+    pub fn enable(config: &Config, name: &str) -> GrindstoneResult<()> {
+        validate_pack_name(name)?;
+
+        let mut enabled = Self::installed_enabled(config)?;
+        enabled.retain(|p| p != name);
+        enabled.push(name.to_string());
+
+        Self::set_enabled(config, &enabled)
+    }
+
+    /// Disable a pack.
+    pub fn disable(config: &Config, name: &str) -> GrindstoneResult<()> {
+        validate_pack_name(name)?;
+
+        let mut enabled = Self::installed_enabled(config)?;
+        enabled.retain(|p| p != name);
+
+        Self::set_enabled(config, &enabled)
+    }
+
+    /// Write the enabled packs into `options.txt`, from the lowest to the highest priority.
+    ///
+    /// Built-in packs (`vanilla`, mod resources...) stay enabled below the given ones.
+    /// * `names` - Names of packs of the `resourcepacks` folder
+    pub fn set_enabled(config: &Config, names: &[String]) -> GrindstoneResult<()> {
+        let packs_path = config.resource_packs_path();
+
+        for name in names {
+            validate_pack_name(name)?;
+        }
+
+        if let Some(missing) = names.iter().find(|n| !packs_path.join(n).exists()) {
+            return Err(GrindstoneError::InvalidPack(
+                missing.clone(),
+                "not installed".to_string(),
+            ));
+        }
+
+        let path = config.options_path();
+        let mut options = GameOptions::load(&path)?;
+
+        // Before 1.13 there are no built-in packs, every name is a file of the folder
+        let packs = match uses_bare_names(&config.version.id) {
+            true => names.to_vec(),
+            false => {
+                let mut packs = options
+                    .resource_packs()
+                    .into_iter()
+                    .filter(|p| !p.starts_with(FILE_PREFIX))
+                    .collect::<Vec<_>>();
+                packs.extend(names.iter().map(|n| format!("{}{}", FILE_PREFIX, n)));
+                packs
+            }
+        };
+
+        options.set_resource_packs(&packs)?;
+        options.save(&path)
+    }
+
+    /// Enabled packs that are still installed, removed ones are dropped like the game does.
+    fn installed_enabled(config: &Config) -> GrindstoneResult<Vec<String>> {
+        let packs_path = config.resource_packs_path();
+        let mut enabled = Self::enabled(config)?;
+        enabled.retain(|p| packs_path.join(p).exists());
+
+        Ok(enabled)
+    }
+}
+
+/// Check if a game version lists the enabled packs by their bare file name.
+///
+/// The `file/` prefix and built-in packs came with 1.13 (snapshot 17w43a).
+/// Unknown version formats are considered recent.
+fn uses_bare_names(version_id: &str) -> bool {
+    // Releases, pre-releases and release candidates: 1.12.2, 1.13-pre1
+    if let Some(rest) = version_id.strip_prefix("1.") {
+        return rest
+            .split(|c: char| !c.is_ascii_digit())
+            .next()
+            .and_then(|minor| minor.parse::<u32>().ok())
+            .is_some_and(|minor| minor < FILE_PREFIX_MINOR);
+    }
+
+    // Snapshots: 17w43a
+    let snapshot = version_id.split_once('w').and_then(|(year, week)| {
+        let week = week.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+        Some((year.parse::<u32>().ok()?, week.parse::<u32>().ok()?))
+    });
+
+    snapshot.is_some_and(|snapshot| snapshot < FILE_PREFIX_SNAPSHOT)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{config::ConfigBuilder, utils::fs::test_folder, version::MinecraftVersionBuilder};
+
+    use super::*;
+
+    fn config(folder: &Path, version_id: &str) -> Config {
+        let mut builder = ConfigBuilder::default()
+            .name("packs")
+            .minecraft_folder_path(folder);
+        builder.version = MinecraftVersionBuilder::default()
+            .version_id(version_id)
+            .build();
+
+        builder.build().unwrap()
+    }
+
+    fn install(config: &Config, name: &str) {
+        let path = config.resource_packs_path().join(name);
+        fs::create_dir_all(&path).unwrap();
+        fs::write(
+            path.join(PACK_MCMETA),
+            r#"{"pack": {"pack_format": 3, "description": "Pack"}}"#,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn bare_name_versions() {
+        for id in ["1.6.4", "1.12.2", "1.12-pre3", "16w50a", "17w31a"] {
+            assert!(uses_bare_names(id), "{}", id);
+        }
+
+        for id in [
+            "1.13",
+            "1.13-pre1",
+            "1.20.1",
+            "17w43a",
+            "23w13a_or_b",
+            "latest",
+        ] {
+            assert!(!uses_bare_names(id), "{}", id);
+        }
+    }
+
+    #[test]
+    fn enable_packs() {
+        let folder = test_folder("resource-packs");
+        let config = config(&folder, "1.20.1");
+        install(&config, "a");
+        install(&config, "b");
+
+        let mut options = GameOptions::default();
+        options
+            .set_resource_packs(&["vanilla".to_string()])
+            .unwrap();
+        options.save(config.options_path()).unwrap();
+
+        ResourcePack::enable(&config, "b").unwrap();
+        ResourcePack::enable(&config, "a").unwrap();
+        ResourcePack::disable(&config, "b").unwrap();
+
+        let options = GameOptions::load(config.options_path()).unwrap();
+        assert_eq!(options.resource_packs(), ["vanilla", "file/a"]);
+        assert_eq!(ResourcePack::enabled(&config).unwrap(), ["a"]);
+
+        let packs = ResourcePack::list_instance(&config).unwrap();
+        assert!(packs[0].enabled);
+        assert!(!packs[1].enabled);
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn enable_packs_before_1_13() {
+        let folder = test_folder("legacy-resource-packs");
+        let config = config(&folder, "1.12.2");
+        install(&config, "a.zip");
+        install(&config, "b");
+
+        ResourcePack::enable(&config, "a.zip").unwrap();
+        ResourcePack::enable(&config, "b").unwrap();
+
+        let options = GameOptions::load(config.options_path()).unwrap();
+        assert_eq!(options.resource_packs(), ["a.zip", "b"]);
+        assert_eq!(ResourcePack::enabled(&config).unwrap(), ["a.zip", "b"]);
+        assert!(ResourcePack::list_instance(&config)
+            .unwrap()
+            .iter()
+            .all(|p| p.enabled));
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn reject_paths_as_names() {
+        let folder = test_folder("resource-pack-names");
+        let config = config(&folder, "1.20.1");
+        install(&config, "a");
+
+        for name in ["..", ".", "", "../a", "a/b", "a\\b"] {
+            assert!(
+                matches!(
+                    ResourcePack::enable(&config, name),
+                    Err(GrindstoneError::InvalidPack(..))
+                ),
+                "{}",
+                name
+            );
+            assert!(ResourcePack::set_enabled(&config, &[name.to_string()]).is_err());
+        }
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
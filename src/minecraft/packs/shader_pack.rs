@@ -0,0 +1,332 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::{debug, trace};
+use zip::ZipArchive;
+
+use crate::{
+    config::Config,
+    errors::{GrindstoneError, GrindstoneResult},
+    utils::fs::write_atomic,
+};
+
+use super::{install_pack, is_pack, list_packs, pack_name, validate_pack_name};
+
+/// Folder holding the shaders inside a shader pack.
+const SHADERS_FOLDER: &str = "shaders";
+/// Settings of Iris, relative to the instance folder.
+const IRIS_PROPERTIES: &str = "config/iris.properties";
+/// Settings of OptiFine shaders, relative to the instance folder.
+const OPTIFINE_PROPERTIES: &str = "optionsshaders.txt";
+/// Value used by OptiFine when shaders are disabled.
+const OPTIFINE_OFF: &str = "OFF";
+
+/// A shader pack of an instance, used by Iris or OptiFine.
+#[derive(Clone, Debug)]
+pub struct ShaderPack {
+    /// Path of the pack.
+    pub path: PathBuf,
+    /// File or folder name of the pack.
+    pub name: String,
+    /// Whether the pack is the selected one.
+    pub enabled: bool,
+}
+
+impl ShaderPack {
+    /// List the shader packs of the configured instance, sorted by name.
+    ///
+    /// Invalid packs are skipped.
+    pub fn list_instance(config: &Config) -> GrindstoneResult<Vec<ShaderPack>> {
+        let enabled = Self::enabled(config)?;
+
+        let packs = list_packs(&config.shader_packs_path())?
+            .into_iter()
+            .filter_map(|path| match Self::read(&path) {
+                Ok(mut pack) => {
+                    pack.enabled = enabled.as_deref() == Some(pack.name.as_str());
+                    Some(pack)
+                }
+                Err(err) => {
+                    debug!("Skipping shader pack {}: {}", path.to_string_lossy(), err);
+                    None
+                }
+            })
+            .collect();
+
+        Ok(packs)
+    }
+
+    /// Read and validate a shader pack, it must contain a `shaders` folder.
+    pub fn read(path: impl AsRef<Path>) -> GrindstoneResult<ShaderPack> {
+        let path = path.as_ref();
+        let name = pack_name(path);
+
+        let valid = if path.is_dir() {
+            path.join(SHADERS_FOLDER).is_dir()
+        } else if is_pack(path) {
+            let zip = ZipArchive::new(fs::File::open(path)?)?;
+            let prefix = format!("{}/", SHADERS_FOLDER);
+            let valid = zip.file_names().any(|n| n.starts_with(&prefix));
+            valid
+        } else {
+            false
+        };
+
+        if !valid {
+            return Err(GrindstoneError::InvalidPack(
+                name,
+                "no shaders folder".to_string(),
+            ));
+        }
+
+        Ok(ShaderPack {
+            path: path.to_path_buf(),
+            name,
+            enabled: false,
+        })
+    }
+
+    /// Copy a shader pack into the configured instance, after validating it.
+    pub fn install(config: &Config, source: impl AsRef<Path>) -> GrindstoneResult<ShaderPack> {
+        // Validated before copying anything
+        Self::read(&source)?;
+
+        let path = install_pack(source.as_ref(), &config.shader_packs_path())?;
+
+        Self::read(path)
+    }
+
+    /// Name of the selected shader pack, `None` if shaders are disabled.
+    pub fn enabled(config: &Config) -> GrindstoneResult<Option<String>> {
+        let instance = config.game_directory();
+
+        let iris = read_properties(&instance.join(IRIS_PROPERTIES))?;
+        if !iris.is_empty() {
+            let enabled = property(&iris, "enableShaders") != Some("false");
+
+            return Ok(property(&iris, "shaderPack")
+                .filter(|p| enabled && !p.is_empty())
+                .map(String::from));
+        }
+
+        let optifine = read_properties(&instance.join(OPTIFINE_PROPERTIES))?;
+        Ok(property(&optifine, "shaderPack")
+            .filter(|p| *p != OPTIFINE_OFF && !p.is_empty())
+            .map(String::from))
+    }
+
+    /// Select a shader pack, for both Iris and OptiFine.
+    pub fn enable(config: &Config, name: &str) -> GrindstoneResult<()> {
+        validate_pack_name(name)?;
+
+        if !config.shader_packs_path().join(name).exists() {
+            return Err(GrindstoneError::InvalidPack(
+                name.to_string(),
+                "not installed".to_string(),
+            ));
+        }
+
+        let instance = config.game_directory();
+        write_properties(
+            &instance.join(IRIS_PROPERTIES),
+            &[("shaderPack", name), ("enableShaders", "true")],
+        )?;
+        write_properties(&instance.join(OPTIFINE_PROPERTIES), &[("shaderPack", name)])
+    }
+
+    /// Disable shaders, for both Iris and OptiFine.
+    pub fn disable(config: &Config) -> GrindstoneResult<()> {
+        let instance = config.game_directory();
+        write_properties(
+            &instance.join(IRIS_PROPERTIES),
+            &[("enableShaders", "false")],
+        )?;
+        write_properties(
+            &instance.join(OPTIFINE_PROPERTIES),
+            &[("shaderPack", OPTIFINE_OFF)],
+        )
+    }
+}
+
+/// Read the lines of a `key=value` properties file, empty if it does not exist.
+fn read_properties(path: &Path) -> GrindstoneResult<Vec<String>> {
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .map(String::from)
+        .collect())
+}
+
+fn property<'a>(lines: &'a [String], key: &str) -> Option<&'a str> {
+    lines
+        .iter()
+        .filter_map(|line| line.split_once('='))
+        .find(|(k, _)| k.trim() == key)
+        .map(|(_, value)| value.trim())
+}
+
+/// Set keys of a properties file, keeping the other lines.
+fn write_properties(path: &Path, values: &[(&str, &str)]) -> GrindstoneResult<()> {
+    let mut lines = read_properties(path)?;
+
+    for (key, value) in values {
+        let line = format!("{}={}", key, value);
+
+        let existing = lines.iter_mut().find(|l| {
+            l.split_once('=')
+                .map(|(k, _)| k.trim() == *key)
+                .unwrap_or_default()
+        });
+
+        match existing {
+            Some(existing) => *existing = line,
+            None => lines.push(line),
+        }
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    trace!("Writing {}", path.to_string_lossy());
+    write_atomic(path, lines.join("\n") + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::{write::FileOptions, ZipWriter};
+
+    use crate::{config::ConfigBuilder, utils::fs::test_folder, version::MinecraftVersionBuilder};
+
+    use super::*;
+
+    fn config(folder: &Path) -> Config {
+        let mut builder = ConfigBuilder::default()
+            .name("shaders")
+            .minecraft_folder_path(folder);
+        builder.version = MinecraftVersionBuilder::default()
+            .version_id("1.20.1")
+            .build();
+
+        builder.build().unwrap()
+    }
+
+    fn zip_pack(path: &Path, entry: &str) {
+        let mut zip = ZipWriter::new(fs::File::create(path).unwrap());
+        zip.start_file(entry, FileOptions::default()).unwrap();
+        zip.write_all(b"void main() {}").unwrap();
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn read_packs() {
+        let folder = test_folder("shader-read");
+
+        let dir = folder.join("Folder");
+        fs::create_dir_all(dir.join(SHADERS_FOLDER)).unwrap();
+        assert_eq!(ShaderPack::read(&dir).unwrap().name, "Folder");
+
+        let zip = folder.join("Zip.zip");
+        zip_pack(&zip, "shaders/final.fsh");
+        assert_eq!(ShaderPack::read(&zip).unwrap().name, "Zip.zip");
+
+        let invalid = folder.join("Invalid.zip");
+        zip_pack(&invalid, "final.fsh");
+        assert!(matches!(
+            ShaderPack::read(&invalid),
+            Err(GrindstoneError::InvalidPack(..))
+        ));
+        assert!(ShaderPack::read(folder.join("Missing")).is_err());
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn enable_and_disable() {
+        let folder = test_folder("shader-enable");
+        let config = config(&folder);
+        let instance = config.game_directory();
+
+        let source = folder.join("Pack.zip");
+        zip_pack(&source, "shaders/final.fsh");
+        ShaderPack::install(&config, &source).unwrap();
+        assert!(ShaderPack::install(&config, &source).is_err());
+        assert_eq!(fs::read_dir(config.shader_packs_path()).unwrap().count(), 1);
+
+        fs::create_dir_all(instance.join("config")).unwrap();
+        fs::write(
+            instance.join(IRIS_PROPERTIES),
+            "maxShadowRenderDistance=32\n",
+        )
+        .unwrap();
+
+        ShaderPack::enable(&config, "Pack.zip").unwrap();
+        let iris = fs::read_to_string(instance.join(IRIS_PROPERTIES)).unwrap();
+        assert_eq!(
+            iris,
+            "maxShadowRenderDistance=32\nshaderPack=Pack.zip\nenableShaders=true\n"
+        );
+        let optifine = fs::read_to_string(instance.join(OPTIFINE_PROPERTIES)).unwrap();
+        assert_eq!(optifine, "shaderPack=Pack.zip\n");
+        assert_eq!(
+            ShaderPack::enabled(&config).unwrap().as_deref(),
+            Some("Pack.zip")
+        );
+
+        let packs = ShaderPack::list_instance(&config).unwrap();
+        assert!(packs[0].enabled);
+
+        ShaderPack::disable(&config).unwrap();
+        let iris = fs::read_to_string(instance.join(IRIS_PROPERTIES)).unwrap();
+        assert!(iris.contains("enableShaders=false"));
+        let optifine = fs::read_to_string(instance.join(OPTIFINE_PROPERTIES)).unwrap();
+        assert_eq!(optifine, "shaderPack=OFF\n");
+        assert_eq!(ShaderPack::enabled(&config).unwrap(), None);
+
+        assert!(ShaderPack::enable(&config, "Missing.zip").is_err());
+        assert!(ShaderPack::enable(&config, "../Pack.zip").is_err());
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn iris_settings_first() {
+        let folder = test_folder("shader-iris");
+        let config = config(&folder);
+        let instance = config.game_directory();
+        fs::create_dir_all(instance.join("config")).unwrap();
+
+        // Only OptiFine settings
+        fs::write(
+            instance.join(OPTIFINE_PROPERTIES),
+            "shaderPack=OptiFine.zip\n",
+        )
+        .unwrap();
+        assert_eq!(
+            ShaderPack::enabled(&config).unwrap().as_deref(),
+            Some("OptiFine.zip")
+        );
+
+        // Iris settings win, even when they disable shaders
+        fs::write(instance.join(IRIS_PROPERTIES), "shaderPack=Iris.zip\n").unwrap();
+        assert_eq!(
+            ShaderPack::enabled(&config).unwrap().as_deref(),
+            Some("Iris.zip")
+        );
+        fs::write(
+            instance.join(IRIS_PROPERTIES),
+            "shaderPack=Iris.zip\nenableShaders=false\n",
+        )
+        .unwrap();
+        assert_eq!(ShaderPack::enabled(&config).unwrap(), None);
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
@@ -0,0 +1,125 @@
+use std::{fmt, str::FromStr};
+
+use crate::{
+    constants,
+    errors::{GrindstoneError, GrindstoneResult},
+};
+
+/// Address of a Minecraft server.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ServerAddress {
+    /// Host name or IP address of the server.
+    pub host: String,
+    /// Port of the server.
+    pub port: u16,
+}
+
+impl ServerAddress {
+    /// Create a new address from a host and a port.
+    pub fn new<S: Into<String>>(host: S, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+        }
+    }
+
+    /// Parse an address in the format used by the multiplayer menu.
+    ///
+    /// Accepted formats are `host`, `host:port`, `[ipv6]` and `[ipv6]:port`.
+    /// The default Minecraft port is used when none is provided.
+    pub fn parse(address: &str) -> GrindstoneResult<Self> {
+        let invalid = || GrindstoneError::InvalidServerAddress(address.to_string());
+        let address = address.trim();
+
+        if address.is_empty() {
+            return Err(invalid());
+        }
+
+        // Bracketed IPv6 address
+        if let Some(rest) = address.strip_prefix('[') {
+            let (host, rest) = rest.split_once(']').ok_or_else(invalid)?;
+            let port = match rest.strip_prefix(':') {
+                Some(port) => port.parse().map_err(|_| invalid())?,
+                None if rest.is_empty() => constants::MC_DEFAULT_SERVER_PORT,
+                None => return Err(invalid()),
+            };
+
+            return Ok(Self::new(host, port));
+        }
+
+        match address.split_once(':') {
+            // Bare IPv6 address without port
+            Some((_, rest)) if rest.contains(':') => {
+                Ok(Self::new(address, constants::MC_DEFAULT_SERVER_PORT))
+            }
+            Some((host, port)) if !host.is_empty() => {
+                Ok(Self::new(host, port.parse().map_err(|_| invalid())?))
+            }
+            Some(_) => Err(invalid()),
+            None => Ok(Self::new(address, constants::MC_DEFAULT_SERVER_PORT)),
+        }
+    }
+}
+
+impl FromStr for ServerAddress {
+    type Err = GrindstoneError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for ServerAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_addresses() {
+        let default = constants::MC_DEFAULT_SERVER_PORT;
+
+        assert_eq!(
+            ServerAddress::parse("example.com").unwrap(),
+            ServerAddress::new("example.com", default)
+        );
+        assert_eq!(
+            ServerAddress::parse(" example.com:25566 ").unwrap(),
+            ServerAddress::new("example.com", 25566)
+        );
+        assert_eq!(
+            ServerAddress::parse("[::1]:25566").unwrap(),
+            ServerAddress::new("::1", 25566)
+        );
+        assert_eq!(
+            ServerAddress::parse("[::1]").unwrap(),
+            ServerAddress::new("::1", default)
+        );
+        assert_eq!(
+            ServerAddress::parse("fe80::1").unwrap(),
+            ServerAddress::new("fe80::1", default)
+        );
+    }
+
+    #[test]
+    fn reject_invalid_addresses() {
+        for address in ["", ":25565", "host:port", "host:70000", "[::1", "[::1]x"] {
+            assert!(ServerAddress::parse(address).is_err(), "{}", address);
+        }
+    }
+
+    #[test]
+    fn display_round_trip() {
+        for address in ["example.com:25565", "[::1]:25566"] {
+            assert_eq!(ServerAddress::parse(address).unwrap().to_string(), address);
+        }
+    }
+}
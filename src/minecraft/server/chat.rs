@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+
+/// Character used by Minecraft to prefix legacy formatting codes.
+const FORMATTING_PREFIX: char = '§';
+
+/// A chat component, as used by servers for their description (MOTD).
+///
+/// Components can be sent as a plain string, a list of components or an object.
+/// All those forms are normalized into this structure.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(from = "RawChatComponent")]
+pub struct ChatComponent {
+    /// Text of the component.
+    /// Can contain legacy formatting codes.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub text: String,
+    /// Translation key, used instead of the text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub translate: Option<String>,
+    /// Arguments of the translation.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub with: Vec<ChatComponent>,
+    /// Color name or hex code (`#RRGGBB`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Bold style.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bold: Option<bool>,
+    /// Italic style.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub italic: Option<bool>,
+    /// Underlined style.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub underlined: Option<bool>,
+    /// Strikethrough style.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strikethrough: Option<bool>,
+    /// Obfuscated style.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub obfuscated: Option<bool>,
+    /// Child components, rendered after this one.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub extra: Vec<ChatComponent>,
+}
+
+impl ChatComponent {
+    /// Create a component holding only text.
+    pub fn text<S: Into<String>>(text: S) -> Self {
+        Self {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Render the component and its children as plain text.
+    ///
+    /// Legacy formatting codes are removed.
+    pub fn to_plain_text(&self) -> String {
+        let mut text = String::new();
+        self.push_plain_text(&mut text);
+
+        strip_formatting_codes(&text)
+    }
+
+    fn push_plain_text(&self, out: &mut String) {
+        match &self.translate {
+            Some(key) if self.text.is_empty() => {
+                out.push_str(key);
+
+                for arg in &self.with {
+                    out.push(' ');
+                    arg.push_plain_text(out);
+                }
+            }
+            _ => out.push_str(&self.text),
+        }
+
+        for child in &self.extra {
+            child.push_plain_text(out);
+        }
+    }
+}
+
+/// Remove legacy formatting codes (`§` followed by a code) from a text.
+pub fn strip_formatting_codes(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c == FORMATTING_PREFIX {
+            chars.next();
+        } else {
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+/// Chat component as it can be found in JSON.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawChatComponent {
+    Text(String),
+    List(Vec<ChatComponent>),
+    Object(ChatObject),
+    Other(serde_json::Value),
+}
+
+#[derive(Deserialize)]
+struct ChatObject {
+    #[serde(default)]
+    text: serde_json::Value,
+    translate: Option<String>,
+    #[serde(default)]
+    with: Vec<ChatComponent>,
+    color: Option<String>,
+    bold: Option<bool>,
+    italic: Option<bool>,
+    underlined: Option<bool>,
+    strikethrough: Option<bool>,
+    obfuscated: Option<bool>,
+    #[serde(default)]
+    extra: Vec<ChatComponent>,
+}
+
+impl From<RawChatComponent> for ChatComponent {
+    fn from(raw: RawChatComponent) -> Self {
+        match raw {
+            RawChatComponent::Text(text) => ChatComponent::text(text),
+            RawChatComponent::List(extra) => ChatComponent {
+                extra,
+                ..Default::default()
+            },
+            RawChatComponent::Object(object) => ChatComponent {
+                text: value_to_text(object.text),
+                translate: object.translate,
+                with: object.with,
+                color: object.color,
+                bold: object.bold,
+                italic: object.italic,
+                underlined: object.underlined,
+                strikethrough: object.strikethrough,
+                obfuscated: object.obfuscated,
+                extra: object.extra,
+            },
+            RawChatComponent::Other(value) => ChatComponent::text(value_to_text(value)),
+        }
+    }
+}
+
+fn value_to_text(value: serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => String::new(),
+        serde_json::Value::String(text) => text,
+        other => other.to_string(),
+    }
+}
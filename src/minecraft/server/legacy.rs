@@ -0,0 +1,190 @@
+use std::time::Instant;
+
+use log::trace;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    time::timeout,
+};
+
+use crate::errors::{GrindstoneError, GrindstoneResult};
+
+use super::{
+    chat::ChatComponent,
+    ping::ServerPinger,
+    status::{ServerPlayers, ServerStatus, ServerVersion},
+};
+
+/// Server list ping packet ID.
+const LEGACY_PING_PACKET_ID: u8 = 0xFE;
+/// Payload of the server list ping packet.
+const LEGACY_PING_PAYLOAD: u8 = 0x01;
+/// Plugin message packet ID.
+const LEGACY_PLUGIN_MESSAGE_ID: u8 = 0xFA;
+/// Channel of the plugin message sent with the ping.
+const LEGACY_PING_CHANNEL: &str = "MC|PingHost";
+/// Protocol version of Minecraft 1.6.4.
+const LEGACY_PROTOCOL_VERSION: u8 = 74;
+/// Kick packet ID, used by the server to send its status.
+const LEGACY_KICK_PACKET_ID: u8 = 0xFF;
+/// Prefix of a 1.4+ status response.
+const LEGACY_RESPONSE_PREFIX: &str = "§1\0";
+
+impl ServerPinger {
+    /// Get the status of the server using the legacy 1.6 ping.
+    ///
+    /// Understood by servers older than 1.7, and by most recent ones.
+    pub async fn ping_legacy(&self) -> GrindstoneResult<ServerStatus> {
+        timeout(self.timeout, self.legacy_exchange())
+            .await
+            .map_err(|_| GrindstoneError::Timeout)?
+    }
+
+    async fn legacy_exchange(&self) -> GrindstoneResult<ServerStatus> {
+        trace!("Connecting to {} for legacy ping", self.address);
+        let mut stream =
+            TcpStream::connect((self.address.host.as_str(), self.address.port)).await?;
+
+        let mut data = vec![LEGACY_PROTOCOL_VERSION];
+        write_legacy_string(&mut data, &self.address.host);
+        data.extend_from_slice(&(self.address.port as i32).to_be_bytes());
+
+        let mut request = vec![
+            LEGACY_PING_PACKET_ID,
+            LEGACY_PING_PAYLOAD,
+            LEGACY_PLUGIN_MESSAGE_ID,
+        ];
+        write_legacy_string(&mut request, LEGACY_PING_CHANNEL);
+        request.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        request.extend_from_slice(&data);
+
+        let start = Instant::now();
+        stream.write_all(&request).await?;
+        stream.flush().await?;
+
+        let id = stream.read_u8().await?;
+        if id != LEGACY_KICK_PACKET_ID {
+            return Err(GrindstoneError::InvalidServerResponse(format!(
+                "Unexpected packet {:#04x} instead of legacy status",
+                id
+            )));
+        }
+
+        let len = stream.read_u16().await? as usize;
+        let mut raw = vec![0; len * 2];
+        stream.read_exact(&mut raw).await?;
+        let latency = start.elapsed();
+
+        let units = raw
+            .chunks_exact(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]))
+            .collect::<Vec<_>>();
+        let response = String::from_utf16(&units).map_err(|_| {
+            GrindstoneError::InvalidServerResponse("Legacy status is not valid UTF-16".to_string())
+        })?;
+
+        let mut status = parse_legacy_response(&response)?;
+        status.latency = Some(latency);
+
+        Ok(status)
+    }
+}
+
+/// Append a string in the legacy format (length in chars + UTF-16BE) to the buffer.
+fn write_legacy_string(buf: &mut Vec<u8>, value: &str) {
+    let units = value.encode_utf16().collect::<Vec<_>>();
+    buf.extend_from_slice(&(units.len() as u16).to_be_bytes());
+
+    for unit in units {
+        buf.extend_from_slice(&unit.to_be_bytes());
+    }
+}
+
+/// Parse the status sent in the kick packet.
+///
+/// Servers from 1.4 send `§1\0<protocol>\0<version>\0<motd>\0<online>\0<max>`,
+/// older ones send `<motd>§<online>§<max>`.
+fn parse_legacy_response(response: &str) -> GrindstoneResult<ServerStatus> {
+    let invalid = || GrindstoneError::InvalidServerResponse("Malformed legacy status".to_string());
+    let parse_number = |value: &str| value.parse::<i32>().map_err(|_| invalid());
+
+    let (version, motd, online, max) = match response.strip_prefix(LEGACY_RESPONSE_PREFIX) {
+        Some(rest) => {
+            let fields = rest.split('\0').collect::<Vec<_>>();
+            if fields.len() != 5 {
+                return Err(invalid());
+            }
+
+            let version = ServerVersion {
+                name: fields[1].to_string(),
+                protocol: parse_number(fields[0])?,
+            };
+
+            (version, fields[2], fields[3], fields[4])
+        }
+        None => {
+            let mut fields = response.rsplitn(3, '§');
+            let max = fields.next().ok_or_else(invalid)?;
+            let online = fields.next().ok_or_else(invalid)?;
+            let motd = fields.next().ok_or_else(invalid)?;
+
+            (ServerVersion::default(), motd, online, max)
+        }
+    };
+
+    Ok(ServerStatus {
+        version,
+        players: ServerPlayers {
+            max: parse_number(max)?,
+            online: parse_number(online)?,
+            sample: Vec::new(),
+        },
+        description: ChatComponent::text(motd),
+        favicon: None,
+        enforces_secure_chat: false,
+        latency: None,
+        legacy: true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_modern_legacy_response() {
+        let status =
+            parse_legacy_response("§1\u{0}74\u{0}1.6.4\u{0}A §aserver\u{0}3\u{0}20").unwrap();
+
+        assert_eq!(status.version.protocol, 74);
+        assert_eq!(status.version.name, "1.6.4");
+        assert_eq!(status.description.to_plain_text(), "A server");
+        assert_eq!(status.players.online, 3);
+        assert_eq!(status.players.max, 20);
+        assert!(status.legacy);
+    }
+
+    #[test]
+    fn parse_beta_response() {
+        let status = parse_legacy_response("A§server§5§10").unwrap();
+
+        assert_eq!(status.description.text, "A§server");
+        assert_eq!(status.players.online, 5);
+        assert_eq!(status.players.max, 10);
+    }
+
+    #[test]
+    fn reject_malformed_response() {
+        assert!(parse_legacy_response("§1\u{0}74\u{0}1.6.4\u{0}motd").is_err());
+        assert!(parse_legacy_response("motd§5").is_err());
+        assert!(parse_legacy_response("motd§five§10").is_err());
+    }
+
+    #[test]
+    fn legacy_string_encoding() {
+        let mut buf = Vec::new();
+        write_legacy_string(&mut buf, "MC");
+
+        assert_eq!(buf, [0, 2, 0, b'M', 0, b'C']);
+    }
+}
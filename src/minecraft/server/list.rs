@@ -0,0 +1,262 @@
+use std::path::{Path, PathBuf};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use log::trace;
+
+use crate::{
+    config::Config,
+    errors::GrindstoneResult,
+    nbt::{Compound, Nbt, Tag},
+};
+
+/// Name of the list holding the servers in `servers.dat`.
+const SERVERS_TAG: &str = "servers";
+
+/// A server of the multiplayer menu.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ServerEntry {
+    /// Name displayed in the menu.
+    pub name: String,
+    /// Address of the server.
+    pub ip: String,
+    /// PNG bytes of the server icon.
+    pub icon: Option<Vec<u8>>,
+    /// Whether server resource packs are accepted.
+    /// `None` means the player is prompted.
+    pub accept_textures: Option<bool>,
+    /// Other tags of the entry, kept as is.
+    extra: Compound,
+}
+
+impl ServerEntry {
+    pub fn new<N: Into<String>, I: Into<String>>(name: N, ip: I) -> Self {
+        Self {
+            name: name.into(),
+            ip: ip.into(),
+            ..Default::default()
+        }
+    }
+
+    fn from_compound(mut compound: Compound) -> Self {
+        let mut take_str = |name: &str| match compound.remove(name) {
+            Some(Tag::String(value)) => value,
+            _ => String::new(),
+        };
+        let name = take_str("name");
+        let ip = take_str("ip");
+
+        // An icon that cannot be decoded stays in the extra tags,
+        // so it is written back untouched.
+        let icon = compound
+            .get_str("icon")
+            .and_then(|icon| STANDARD.decode(icon).ok());
+        if icon.is_some() {
+            compound.remove("icon");
+        }
+
+        let accept_textures = compound.get_bool("acceptTextures");
+        compound.remove("acceptTextures");
+
+        Self {
+            name,
+            ip,
+            icon,
+            accept_textures,
+            extra: compound,
+        }
+    }
+
+    fn to_compound(&self) -> Compound {
+        let mut compound = self.extra.clone();
+
+        compound.insert("name", Tag::String(self.name.clone()));
+        compound.insert("ip", Tag::String(self.ip.clone()));
+
+        if let Some(icon) = &self.icon {
+            compound.insert("icon", Tag::String(STANDARD.encode(icon)));
+        }
+
+        if let Some(accept) = self.accept_textures {
+            compound.insert("acceptTextures", Tag::Byte(accept as i8));
+        }
+
+        compound
+    }
+}
+
+/// The multiplayer server list of an instance, stored in `servers.dat`.
+#[derive(Clone, Debug)]
+pub struct ServerList {
+    path: PathBuf,
+    entries: Vec<ServerEntry>,
+    /// Other tags of the file, kept as is.
+    extra: Compound,
+}
+
+impl ServerList {
+    /// Load the server list of the configured instance.
+    pub fn load_instance(config: &Config) -> GrindstoneResult<Self> {
+        Self::load(config.servers_dat_path())
+    }
+
+    /// Load a server list file.
+    /// A missing file is treated as an empty list.
+    pub fn load(path: impl AsRef<Path>) -> GrindstoneResult<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        if !path.exists() {
+            trace!("No server list found, starting with an empty one");
+            return Ok(Self {
+                path,
+                entries: Vec::new(),
+                extra: Compound::new(),
+            });
+        }
+
+        let mut root = Nbt::read_file(&path)?.root;
+
+        let entries = match root.remove(SERVERS_TAG) {
+            Some(Tag::List(servers)) => servers
+                .into_iter()
+                .filter_map(|tag| match tag {
+                    Tag::Compound(compound) => Some(ServerEntry::from_compound(compound)),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        Ok(Self {
+            path,
+            entries,
+            extra: root,
+        })
+    }
+
+    /// Write the server list back to its file.
+    pub fn save(&self) -> GrindstoneResult<()> {
+        if let Some(parent) = self.path.parent() {
+            trace!("Creating parent folder for server list");
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut root = self.extra.clone();
+        root.insert(
+            SERVERS_TAG,
+            Tag::List(
+                self.entries
+                    .iter()
+                    .map(|e| Tag::Compound(e.to_compound()))
+                    .collect(),
+            ),
+        );
+
+        Nbt::new(root).write_file(&self.path)
+    }
+
+    /// Path of the server list file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Servers, in the order of the multiplayer menu.
+    pub fn entries(&self) -> &[ServerEntry] {
+        &self.entries
+    }
+
+    /// Find a server by its address.
+    pub fn get(&self, ip: &str) -> Option<&ServerEntry> {
+        self.position(ip).map(|i| &self.entries[i])
+    }
+
+    /// Find a mutable server by its address.
+    pub fn get_mut(&mut self, ip: &str) -> Option<&mut ServerEntry> {
+        self.position(ip).map(|i| &mut self.entries[i])
+    }
+
+    /// Index of a server in the list.
+    pub fn position(&self, ip: &str) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|e| e.ip.eq_ignore_ascii_case(ip))
+    }
+
+    /// Add a server at the end of the list.
+    ///
+    /// Nothing is changed if a server with the same address already exists, so
+    /// entries edited by the player are kept. Returns `true` if the server was added.
+    pub fn add(&mut self, entry: ServerEntry) -> bool {
+        if self.position(&entry.ip).is_some() {
+            return false;
+        }
+
+        self.entries.push(entry);
+        true
+    }
+
+    /// Insert a server at the given index, or move it there if it already exists.
+    /// An existing entry is kept as is.
+    pub fn insert(&mut self, index: usize, entry: ServerEntry) {
+        let entry = match self.position(&entry.ip) {
+            Some(i) => self.entries.remove(i),
+            None => entry,
+        };
+
+        let index = index.min(self.entries.len());
+        self.entries.insert(index, entry);
+    }
+
+    /// Remove a server by its address.
+    pub fn remove(&mut self, ip: &str) -> Option<ServerEntry> {
+        self.position(ip).map(|i| self.entries.remove(i))
+    }
+
+    /// Move a server to another index.
+    /// Returns `false` if the server is not in the list.
+    pub fn move_to(&mut self, ip: &str, index: usize) -> bool {
+        match self.position(ip) {
+            Some(i) => {
+                let entry = self.entries.remove(i);
+                let index = index.min(self.entries.len());
+                self.entries.insert(index, entry);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_round_trip_keeps_unknown_tags() {
+        let mut compound = Compound::new();
+        compound.insert("name", Tag::String("Server".to_string()));
+        compound.insert("ip", Tag::String("example.com".to_string()));
+        compound.insert("icon", Tag::String(STANDARD.encode(b"\x89PNG")));
+        compound.insert("acceptTextures", Tag::Byte(1));
+        compound.insert("hidden", Tag::Byte(0));
+
+        let entry = ServerEntry::from_compound(compound.clone());
+        assert_eq!(entry.name, "Server");
+        assert_eq!(entry.icon.as_deref(), Some(&b"\x89PNG"[..]));
+        assert_eq!(entry.accept_textures, Some(true));
+
+        let written = entry.to_compound();
+        assert_eq!(written.get_bool("hidden"), Some(false));
+        assert_eq!(written.get_str("icon"), compound.get_str("icon"));
+        assert_eq!(written.get_bool("acceptTextures"), Some(true));
+    }
+
+    #[test]
+    fn invalid_icon_is_kept_as_is() {
+        let mut compound = Compound::new();
+        compound.insert("icon", Tag::String("%%%".to_string()));
+
+        let entry = ServerEntry::from_compound(compound);
+        assert!(entry.icon.is_none());
+        assert_eq!(entry.to_compound().get_str("icon"), Some("%%%"));
+    }
+}
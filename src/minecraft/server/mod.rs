@@ -0,0 +1,13 @@
+mod address;
+mod chat;
+mod legacy;
+mod list;
+mod ping;
+mod protocol;
+mod status;
+
+pub use address::ServerAddress;
+pub use chat::ChatComponent;
+pub use list::{ServerEntry, ServerList};
+pub use ping::ServerPinger;
+pub use status::{PlayerSample, ServerPlayers, ServerStatus, ServerVersion};
@@ -0,0 +1,252 @@
+use std::{
+    io::ErrorKind,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use log::{debug, trace};
+use tokio::{net::TcpStream, time::timeout};
+
+use crate::errors::{GrindstoneError, GrindstoneResult};
+
+use super::{
+    address::ServerAddress,
+    protocol::{decode_string, read_packet, write_packet, write_string, write_varint},
+    status::ServerStatus,
+};
+
+/// ID of the handshake packet.
+const HANDSHAKE_PACKET_ID: i32 = 0x00;
+/// ID of the status request and response packets.
+const STATUS_PACKET_ID: i32 = 0x00;
+/// ID of the ping and pong packets.
+const PING_PACKET_ID: i32 = 0x01;
+/// State requested in the handshake to get the server status.
+const NEXT_STATE_STATUS: i32 = 1;
+/// Protocol version sent when none is configured.
+/// Servers answer with their own version whatever the value is.
+const DEFAULT_PROTOCOL_VERSION: i32 = -1;
+/// Default time allowed for a ping.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Client for the Server List Ping protocol.
+#[derive(Clone, Debug)]
+pub struct ServerPinger {
+    pub(super) address: ServerAddress,
+    pub(super) timeout: Duration,
+    protocol_version: i32,
+}
+
+impl ServerPinger {
+    /// Create a pinger for the given server address.
+    ///
+    /// * `address` - Address in the `host[:port]` format.
+    pub fn new(address: &str) -> GrindstoneResult<Self> {
+        Ok(Self::from_address(ServerAddress::parse(address)?))
+    }
+
+    /// Create a pinger for an already parsed server address.
+    pub fn from_address(address: ServerAddress) -> Self {
+        Self {
+            address,
+            timeout: DEFAULT_TIMEOUT,
+            protocol_version: DEFAULT_PROTOCOL_VERSION,
+        }
+    }
+
+    /// Set the time allowed for a whole ping exchange.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the protocol version sent in the handshake.
+    pub fn protocol_version(mut self, protocol_version: i32) -> Self {
+        self.protocol_version = protocol_version;
+        self
+    }
+
+    /// Get the status of the server.
+    ///
+    /// Uses the current protocol, and falls back to the legacy 1.6 ping
+    /// when the server does not understand it.
+    /// An unreachable server or a timeout fails right away.
+    pub async fn ping(&self) -> GrindstoneResult<ServerStatus> {
+        match self.ping_modern().await {
+            Ok(status) => Ok(status),
+            Err(err) if !is_protocol_error(&err) => Err(err),
+            Err(err) => {
+                debug!(
+                    "Ping of {} failed ({}), trying legacy ping",
+                    self.address, err
+                );
+                self.ping_legacy().await.map_err(|_| err)
+            }
+        }
+    }
+
+    /// Get the status of the server using the current (1.7+) protocol.
+    pub async fn ping_modern(&self) -> GrindstoneResult<ServerStatus> {
+        timeout(self.timeout, self.status_exchange())
+            .await
+            .map_err(|_| GrindstoneError::Timeout)?
+    }
+
+    async fn status_exchange(&self) -> GrindstoneResult<ServerStatus> {
+        trace!("Connecting to {}", self.address);
+        let mut stream =
+            TcpStream::connect((self.address.host.as_str(), self.address.port)).await?;
+
+        trace!("Sending handshake");
+        let mut handshake = Vec::new();
+        write_varint(&mut handshake, self.protocol_version);
+        write_string(&mut handshake, &self.address.host);
+        handshake.extend_from_slice(&self.address.port.to_be_bytes());
+        write_varint(&mut handshake, NEXT_STATE_STATUS);
+        write_packet(&mut stream, HANDSHAKE_PACKET_ID, &handshake).await?;
+
+        trace!("Requesting status");
+        write_packet(&mut stream, STATUS_PACKET_ID, &[]).await?;
+
+        let (id, payload) = read_packet(&mut stream).await?;
+        if id != STATUS_PACKET_ID {
+            return Err(GrindstoneError::InvalidServerResponse(format!(
+                "Unexpected packet {:#04x} instead of status response",
+                id
+            )));
+        }
+
+        let (json, _) = decode_string(&payload)?;
+        let mut status = ServerStatus::from_json(&json)?;
+
+        // Some servers close the connection right after the status, the
+        // latency is then left unknown instead of failing the whole ping.
+        match ping_exchange(&mut stream).await {
+            Ok(latency) => status.latency = Some(latency),
+            Err(err) => debug!("Server {} did not answer ping: {}", self.address, err),
+        }
+
+        Ok(status)
+    }
+}
+
+/// Check if a failed ping comes from a server not understanding the protocol,
+/// rather than from an unreachable one.
+fn is_protocol_error(err: &GrindstoneError) -> bool {
+    match err {
+        GrindstoneError::InvalidServerResponse(_) | GrindstoneError::Serde(_) => true,
+        // Old servers close the connection on packets they do not know
+        GrindstoneError::IO(err) => matches!(
+            err.kind(),
+            ErrorKind::UnexpectedEof
+                | ErrorKind::ConnectionReset
+                | ErrorKind::BrokenPipe
+                | ErrorKind::InvalidData
+        ),
+        _ => false,
+    }
+}
+
+/// Send a ping packet and wait for the pong to measure the latency.
+async fn ping_exchange(stream: &mut TcpStream) -> GrindstoneResult<Duration> {
+    let payload = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+        .to_be_bytes();
+
+    let start = Instant::now();
+    write_packet(stream, PING_PACKET_ID, &payload).await?;
+    let (id, pong) = read_packet(stream).await?;
+    let latency = start.elapsed();
+
+    if id != PING_PACKET_ID || pong != payload {
+        return Err(GrindstoneError::InvalidServerResponse(
+            "Pong does not match ping".to_string(),
+        ));
+    }
+
+    Ok(latency)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    /// Start a server calling `answer` on every connection, returning its address.
+    async fn server(answer: fn(TcpStream) -> tokio::task::JoinHandle<()>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                answer(socket);
+            }
+        });
+
+        address
+    }
+
+    #[test]
+    fn protocol_errors() {
+        assert!(is_protocol_error(&GrindstoneError::InvalidServerResponse(
+            String::new()
+        )));
+        assert!(is_protocol_error(&GrindstoneError::IO(
+            ErrorKind::UnexpectedEof.into()
+        )));
+        assert!(!is_protocol_error(&GrindstoneError::IO(
+            ErrorKind::ConnectionRefused.into()
+        )));
+        assert!(!is_protocol_error(&GrindstoneError::Timeout));
+    }
+
+    #[tokio::test]
+    async fn legacy_fallback() {
+        // A beta server, answering any packet with its legacy status
+        let address = server(|mut socket| {
+            tokio::spawn(async move {
+                let mut buf = [0; 256];
+                let _ = socket.read(&mut buf).await;
+
+                let mut kick = vec![0xFF, 0, 6];
+                for unit in "A§5§10".encode_utf16() {
+                    kick.extend_from_slice(&unit.to_be_bytes());
+                }
+                let _ = socket.write_all(&kick).await;
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            })
+        })
+        .await;
+
+        let status = ServerPinger::new(&address).unwrap().ping().await.unwrap();
+        assert!(status.legacy);
+        assert_eq!(status.players.max, 10);
+    }
+
+    #[tokio::test]
+    async fn no_fallback_after_timeout() {
+        // A server that never answers
+        let address = server(|mut socket| {
+            tokio::spawn(async move {
+                let mut buf = [0; 256];
+                while socket.read(&mut buf).await.is_ok_and(|n| n > 0) {}
+            })
+        })
+        .await;
+
+        let start = Instant::now();
+        let res = ServerPinger::new(&address)
+            .unwrap()
+            .timeout(Duration::from_millis(200))
+            .ping()
+            .await;
+
+        assert!(matches!(res, Err(GrindstoneError::Timeout)));
+        assert!(start.elapsed() < Duration::from_millis(380));
+    }
+}
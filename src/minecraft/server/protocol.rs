@@ -0,0 +1,199 @@
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::errors::{GrindstoneError, GrindstoneResult};
+
+/// Maximum number of bytes a VarInt can be encoded on.
+const VARINT_MAX_BYTES: usize = 5;
+
+/// Maximum accepted size of a packet.
+/// Status responses contain the favicon, so they can be quite large.
+const MAX_PACKET_SIZE: usize = 2 * 1024 * 1024;
+
+/// Append a VarInt to the buffer.
+pub fn write_varint(buf: &mut Vec<u8>, value: i32) {
+    let mut value = value as u32;
+
+    loop {
+        if value & !0x7F == 0 {
+            buf.push(value as u8);
+            return;
+        }
+
+        buf.push((value & 0x7F | 0x80) as u8);
+        value >>= 7;
+    }
+}
+
+/// Append a string prefixed with its length to the buffer.
+pub fn write_string(buf: &mut Vec<u8>, value: &str) {
+    write_varint(buf, value.len() as i32);
+    buf.extend_from_slice(value.as_bytes());
+}
+
+/// Read a VarInt from a stream.
+pub async fn read_varint<R: AsyncRead + Unpin>(reader: &mut R) -> GrindstoneResult<i32> {
+    let mut value = 0u32;
+
+    for i in 0..VARINT_MAX_BYTES {
+        let byte = reader.read_u8().await?;
+        value |= ((byte & 0x7F) as u32) << (7 * i);
+
+        if byte & 0x80 == 0 {
+            return Ok(value as i32);
+        }
+    }
+
+    Err(GrindstoneError::InvalidServerResponse(
+        "VarInt is too big".to_string(),
+    ))
+}
+
+/// Decode a VarInt from a buffer.
+/// Returns the value and the remaining bytes.
+pub fn decode_varint(buf: &[u8]) -> GrindstoneResult<(i32, &[u8])> {
+    let mut value = 0u32;
+
+    for (i, byte) in buf.iter().take(VARINT_MAX_BYTES).enumerate() {
+        value |= ((byte & 0x7F) as u32) << (7 * i);
+
+        if byte & 0x80 == 0 {
+            return Ok((value as i32, &buf[i + 1..]));
+        }
+    }
+
+    Err(GrindstoneError::InvalidServerResponse(
+        "VarInt is truncated or too big".to_string(),
+    ))
+}
+
+/// Decode a string prefixed with its length from a buffer.
+/// Returns the string and the remaining bytes.
+pub fn decode_string(buf: &[u8]) -> GrindstoneResult<(String, &[u8])> {
+    let (len, rest) = decode_varint(buf)?;
+    let len = usize::try_from(len)
+        .ok()
+        .filter(|len| *len <= rest.len())
+        .ok_or_else(|| {
+            GrindstoneError::InvalidServerResponse("String length is invalid".to_string())
+        })?;
+
+    let value = String::from_utf8(rest[..len].to_vec()).map_err(|_| {
+        GrindstoneError::InvalidServerResponse("String is not valid UTF-8".to_string())
+    })?;
+
+    Ok((value, &rest[len..]))
+}
+
+/// Write a packet, framed with its length.
+pub async fn write_packet<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    id: i32,
+    payload: &[u8],
+) -> GrindstoneResult<()> {
+    let mut body = Vec::with_capacity(payload.len() + VARINT_MAX_BYTES);
+    write_varint(&mut body, id);
+    body.extend_from_slice(payload);
+
+    let mut packet = Vec::with_capacity(body.len() + VARINT_MAX_BYTES);
+    write_varint(&mut packet, body.len() as i32);
+    packet.extend_from_slice(&body);
+
+    writer.write_all(&packet).await?;
+    writer.flush().await?;
+
+    Ok(())
+}
+
+/// Read a packet framed with its length.
+/// Returns the packet ID and its payload.
+pub async fn read_packet<R: AsyncRead + Unpin>(reader: &mut R) -> GrindstoneResult<(i32, Vec<u8>)> {
+    let len = read_varint(reader).await?;
+    let len = usize::try_from(len)
+        .ok()
+        .filter(|len| *len > 0 && *len <= MAX_PACKET_SIZE)
+        .ok_or_else(|| {
+            GrindstoneError::InvalidServerResponse(format!("Invalid packet length {}", len))
+        })?;
+
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body).await?;
+
+    let (id, payload) = decode_varint(&body)?;
+
+    Ok((id, payload.to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn varint_round_trip() {
+        for value in [0, 1, 127, 128, 255, 25565, 2097151, i32::MAX, -1, i32::MIN] {
+            let mut buf = Vec::new();
+            write_varint(&mut buf, value);
+
+            assert!(buf.len() <= VARINT_MAX_BYTES);
+            assert_eq!(decode_varint(&buf).unwrap(), (value, &[][..]));
+        }
+    }
+
+    #[test]
+    fn varint_known_encodings() {
+        let mut buf = Vec::new();
+        write_varint(&mut buf, 300);
+        assert_eq!(buf, [0xAC, 0x02]);
+
+        buf.clear();
+        write_varint(&mut buf, -1);
+        assert_eq!(buf, [0xFF, 0xFF, 0xFF, 0xFF, 0x0F]);
+    }
+
+    #[test]
+    fn varint_truncated() {
+        assert!(decode_varint(&[0x80, 0x80]).is_err());
+        assert!(decode_varint(&[0xFF; 6]).is_err());
+    }
+
+    #[test]
+    fn string_round_trip() {
+        let mut buf = Vec::new();
+        write_string(&mut buf, "héllo");
+        buf.push(42);
+
+        let (value, rest) = decode_string(&buf).unwrap();
+        assert_eq!(value, "héllo");
+        assert_eq!(rest, [42]);
+    }
+
+    #[test]
+    fn string_too_long() {
+        let mut buf = Vec::new();
+        write_varint(&mut buf, 10);
+        buf.extend_from_slice(b"abc");
+
+        assert!(decode_string(&buf).is_err());
+    }
+
+    #[tokio::test]
+    async fn packet_round_trip() {
+        let mut packet = Vec::new();
+        write_packet(&mut packet, 0x01, &[1, 2, 3]).await.unwrap();
+        assert_eq!(packet, [4, 0x01, 1, 2, 3]);
+
+        let (id, payload) = read_packet(&mut packet.as_slice()).await.unwrap();
+        assert_eq!(id, 0x01);
+        assert_eq!(payload, [1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn packet_invalid_length() {
+        let mut buf = Vec::new();
+        write_varint(&mut buf, 0);
+        assert!(read_packet(&mut buf.as_slice()).await.is_err());
+
+        buf.clear();
+        write_varint(&mut buf, MAX_PACKET_SIZE as i32 + 1);
+        assert!(read_packet(&mut buf.as_slice()).await.is_err());
+    }
+}
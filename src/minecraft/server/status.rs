@@ -0,0 +1,171 @@
+use std::time::Duration;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::GrindstoneResult;
+
+use super::chat::ChatComponent;
+
+/// Prefix of the favicon data URI sent by servers.
+const FAVICON_PREFIX: &str = "data:image/png;base64,";
+
+/// Status of a Minecraft server, as displayed in the multiplayer menu.
+#[derive(Clone, Debug)]
+pub struct ServerStatus {
+    /// Version of the server.
+    pub version: ServerVersion,
+    /// Players on the server.
+    pub players: ServerPlayers,
+    /// Description of the server (MOTD).
+    pub description: ChatComponent,
+    /// PNG bytes of the server icon.
+    pub favicon: Option<Vec<u8>>,
+    /// Whether the server enforces secure chat.
+    pub enforces_secure_chat: bool,
+    /// Round-trip time to the server.
+    /// `None` if the server did not answer the ping.
+    pub latency: Option<Duration>,
+    /// Whether the status was obtained with the legacy (pre 1.7) ping.
+    /// Legacy responses have no player sample nor favicon.
+    pub legacy: bool,
+}
+
+/// Version of a server.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ServerVersion {
+    /// Version name, for example `1.19.2` or `Paper 1.19.2`.
+    pub name: String,
+    /// Protocol version number.
+    pub protocol: i32,
+}
+
+/// Players connected to a server.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ServerPlayers {
+    /// Maximum number of players.
+    pub max: i32,
+    /// Number of players currently online.
+    pub online: i32,
+    /// Sample of the connected players.
+    #[serde(default)]
+    pub sample: Vec<PlayerSample>,
+}
+
+/// A player listed in the status sample.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PlayerSample {
+    /// Name of the player.
+    pub name: String,
+    /// UUID of the player.
+    pub id: String,
+}
+
+/// Status JSON sent by the server.
+#[derive(Deserialize)]
+struct StatusResponse {
+    #[serde(default)]
+    version: ServerVersion,
+    #[serde(default)]
+    players: ServerPlayers,
+    #[serde(default)]
+    description: ChatComponent,
+    favicon: Option<String>,
+    #[serde(default, alias = "enforcesSecureChat")]
+    enforces_secure_chat: bool,
+}
+
+impl ServerStatus {
+    /// Parse the status JSON sent by a server.
+    pub fn from_json(json: &str) -> GrindstoneResult<Self> {
+        let response = serde_json::from_str::<StatusResponse>(json)?;
+
+        Ok(Self {
+            version: response.version,
+            players: response.players,
+            description: response.description,
+            favicon: response.favicon.as_deref().and_then(decode_favicon),
+            enforces_secure_chat: response.enforces_secure_chat,
+            latency: None,
+            legacy: false,
+        })
+    }
+}
+
+/// Decode the favicon data URI into PNG bytes.
+fn decode_favicon(favicon: &str) -> Option<Vec<u8>> {
+    let data = favicon.strip_prefix(FAVICON_PREFIX).unwrap_or(favicon);
+    // Some servers split the base64 data on multiple lines
+    let data = data
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>();
+
+    match STANDARD.decode(data) {
+        Ok(bytes) => Some(bytes),
+        Err(err) => {
+            debug!("Ignoring invalid server favicon: {}", err);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_full_status() {
+        let json = r#"{
+            "version": {"name": "1.19.2", "protocol": 760},
+            "players": {"max": 20, "online": 1, "sample": [{"name": "Notch", "id": "069a79f4-44e9-4726-a5be-fca90e38aaf5"}]},
+            "description": {"text": "A ", "extra": [{"text": "§aserver", "bold": true}]},
+            "favicon": "data:image/png;base64,iVBO\nRw0K",
+            "enforcesSecureChat": true
+        }"#;
+
+        let status = ServerStatus::from_json(json).unwrap();
+        assert_eq!(status.version.name, "1.19.2");
+        assert_eq!(status.version.protocol, 760);
+        assert_eq!(status.players.max, 20);
+        assert_eq!(status.players.online, 1);
+        assert_eq!(status.players.sample[0].name, "Notch");
+        assert_eq!(status.description.to_plain_text(), "A server");
+        assert_eq!(status.favicon.unwrap(), b"\x89PNG\r\n");
+        assert!(status.enforces_secure_chat);
+        assert!(!status.legacy);
+    }
+
+    #[test]
+    fn parse_minimal_status() {
+        let status = ServerStatus::from_json(r#"{"description": "Hello"}"#).unwrap();
+        assert_eq!(status.description.to_plain_text(), "Hello");
+        assert_eq!(status.players.online, 0);
+        assert!(status.favicon.is_none());
+    }
+
+    #[test]
+    fn invalid_favicon_is_ignored() {
+        let status =
+            ServerStatus::from_json(r#"{"favicon": "data:image/png;base64,%%%"}"#).unwrap();
+        assert!(status.favicon.is_none());
+    }
+
+    #[test]
+    fn description_forms() {
+        let parse = |description: &str| {
+            ServerStatus::from_json(&format!(r#"{{"description": {}}}"#, description))
+                .unwrap()
+                .description
+                .to_plain_text()
+        };
+
+        assert_eq!(parse(r#"[{"text": "a"}, "b"]"#), "ab");
+        assert_eq!(parse(r#"{"text": 12}"#), "12");
+        assert_eq!(
+            parse(r#"{"translate": "multiplayer.status", "with": ["x"]}"#),
+            "multiplayer.status x"
+        );
+    }
+}
@@ -2,19 +2,23 @@ use log::{debug, trace};
 
 use crate::{
     config::Config,
+    download::{Download, DownloadManager},
     errors::GrindstoneResult,
     event::EventType,
     invoke_callback,
     minecraft::VersionData,
-    utils::download::{download_file_check, Download},
 };
 
 pub struct Client;
 
 impl Client {
-    pub async fn install(config: &Config, version_data: &VersionData) -> GrindstoneResult<()> {
-        let downloads = match &version_data.downloads {
-            Some(downloads) => downloads,
+    pub async fn install(
+        config: &Config,
+        version_data: &VersionData,
+        downloads: &DownloadManager,
+    ) -> GrindstoneResult<()> {
+        let files = match &version_data.downloads {
+            Some(files) => files,
             None => {
                 debug!(
                     "The version data does not contain download information. Skipping download."
@@ -25,19 +29,17 @@ impl Client {
 
         trace!("Building download for client");
         let minecraft_path = config.version_jar_path();
-        let sha1 = hex::decode(&downloads.client.sha1)?;
-        let downloads = Download {
-            url: downloads.client.url.clone(),
+        let sha1 = hex::decode(&files.client.sha1)?;
+        let download = Download {
+            url: files.client.url.clone(),
             file: minecraft_path,
             sha1: Some(sha1),
+            size: Some(files.client.size as u64),
+            alternate_urls: Vec::new(),
         };
 
-        let client = reqwest::Client::new();
-
         invoke_callback!(config, EventType::DownloadClient, "Dowloading client jar");
 
-        download_file_check(&client, downloads.url, downloads.file, downloads.sha1).await?;
-
-        Ok(())
+        downloads.download_one(download).await
     }
 }
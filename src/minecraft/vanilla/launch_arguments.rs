@@ -0,0 +1,178 @@
+use std::{collections::HashMap, path::Path};
+
+use crate::{config::Config, utils::os::Platform};
+
+use super::models::version_data::VersionData;
+
+/// Name of the launcher given to the game.
+const LAUNCHER_NAME: &str = env!("CARGO_PKG_NAME");
+/// Version of the launcher given to the game.
+const LAUNCHER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+impl VersionData {
+    /// Game arguments with the known variables replaced.
+    ///
+    /// Variables depending on the player session (`${auth_player_name}`, ...)
+    /// are kept as is.
+    pub fn game_arguments(&self, config: &Config) -> Vec<String> {
+        let args = match (&self.arguments, &self.minecraft_arguments) {
+            (Some(arguments), _) => arguments.game_arguments(),
+            (None, Some(legacy)) => legacy.split_whitespace().map(String::from).collect(),
+            (None, None) => Vec::new(),
+        };
+
+        self.substitute_variables(config, args)
+    }
+
+    /// JVM arguments with the known variables replaced.
+    pub fn jvm_arguments(&self, config: &Config) -> Vec<String> {
+        let args = match &self.arguments {
+            Some(arguments) => arguments.jvm_arguments(),
+            // Versions older than 1.13 do not declare their JVM arguments
+            None => vec![
+                "-Djava.library.path=${natives_directory}".to_string(),
+                "-cp".to_string(),
+                "${classpath}".to_string(),
+            ],
+        };
+
+        self.substitute_variables(config, args)
+    }
+
+    /// Builds the classpath: the needed libraries followed by the client jar.
+    pub fn classpath(&self, config: &Config) -> String {
+        let libraries_path = config.libraries_path();
+
+        let mut entries = self
+            .needed_libraries()
+            .into_iter()
+            .filter(|library| !library.needs_extract())
+            .map(|library| path_string(library.jar_path(&libraries_path)))
+            .collect::<Vec<_>>();
+        entries.push(path_string(config.version_jar_path()));
+
+        entries.join(&Platform::current().classpath_separator().to_string())
+    }
+
+    fn substitute_variables(&self, config: &Config, args: Vec<String>) -> Vec<String> {
+        let variables = self.launch_variables(config);
+
+        args.iter().map(|arg| substitute(arg, &variables)).collect()
+    }
+
+    fn launch_variables(&self, config: &Config) -> HashMap<&'static str, String> {
+        let game_assets = match self.assets.as_str() {
+            "legacy" => config.virtual_assets_path("legacy"),
+            "pre-1.6" => config.resources_path(),
+            _ => config.assets_path(),
+        };
+
+        HashMap::from([
+            ("game_directory", path_string(config.game_directory())),
+            ("assets_root", path_string(config.assets_path())),
+            ("game_assets", path_string(game_assets)),
+            ("assets_index_name", self.assets.clone()),
+            ("version_name", self.id.clone()),
+            ("version_type", self._type.to_string()),
+            ("library_directory", path_string(config.libraries_path())),
+            ("natives_directory", path_string(config.natives_path())),
+            ("classpath", self.classpath(config)),
+            (
+                "classpath_separator",
+                Platform::current().classpath_separator().to_string(),
+            ),
+            ("launcher_name", LAUNCHER_NAME.to_string()),
+            ("launcher_version", LAUNCHER_VERSION.to_string()),
+        ])
+    }
+}
+
+/// Replace the `${name}` variables of an argument.
+/// Unknown variables are left untouched.
+fn substitute(arg: &str, variables: &HashMap<&'static str, String>) -> String {
+    let mut result = String::with_capacity(arg.len());
+    let mut rest = arg;
+
+    while let Some(start) = rest.find("${") {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+
+        result.push_str(&rest[..start]);
+
+        match variables.get(&rest[start + 2..end]) {
+            Some(value) => result.push_str(value),
+            None => result.push_str(&rest[start..=end]),
+        }
+
+        rest = &rest[end + 1..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+fn path_string(path: impl AsRef<Path>) -> String {
+    path.as_ref().to_string_lossy().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{config::ConfigBuilder, version::MinecraftVersionBuilder};
+
+    use super::*;
+
+    #[test]
+    fn substitute_variables() {
+        let variables = HashMap::from([("game_directory", "/games/Survival".to_string())]);
+
+        assert_eq!(
+            substitute("${game_directory}", &variables),
+            "/games/Survival"
+        );
+        assert_eq!(
+            substitute("-Dgame=${game_directory}/logs", &variables),
+            "-Dgame=/games/Survival/logs"
+        );
+        assert_eq!(
+            substitute("${auth_player_name}", &variables),
+            "${auth_player_name}"
+        );
+        assert_eq!(substitute("${unclosed", &variables), "${unclosed");
+    }
+
+    #[test]
+    fn game_directory_is_instance_folder() {
+        let version_data = serde_json::from_str::<VersionData>(
+            r#"{
+                "assetIndex": {"id": "1.12", "sha1": "00", "size": 1, "totalSize": 1, "url": "https://example.com"},
+                "assets": "1.12",
+                "complianceLevel": 0,
+                "id": "1.12.2",
+                "javaVersion": {"component": "jre-legacy", "majorVersion": 8},
+                "libraries": [],
+                "mainClass": "net.minecraft.client.main.Main",
+                "minecraftArguments": "--gameDir ${game_directory} --username ${auth_player_name}",
+                "minimumLauncherVersion": 18,
+                "releaseTime": "2017-09-18T08:39:46+00:00",
+                "time": "2017-09-18T08:39:46+00:00",
+                "type": "release"
+            }"#,
+        )
+        .unwrap();
+
+        let mut builder = ConfigBuilder::default()
+            .name("Survival")
+            .minecraft_folder_path(std::env::temp_dir());
+        builder.version = MinecraftVersionBuilder::default()
+            .version_id("1.12.2")
+            .build();
+        let config = builder.build().unwrap();
+
+        let args = version_data.game_arguments(&config);
+        assert_eq!(args[1], path_string(config.current_instance()));
+        assert_ne!(args[1], path_string(config.dot_minecraft_path()));
+        assert_eq!(args[3], "${auth_player_name}");
+    }
+}
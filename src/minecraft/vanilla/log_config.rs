@@ -2,11 +2,11 @@ use log::{debug, trace};
 
 use crate::{
     config::Config,
+    download::{Download, DownloadManager},
     errors::GrindstoneResult,
     event::EventType,
     invoke_callback,
     minecraft::VersionData,
-    utils::download::{download_file_check, Download},
 };
 
 use super::models::version_data::logging_info::LoggingInfo;
@@ -15,6 +15,7 @@ impl LoggingInfo {
     pub async fn install_log_patch(
         config: &Config,
         version_data: &VersionData,
+        downloads: &DownloadManager,
     ) -> GrindstoneResult<()> {
         let logging_info = match &version_data.logging {
             Some(info) => info,
@@ -36,22 +37,20 @@ impl LoggingInfo {
         );
 
         let sha1 = hex::decode(&logging_info.client.file.sha1)?;
-        let downloads = Download {
+        let download = Download {
             url: logging_info.client.file.url.clone(),
             file: config_path,
             sha1: Some(sha1),
+            size: Some(logging_info.client.file.size as u64),
+            alternate_urls: Vec::new(),
         };
 
-        let client = reqwest::Client::new();
-
         invoke_callback!(
             config,
             EventType::DownloadLogConfig,
             "Downloading log config"
         );
 
-        download_file_check(&client, downloads.url, downloads.file, downloads.sha1).await?;
-
-        Ok(())
+        downloads.download_one(download).await
     }
 }
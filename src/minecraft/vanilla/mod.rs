@@ -1,4 +1,5 @@
 pub mod client;
+mod launch_arguments;
 mod libraries;
 mod log_config;
 pub mod models;
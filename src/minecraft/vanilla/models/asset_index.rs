@@ -1,27 +1,33 @@
 use std::{
     collections::HashMap,
-    path::{Path, PathBuf},
-    sync::Arc,
+    panic,
+    path::{Component, Path, PathBuf},
 };
 
-use futures::{stream::FuturesUnordered, StreamExt};
-use log::trace;
+use futures::{stream, StreamExt};
+use log::{debug, trace};
 use serde::{Deserialize, Serialize};
-use tokio::{fs, sync::Semaphore};
+use tokio::{fs, task};
 
 use crate::{
-    config::Config,
+    config::{AssetLinkStrategy, Config},
     constants,
+    download::{Download, DownloadManager},
     errors::GrindstoneResult,
     event::{AssetInstallationUpdate, EventType, Progress},
     invoke_callback,
-    utils::download::{download_file_check, Download},
+    utils::sha1::get_sha1,
 };
 
 /// Map of all the assets for a `Minecraft` version.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct AssetIndex {
-    /// Wether to install the assets as resources or not.
+    /// Whether to also install the assets as named files in `assets/virtual/<index>`.
+    ///
+    /// Used by the `legacy` index (pre1.7).
+    #[serde(default, rename = "virtual")]
+    pub is_virtual: bool,
+    /// Whether to install the assets as resources or not.
     ///
     /// Used primarily on older versions (pre1.6).
     #[serde(default)]
@@ -54,8 +60,8 @@ impl AssetInfo {
     }
 
     /// Builds the complete path for the asset file mapped as a resource.
-    pub fn resource_path(key: &str, minecraft_path: impl AsRef<Path>) -> PathBuf {
-        let mut resource_path = PathBuf::from(minecraft_path.as_ref());
+    pub fn resource_path(key: &str, game_directory: impl AsRef<Path>) -> PathBuf {
+        let mut resource_path = PathBuf::from(game_directory.as_ref());
 
         resource_path.push("resources");
         resource_path.push(key);
@@ -77,16 +83,20 @@ impl AssetInfo {
 }
 
 impl AssetIndex {
-    pub async fn install_assets(&self, config: &Config) -> GrindstoneResult<()> {
-        let downloads = self
+    /// Download the assets, then place them in the legacy folders when the index requires it.
+    /// * `index_id` - ID of the index, naming its virtual folder
+    pub async fn install_assets(
+        &self,
+        config: &Config,
+        index_id: &str,
+        downloads: &DownloadManager,
+    ) -> GrindstoneResult<()> {
+        let batch = self
             .objects
             .values()
             .map(|a| build_download(a, config.assets_path()))
             .collect::<Result<Vec<_>, _>>()?;
 
-        let mut count = 0;
-        let max = downloads.len() as u32;
-
         let log_progress = |cur: u32, m: u32, msg: String| {
             invoke_callback!(
                 config,
@@ -102,39 +112,65 @@ impl AssetIndex {
             );
         };
 
-        let client = reqwest::Client::new();
-        let mut tasks = FuturesUnordered::new();
-        let semaphore = Arc::new(Semaphore::new(constants::MAX_PARALLEL_DOWNLOAD));
+        downloads
+            .download(batch, |count, max, name| {
+                log_progress(count, max, format!("Downloaded asset {}", name))
+            })
+            .await?;
 
-        for d in downloads {
-            let c = client.clone();
-
-            let permit = semaphore.clone().acquire_owned().await.unwrap();
-            tasks.push(tokio::spawn(async move {
-                let res = download_file_check(&c, d.url, d.file, d.sha1).await;
-                drop(permit);
-                res
-            }));
+        let mut targets = Vec::new();
+        if self.is_virtual {
+            targets.push(config.virtual_assets_path(index_id));
         }
-
-        while let Some(Ok(res)) = tasks.next().await {
-            count += 1;
-            let name = res?;
-            log_progress(count, max, format!("Downloaded asset {}", name));
-        }
-
         if self.map_to_resources {
-            let mut tasks = FuturesUnordered::new();
+            targets.push(config.resources_path());
+        }
 
-            for (key, asset) in self.objects.iter() {
-                let a = create_symlink(key, asset, config);
-                tasks.push(a);
-            }
+        if !targets.is_empty() {
+            let assets_path = config.assets_path();
+            let strategy = config.asset_link_strategy;
+
+            let mut count = 0;
+            let max = (self.objects.len() * targets.len()) as u32;
+
+            let placements = targets.iter().flat_map(|target| {
+                let assets_path = &assets_path;
+
+                self.objects
+                    .iter()
+                    .filter_map(move |(key, asset)| match safe_key(key) {
+                        Some(name) => {
+                            Some((key, asset, asset.asset_path(assets_path), target.join(name)))
+                        }
+                        None => {
+                            debug!("Ignoring unsafe asset name {}", key);
+                            None
+                        }
+                    })
+            });
+
+            let mut tasks = stream::iter(placements)
+                .map(|(key, asset, source, dest)| async move {
+                    place_asset(&source, &dest, asset, strategy).await?;
+                    GrindstoneResult::Ok(key)
+                })
+                .buffer_unordered(constants::MAX_PARALLEL_ASSET_PLACEMENT);
 
             while let Some(res) = tasks.next().await {
                 count += 1;
-                let name = res?;
-                log_progress(count, max, format!("Symlinked asset {}", name));
+                let key = res?;
+                invoke_callback!(
+                    config,
+                    EventType::Assets(
+                        Progress {
+                            current: count,
+                            max,
+                            message: format!("Placed asset {}", key)
+                        },
+                        AssetInstallationUpdate::Symlink
+                    ),
+                    "Placing legacy assets"
+                );
             }
         }
 
@@ -149,41 +185,152 @@ fn build_download(asset: &AssetInfo, assets_path: impl AsRef<Path>) -> Grindston
         url: asset.download_url(),
         file: asset.asset_path(assets_path),
         sha1: Some(sha1),
+        size: Some(asset.size as u64),
+        alternate_urls: Vec::new(),
     })
 }
 
-async fn create_symlink(
-    key: &String,
+/// Asset names are relative paths, names leaving the target folder are rejected.
+fn safe_key(key: &str) -> Option<&Path> {
+    let path = Path::new(key);
+
+    path.components()
+        .all(|c| matches!(c, Component::Normal(_)))
+        .then_some(path)
+}
+
+/// Check if a placed asset is already correct.
+/// Symlinks are replaced when another strategy is used, as they break when the assets move.
+async fn is_placed(
+    source: &Path,
+    dest: &Path,
+    asset: &AssetInfo,
+    strategy: AssetLinkStrategy,
+) -> GrindstoneResult<bool> {
+    let metadata = match fs::symlink_metadata(dest).await {
+        Ok(metadata) => metadata,
+        Err(_) => return Ok(false),
+    };
+
+    if metadata.file_type().is_symlink() {
+        return Ok(strategy == AssetLinkStrategy::Symlink && fs::read_link(dest).await? == source);
+    }
+
+    if !metadata.is_file() || metadata.len() != asset.size as u64 {
+        return Ok(false);
+    }
+
+    // Hashing reads the whole file, keep it off the async workers
+    let path = dest.to_path_buf();
+    let sha1 = match task::spawn_blocking(move || get_sha1(path)).await {
+        Ok(sha1) => sha1?,
+        Err(err) => panic::resume_unwind(err.into_panic()),
+    };
+
+    Ok(hex::encode(sha1) == asset.hash)
+}
+
+/// Place an asset in a legacy folder, doing nothing when it already is.
+async fn place_asset(
+    source: &Path,
+    dest: &Path,
     asset: &AssetInfo,
-    config: &Config,
-) -> GrindstoneResult<String> {
-    let assets_path = config.assets_path();
-    let minecraft_path = config.dot_minecraft_path();
+    strategy: AssetLinkStrategy,
+) -> GrindstoneResult<()> {
+    if is_placed(source, dest, asset, strategy).await? {
+        return Ok(());
+    }
+
+    if fs::symlink_metadata(dest).await.is_ok() {
+        trace!("Replacing outdated asset {}", dest.to_string_lossy());
+        fs::remove_file(dest).await?;
+    } else if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).await?;
+    }
 
-    let name = asset
-        .asset_path(&assets_path)
-        .file_name()
-        .map(|s| s.to_string_lossy().to_string())
-        .unwrap_or_default();
+    let linked = match strategy {
+        AssetLinkStrategy::Copy => return Ok(fs::copy(source, dest).await.map(|_| ())?),
+        AssetLinkStrategy::HardLink => fs::hard_link(source, dest).await,
+        #[cfg(unix)]
+        AssetLinkStrategy::Symlink => fs::symlink(source, dest).await,
+        #[cfg(windows)]
+        AssetLinkStrategy::Symlink => fs::symlink_file(source, dest).await,
+    };
+
+    if let Err(err) = linked {
+        debug!(
+            "Could not link {}, copying it: {}",
+            dest.to_string_lossy(),
+            err
+        );
+        fs::copy(source, dest).await?;
+    }
 
-    let asset_path = asset.asset_path(&assets_path);
-    let resource_path = AssetInfo::resource_path(key.as_str(), &minecraft_path);
-    let parent_dir = resource_path.parent().unwrap();
+    Ok(())
+}
 
-    trace!("Creating parent directory for symlink");
-    fs::create_dir_all(parent_dir).await?;
+#[cfg(test)]
+mod tests {
+    use crate::utils::{fs::test_folder, sha1::get_sha1_bytes};
 
-    trace!(
-        "Creating symlink: {} => {}",
-        resource_path.to_string_lossy(),
-        asset_path.to_string_lossy()
-    );
+    use super::*;
 
-    #[cfg(unix)]
-    fs::symlink(asset_path, resource_path).await?;
+    fn asset(content: &[u8]) -> AssetInfo {
+        AssetInfo {
+            hash: hex::encode(get_sha1_bytes(content)),
+            size: content.len(),
+        }
+    }
 
-    #[cfg(windows)]
-    fs::symlink_file(asset_path, resource_path).await?;
+    #[test]
+    fn asset_keys() {
+        assert_eq!(
+            safe_key("minecraft/sounds/a.ogg"),
+            Some(Path::new("minecraft/sounds/a.ogg"))
+        );
+        assert_eq!(safe_key("../a.ogg"), None);
+        assert_eq!(safe_key("/a.ogg"), None);
+    }
 
-    Ok(name)
+    #[tokio::test]
+    async fn place_and_replace_assets() {
+        let folder = test_folder("place-assets");
+        let source = folder.join("objects/ab");
+        std::fs::create_dir_all(folder.join("objects")).unwrap();
+        std::fs::write(&source, b"sound").unwrap();
+        let asset = asset(b"sound");
+        let dest = folder.join("virtual/legacy/sounds/a.ogg");
+
+        assert!(!is_placed(&source, &dest, &asset, AssetLinkStrategy::Copy)
+            .await
+            .unwrap());
+        place_asset(&source, &dest, &asset, AssetLinkStrategy::Copy)
+            .await
+            .unwrap();
+        assert!(is_placed(&source, &dest, &asset, AssetLinkStrategy::Copy)
+            .await
+            .unwrap());
+
+        // A modified copy is replaced
+        std::fs::write(&dest, b"other").unwrap();
+        assert!(!is_placed(&source, &dest, &asset, AssetLinkStrategy::Copy)
+            .await
+            .unwrap());
+
+        place_asset(&source, &dest, &asset, AssetLinkStrategy::Symlink)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), b"sound");
+        assert!(
+            is_placed(&source, &dest, &asset, AssetLinkStrategy::Symlink)
+                .await
+                .unwrap()
+        );
+        // Links are replaced by copies with another strategy
+        assert!(!is_placed(&source, &dest, &asset, AssetLinkStrategy::Copy)
+            .await
+            .unwrap());
+
+        std::fs::remove_dir_all(folder).unwrap();
+    }
 }
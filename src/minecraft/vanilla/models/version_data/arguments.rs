@@ -24,7 +24,7 @@ impl Arguments {
         Self::collect_args(&self.game)
     }
 
-    /// Collects all arguments and checks wether they are needed by checking defined rules.
+    /// Collects all arguments and checks whether they are needed by checking defined rules.
     fn collect_args(args: &[Argument]) -> Vec<String> {
         let mut arguments = vec![];
 
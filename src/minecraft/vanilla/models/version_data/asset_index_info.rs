@@ -1,6 +1,15 @@
+use std::fs;
+
+use log::{debug, trace};
 use serde::{Deserialize, Serialize};
 
-use crate::minecraft::vanilla::models::asset_index::AssetIndex;
+use crate::{
+    config::Config,
+    download::{Download, DownloadManager},
+    errors::GrindstoneResult,
+    minecraft::vanilla::models::asset_index::AssetIndex,
+    utils::sha1::get_sha1_bytes,
+};
 
 /// The asset index that needs to be used to get all the needed assets to launch the game.
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -19,12 +28,61 @@ pub struct AssetIndexInfo {
 }
 
 impl AssetIndexInfo {
-    /// Gets the index itself from Minecraft servers.
-    pub async fn fetch_index(&self) -> reqwest::Result<AssetIndex> {
-        reqwest::get(&self.url)
-            .await?
-            .error_for_status()?
-            .json()
-            .await
+    /// Gets the index from `assets/indexes` when it is valid,
+    /// otherwise downloads it, checks it and saves it there.
+    pub async fn install_index(
+        &self,
+        config: &Config,
+        downloads: &DownloadManager,
+    ) -> GrindstoneResult<AssetIndex> {
+        if let Some(index) = self.read_index(config)? {
+            trace!("Using saved asset index {}", self.id);
+            return Ok(index);
+        }
+
+        trace!("Downloading asset index {}", self.id);
+        let path = config.asset_index_file_path(&self.id);
+
+        // Saved as downloaded, the checksum is the one of the original file
+        downloads
+            .download_one(Download {
+                url: self.url.clone(),
+                file: path.clone(),
+                sha1: Some(hex::decode(&self.sha1)?),
+                size: Some(self.size as u64),
+                alternate_urls: Vec::new(),
+            })
+            .await?;
+
+        Ok(serde_json::from_slice::<AssetIndex>(&fs::read(path)?)?)
+    }
+
+    /// Read the index saved in `assets/indexes`.
+    /// Returns `None` if it is missing or does not match the expected size and SHA1.
+    pub fn read_index(&self, config: &Config) -> GrindstoneResult<Option<AssetIndex>> {
+        let path = config.asset_index_file_path(&self.id);
+
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let content = fs::read(path)?;
+        if !self.is_valid(&content)? {
+            debug!("Saved asset index {} does not match its checksum", self.id);
+            return Ok(None);
+        }
+
+        match serde_json::from_slice::<AssetIndex>(&content) {
+            Ok(index) => Ok(Some(index)),
+            Err(err) => {
+                debug!("Saved asset index {} is invalid: {}", self.id, err);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Check the size and SHA1 of the index JSON.
+    fn is_valid(&self, content: &[u8]) -> GrindstoneResult<bool> {
+        Ok(content.len() == self.size && get_sha1_bytes(content) == hex::decode(&self.sha1)?)
     }
 }
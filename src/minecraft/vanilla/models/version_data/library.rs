@@ -4,12 +4,15 @@ use serde::{Deserialize, Serialize};
 
 use crate::{constants, utils::os::Architecture};
 
-use super::{extract::Extract, library_download::LibraryDownloads, natives::Natives, rules::Rule};
+use super::{
+    extract::Extract, file::File, library_download::LibraryDownloads, natives::Natives, rules::Rule,
+};
 
 /// A library need for running the game.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Library {
     /// Information about downloading the library.
+    #[serde(default)]
     pub downloads: LibraryDownloads,
     /// Name of the library.
     pub name: String,
@@ -20,9 +23,52 @@ pub struct Library {
     pub rules: Vec<Rule>,
     /// Extract options.
     pub extract: Option<Extract>,
+    /// Base URL of the Maven repository serving the library, when it has no download information.
+    /// Used by the libraries of mod loaders.
+    pub url: Option<String>,
 }
 
 impl Library {
+    /// A library of a Maven repository, with its checksum and size if they are known.
+    /// * `repository` - Base URL of the repository, the libraries of the game by default
+    pub fn from_maven(
+        name: String,
+        repository: Option<String>,
+        sha1: Option<String>,
+        size: Option<usize>,
+    ) -> Self {
+        let mut library = Self {
+            downloads: LibraryDownloads::default(),
+            name,
+            natives: None,
+            rules: Vec::new(),
+            extract: None,
+            url: repository,
+        };
+
+        if let (Some(sha1), Some(size)) = (sha1, size) {
+            library.downloads.artifact = Some(File {
+                id: None,
+                path: None,
+                sha1,
+                size,
+                url: library.build_url_from_name(),
+            });
+        }
+
+        library
+    }
+
+    /// Name of the library without its version, the same for every version of a library.
+    pub fn unversioned_name(&self) -> String {
+        let mut parts = self.split_name();
+        if parts.len() > 2 {
+            parts.remove(2);
+        }
+
+        parts.join(":")
+    }
+
     /// Checks if the library needs to be used on the current machine.
     pub fn check_use(&self) -> bool {
         for rule in &self.rules {
@@ -130,7 +176,11 @@ impl Library {
         // Take package, name and version
         let parts = self.split_name().into_iter().take(3);
 
-        let mut url = vec![constants::MC_LIBRARIES_BASE_URL.to_string()];
+        let repository = self
+            .url
+            .as_deref()
+            .unwrap_or(constants::MC_LIBRARIES_BASE_URL);
+        let mut url = vec![repository.trim_end_matches('/').to_string()];
 
         for (i, part) in parts.enumerate() {
             if i == 0 {
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use super::file::File;
 
 /// Download information of a library.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct LibraryDownloads {
     /// Library file information.
     pub artifact: Option<File>,
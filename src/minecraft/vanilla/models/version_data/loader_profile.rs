@@ -0,0 +1,270 @@
+use std::collections::HashSet;
+
+use serde::Deserialize;
+
+use crate::{
+    constants,
+    download::DownloadManager,
+    errors::GrindstoneResult,
+    version::{MinecraftVersion, VersionType},
+};
+
+use super::{arguments::Argument, library::Library, VersionData};
+
+/// Version profile of a mod loader, published by its meta server.
+///
+/// It is a version JSON inheriting from the game version, with libraries given by Maven name.
+#[derive(Clone, Debug, Deserialize)]
+pub struct LoaderProfile {
+    /// Version ID, e.g. `fabric-loader-0.14.21-1.20.1`.
+    pub id: String,
+    /// Main class replacing the one of the game.
+    #[serde(alias = "mainClass")]
+    pub main_class: String,
+    /// Arguments added to the ones of the game.
+    #[serde(default)]
+    arguments: LoaderArguments,
+    /// Libraries added to the ones of the game.
+    #[serde(default)]
+    libraries: Vec<LoaderLibrary>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+struct LoaderArguments {
+    #[serde(default)]
+    game: Vec<Argument>,
+    #[serde(default)]
+    jvm: Vec<Argument>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct LoaderLibrary {
+    name: String,
+    /// Base URL of the Maven repository serving the library.
+    url: Option<String>,
+    sha1: Option<String>,
+    size: Option<usize>,
+}
+
+impl LoaderProfile {
+    /// URL of the profile of the mod loader of a version,
+    /// `None` without mod loader or for a mod loader that cannot be installed.
+    pub fn url(version: &MinecraftVersion) -> Option<String> {
+        match &version.version_type {
+            VersionType::Fabric(loader) => Some(format!(
+                "{}/v2/versions/loader/{}/{}/profile/json",
+                constants::FABRIC_META_URL,
+                version.id,
+                loader
+            )),
+            VersionType::Quilt(loader) => Some(format!(
+                "{}/v3/versions/loader/{}/{}/profile/json",
+                constants::QUILT_META_URL,
+                version.id,
+                loader
+            )),
+            _ => None,
+        }
+    }
+
+    /// Fetch the profile of the mod loader of a version, `None` if it has none to install.
+    pub async fn fetch(
+        downloads: &DownloadManager,
+        version: &MinecraftVersion,
+    ) -> GrindstoneResult<Option<Self>> {
+        match Self::url(version) {
+            Some(url) => Ok(Some(downloads.fetch_json(&url).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Patch the version data of the game with the mod loader.
+    ///
+    /// Its libraries come first and replace the libraries of the game with the same name,
+    /// its arguments are added to the game ones.
+    pub fn apply(self, version_data: &mut VersionData) {
+        let libraries = self
+            .libraries
+            .into_iter()
+            .map(|l| Library::from_maven(l.name, l.url, l.sha1, l.size))
+            .collect::<Vec<_>>();
+        let names = libraries
+            .iter()
+            .map(Library::unversioned_name)
+            .collect::<HashSet<_>>();
+
+        version_data
+            .libraries
+            .retain(|l| !names.contains(&l.unversioned_name()));
+        version_data.libraries.splice(0..0, libraries);
+
+        match &mut version_data.arguments {
+            Some(arguments) => {
+                arguments.game.extend(self.arguments.game);
+                arguments.jvm.extend(self.arguments.jvm);
+            }
+            None => {
+                // Versions older than 1.13 only have game arguments, in a single string
+                let legacy = version_data
+                    .minecraft_arguments
+                    .get_or_insert_with(String::new);
+
+                for argument in self.arguments.game {
+                    if let Argument::Simple(argument) = argument {
+                        legacy.push(' ');
+                        legacy.push_str(&argument);
+                    }
+                }
+            }
+        }
+
+        version_data.id = self.id;
+        version_data.main_class = self.main_class;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::version::MinecraftVersionBuilder;
+
+    use super::*;
+
+    const PROFILE: &str = r#"{
+        "id": "fabric-loader-0.14.21-1.20.1",
+        "inheritsFrom": "1.20.1",
+        "releaseTime": "2023-06-06T12:00:00+0000",
+        "time": "2023-06-06T12:00:00+0000",
+        "type": "release",
+        "mainClass": "net.fabricmc.loader.impl.launch.knot.KnotClient",
+        "arguments": {
+            "game": [],
+            "jvm": ["-DFabricMcEmu= net.minecraft.client.main.Main "]
+        },
+        "libraries": [
+            {"name": "org.ow2.asm:asm:9.5", "url": "https://maven.fabricmc.net/",
+             "sha1": "dc6ea1875f4d64fbc85e1691c95b96a3d8569c90", "size": 122004},
+            {"name": "net.fabricmc:fabric-loader:0.14.21", "url": "https://maven.fabricmc.net/"}
+        ]
+    }"#;
+
+    fn version_data(arguments: &str) -> VersionData {
+        serde_json::from_str(&format!(
+            r#"{{
+                "assetIndex": {{"id": "5", "sha1": "00", "size": 1, "totalSize": 1, "url": "https://example.com"}},
+                "assets": "5",
+                "complianceLevel": 1,
+                "id": "1.20.1",
+                "javaVersion": {{"component": "java-runtime-gamma", "majorVersion": 17}},
+                "libraries": [
+                    {{"name": "org.ow2.asm:asm:9.3", "downloads": {{"artifact": {{
+                        "sha1": "00", "size": 1, "url": "https://libraries.minecraft.net/asm.jar"
+                    }}}}}},
+                    {{"name": "com.mojang:brigadier:1.1.8", "downloads": {{"artifact": {{
+                        "sha1": "00", "size": 1, "url": "https://libraries.minecraft.net/brigadier.jar"
+                    }}}}}}
+                ],
+                "mainClass": "net.minecraft.client.main.Main",
+                {arguments},
+                "minimumLauncherVersion": 21,
+                "releaseTime": "2023-06-12T13:25:51+00:00",
+                "time": "2023-06-12T13:25:51+00:00",
+                "type": "release"
+            }}"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn profile_urls() {
+        let version = |version_type| {
+            MinecraftVersionBuilder::default()
+                .version_id("1.20.1")
+                .version_type(version_type)
+                .build()
+        };
+
+        assert_eq!(
+            LoaderProfile::url(&version(VersionType::Fabric("0.14.21".to_string()))).unwrap(),
+            "https://meta.fabricmc.net/v2/versions/loader/1.20.1/0.14.21/profile/json"
+        );
+        assert_eq!(
+            LoaderProfile::url(&version(VersionType::Quilt("0.19.1".to_string()))).unwrap(),
+            "https://meta.quiltmc.org/v3/versions/loader/1.20.1/0.19.1/profile/json"
+        );
+        assert!(LoaderProfile::url(&version(VersionType::Vanilla)).is_none());
+        assert!(LoaderProfile::url(&version(VersionType::Forge("47.1.0".to_string()))).is_none());
+    }
+
+    #[test]
+    fn apply_profile() {
+        let profile = serde_json::from_str::<LoaderProfile>(PROFILE).unwrap();
+        let mut data = version_data(r#""arguments": {"game": ["--demo"], "jvm": ["-Xss1M"]}"#);
+        profile.apply(&mut data);
+
+        assert_eq!(data.id, "fabric-loader-0.14.21-1.20.1");
+        assert_eq!(
+            data.main_class,
+            "net.fabricmc.loader.impl.launch.knot.KnotClient"
+        );
+
+        // The loader version of a library replaces the game one
+        let names = data
+            .libraries
+            .iter()
+            .map(|l| l.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "org.ow2.asm:asm:9.5",
+                "net.fabricmc:fabric-loader:0.14.21",
+                "com.mojang:brigadier:1.1.8"
+            ]
+        );
+
+        let (url, sha1, size) = data.libraries[0].download_url();
+        assert_eq!(
+            url,
+            "https://maven.fabricmc.net/org/ow2/asm/asm/9.5/asm-9.5.jar"
+        );
+        assert!(sha1.is_some());
+        assert_eq!(size, Some(122004));
+        let (url, sha1, _) = data.libraries[1].download_url();
+        assert_eq!(
+            url,
+            "https://maven.fabricmc.net/net/fabricmc/fabric-loader/0.14.21/fabric-loader-0.14.21.jar"
+        );
+        assert!(sha1.is_none());
+
+        let arguments = data.arguments.unwrap();
+        assert_eq!(arguments.game_arguments(), ["--demo"]);
+        assert_eq!(
+            arguments.jvm_arguments(),
+            ["-Xss1M", "-DFabricMcEmu= net.minecraft.client.main.Main "]
+        );
+    }
+
+    #[test]
+    fn apply_profile_to_legacy_version() {
+        let profile = serde_json::from_str::<LoaderProfile>(
+            r#"{
+                "id": "quilt-loader-0.19.1-1.12.2",
+                "mainClass": "org.quiltmc.loader.impl.launch.knot.KnotClient",
+                "arguments": {"game": ["--tweakClass", "example.Tweaker"]},
+                "libraries": [{"name": "org.quiltmc:quilt-loader:0.19.1", "url": "https://maven.quiltmc.org/repository/release"}]
+            }"#,
+        )
+        .unwrap();
+        let mut data = version_data(r#""minecraftArguments": "--username ${auth_player_name}""#);
+        profile.apply(&mut data);
+
+        assert_eq!(
+            data.minecraft_arguments.as_deref(),
+            Some("--username ${auth_player_name} --tweakClass example.Tweaker")
+        );
+        assert_eq!(
+            data.libraries[0].download_url().0,
+            "https://maven.quiltmc.org/repository/release/org/quiltmc/quilt-loader/0.19.1/quilt-loader-0.19.1.jar"
+        );
+    }
+}
@@ -6,6 +6,7 @@ mod file;
 pub mod java_version;
 pub mod library;
 mod library_download;
+pub mod loader_profile;
 pub mod logging_info;
 mod natives;
 mod rules;
@@ -13,7 +13,7 @@ pub struct Rule {
 }
 
 impl Rule {
-    /// Checks wether the rule allows functionality or not.
+    /// Checks whether the rule allows functionality or not.
     pub fn allows(&self) -> bool {
         if let Some(os) = &self.os {
             if let Some(platform) = &os.platform {
@@ -1,10 +1,13 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fs};
 
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    config::Config,
     constants,
+    download::DownloadManager,
     errors::{GrindstoneError, GrindstoneResult},
+    utils::fs::write_atomic,
     version::MinecraftVersion,
 };
 
@@ -12,7 +15,7 @@ use super::version_summary::VersionSummary;
 
 /// Manifest with all Minecraft versions.
 /// Includes identifiers for the latest release and snapshot version.
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct VersionsManifest {
     /// Latest release and snapshot version.
     pub latest: LatestVersion,
@@ -22,18 +25,30 @@ pub struct VersionsManifest {
 
 impl VersionsManifest {
     /// Get the manifest from Minecraft servers.
-    pub async fn fetch() -> GrindstoneResult<Self> {
-        let response = reqwest::Client::new()
-            .get(constants::MC_VERSION_MANIFEST_URL)
-            .send()
-            .await?
-            .error_for_status()?
-            .json::<VersionsManifestResponse>()
+    pub async fn fetch(downloads: &DownloadManager) -> GrindstoneResult<Self> {
+        let response = downloads
+            .fetch_cached_json::<VersionsManifestResponse>(constants::MC_VERSION_MANIFEST_URL)
             .await?;
 
         Ok(response.into())
     }
 
+    /// Save the manifest in the updater folder, for offline mode.
+    pub fn save(&self, config: &Config) -> GrindstoneResult<()> {
+        write_atomic(config.version_manifest_path(), serde_json::to_vec(self)?)
+    }
+
+    /// Read the manifest saved by the last online update.
+    pub fn read(config: &Config) -> GrindstoneResult<Self> {
+        let path = config.version_manifest_path();
+
+        if !path.is_file() {
+            return Err(GrindstoneError::MissingFiles(vec![path]));
+        }
+
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
     pub fn get_version(&self, version: &MinecraftVersion) -> GrindstoneResult<&VersionSummary> {
         let summary = self
             .versions
@@ -1,20 +1,17 @@
 use std::fs;
 
 use log::trace;
-use tokio::{fs::File, io::AsyncWriteExt};
 
-use crate::{config::Config, errors::GrindstoneResult};
+use crate::{
+    config::Config, download::DownloadManager, errors::GrindstoneResult, utils::fs::write_atomic,
+};
 
 use super::models::version_data::VersionData;
 
 impl VersionData {
     /// Fetch the version data JSON
-    pub async fn fetch(url: &String) -> reqwest::Result<Self> {
-        reqwest::get(url)
-            .await?
-            .error_for_status()?
-            .json::<Self>()
-            .await
+    pub async fn fetch(downloads: &DownloadManager, url: &str) -> GrindstoneResult<Self> {
+        downloads.fetch_json(url).await
     }
 
     /// Saves the version data JSON to disk
@@ -29,11 +26,7 @@ impl VersionData {
             std::fs::create_dir_all(parent)?;
         }
 
-        let mut file = File::create(version_data_path).await?;
-        file.write_all(&version_data_json).await?;
-        file.sync_all().await?;
-
-        Ok(())
+        write_atomic(version_data_path, version_data_json)
     }
 
     /// Read the version data JSON from disk.
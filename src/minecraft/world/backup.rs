@@ -0,0 +1,250 @@
+use std::{ffi::OsStr, fs, path::Path};
+
+use log::trace;
+use zip::ZipArchive;
+
+use crate::{
+    errors::{GrindstoneError, GrindstoneResult},
+    utils::{
+        archive::{extract_zip, zip_folder},
+        fs::safe_relative_path,
+    },
+};
+
+use super::{World, LEVEL_DAT};
+
+/// Lock file held by the game while the world is open.
+const SESSION_LOCK: &str = "session.lock";
+
+impl World {
+    /// Save the world into a zip archive.
+    ///
+    /// The world folder is stored at the root of the archive, like the game does.
+    pub fn backup(&self, dest: impl AsRef<Path>) -> GrindstoneResult<()> {
+        trace!(
+            "Backing up world {} to {}",
+            self.name,
+            dest.as_ref().to_string_lossy()
+        );
+
+        zip_folder(&self.path, dest, &self.folder_name(), |path| {
+            path == Path::new(SESSION_LOCK)
+        })
+    }
+
+    /// Restore a world from a backup archive into a saves folder.
+    ///
+    /// An existing world with the same folder name is replaced.
+    pub fn restore(
+        archive: impl AsRef<Path>,
+        saves_path: impl AsRef<Path>,
+    ) -> GrindstoneResult<World> {
+        let archive = archive.as_ref();
+        let prefix = find_world_prefix(archive)?;
+
+        // Archives holding the world files at their root are named after the archive
+        let folder_name = if prefix.is_empty() {
+            archive
+                .file_stem()
+                .and_then(|s| world_folder_name(&s.to_string_lossy()))
+                .unwrap_or_else(|| "world".to_string())
+        } else {
+            prefix.clone()
+        };
+
+        let world_path = saves_path.as_ref().join(&folder_name);
+        let temp_path = saves_path
+            .as_ref()
+            .join(format!(".{}.restore", folder_name));
+
+        if temp_path.exists() {
+            fs::remove_dir_all(&temp_path)?;
+        }
+
+        trace!("Extracting backup to {}", temp_path.to_string_lossy());
+        extract_zip(archive, &temp_path, &prefix, |_| false)?;
+
+        if world_path.exists() {
+            trace!("Replacing existing world {}", folder_name);
+            fs::remove_dir_all(&world_path)?;
+        }
+        fs::rename(&temp_path, &world_path)?;
+
+        World::read(world_path)
+    }
+}
+
+/// Find the folder of the archive holding `level.dat`.
+/// Returns an empty string when it is at the root of the archive.
+///
+/// Only a single plain folder is accepted: the prefix becomes the name of the
+/// world folder, which is replaced by the restored world.
+fn find_world_prefix(archive: &Path) -> GrindstoneResult<String> {
+    let mut zip = ZipArchive::new(fs::File::open(archive)?)?;
+    let mut prefix: Option<String> = None;
+
+    for i in 0..zip.len() {
+        let entry = zip.by_index(i)?;
+        let parent = match entry.enclosed_name() {
+            Some(name) if name.file_name() == Some(OsStr::new(LEVEL_DAT)) => {
+                name.parent().unwrap_or(Path::new("")).to_path_buf()
+            }
+            _ => continue,
+        };
+
+        let candidate = if parent.as_os_str().is_empty() {
+            String::new()
+        } else {
+            match world_folder_name(&parent.to_string_lossy()) {
+                Some(name) => name,
+                None => {
+                    trace!("Ignoring level.dat in {}", parent.to_string_lossy());
+                    continue;
+                }
+            }
+        };
+
+        if prefix.as_ref().is_none_or(|p| candidate.len() < p.len()) {
+            prefix = Some(candidate);
+        }
+    }
+
+    prefix.ok_or_else(|| {
+        GrindstoneError::InvalidArchive(format!(
+            "{} does not contain a world",
+            archive.to_string_lossy()
+        ))
+    })
+}
+
+/// Check that a name can be used as a world folder: a single plain path component.
+fn world_folder_name(name: &str) -> Option<String> {
+    safe_relative_path(name)
+        .filter(|path| path.components().count() == 1)
+        .map(|path| path.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::{write::FileOptions, ZipWriter};
+
+    use crate::{
+        nbt::{Compound, Nbt, Tag},
+        utils::fs::test_folder,
+    };
+
+    use super::*;
+
+    fn level_dat(name: &str) -> Vec<u8> {
+        let mut data = Compound::new();
+        data.insert("LevelName", Tag::String(name.to_string()));
+        let mut root = Compound::new();
+        root.insert("Data", Tag::Compound(data));
+
+        let mut bytes = Vec::new();
+        Nbt::new(root).write_gzip(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
+        let mut zip = ZipWriter::new(fs::File::create(path).unwrap());
+
+        for (name, content) in entries {
+            zip.start_file(*name, FileOptions::default()).unwrap();
+            zip.write_all(content).unwrap();
+        }
+
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn world_prefix() {
+        let folder = test_folder("world-prefix");
+        let archive = folder.join("backup.zip");
+
+        for (entries, prefix) in [
+            (&[("level.dat", &b""[..])][..], ""),
+            (&[("My World/level.dat", &b""[..])], "My World"),
+            (&[("a/level.dat", &b""[..]), ("level.dat", &b""[..])], ""),
+            (
+                &[("a/b/level.dat", &b""[..]), ("w/level.dat", &b""[..])],
+                "w",
+            ),
+        ] {
+            write_zip(&archive, entries);
+            assert_eq!(find_world_prefix(&archive).unwrap(), prefix);
+        }
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn reject_unsafe_world_prefix() {
+        let folder = test_folder("unsafe-prefix");
+        let archive = folder.join("backup.zip");
+
+        for name in [
+            "../level.dat",
+            "./level.dat",
+            "/level.dat",
+            "a/b/level.dat",
+            "a/../../level.dat",
+        ] {
+            write_zip(&archive, &[(name, &b""[..])]);
+            assert!(find_world_prefix(&archive).is_err(), "{}", name);
+        }
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn restore_does_not_touch_other_folders() {
+        let folder = test_folder("malicious-restore");
+        let saves = folder.join("instance").join("saves");
+        fs::create_dir_all(&saves).unwrap();
+        fs::write(saves.join("keep.txt"), "").unwrap();
+        fs::write(folder.join("instance").join("keep.txt"), "").unwrap();
+
+        let level = level_dat("Evil");
+        for name in ["../level.dat", "./level.dat", "../../level.dat"] {
+            let archive = folder.join("evil.zip");
+            write_zip(&archive, &[(name, &level)]);
+
+            assert!(World::restore(&archive, &saves).is_err(), "{}", name);
+            assert!(saves.join("keep.txt").exists());
+            assert!(folder.join("instance").join("keep.txt").exists());
+        }
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn restore_world() {
+        let folder = test_folder("restore");
+        let saves = folder.join("saves");
+        fs::create_dir_all(saves.join("World")).unwrap();
+        fs::write(saves.join("World").join("old.txt"), "").unwrap();
+
+        let archive = folder.join("backup.zip");
+        write_zip(
+            &archive,
+            &[
+                ("World/level.dat", &level_dat("Restored")),
+                ("World/region/r.0.0.mca", b"region"),
+                ("../escape.txt", b""),
+            ],
+        );
+
+        let world = World::restore(&archive, &saves).unwrap();
+        assert_eq!(world.name, "Restored");
+        assert_eq!(world.folder_name(), "World");
+        assert!(saves.join("World/region/r.0.0.mca").exists());
+        assert!(!saves.join("World/old.txt").exists());
+        assert!(!folder.join("escape.txt").exists());
+        assert!(!saves.join(".World.restore").exists());
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
@@ -0,0 +1,36 @@
+use std::fmt::{Display, Formatter};
+
+/// Game mode of a world.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameMode {
+    Survival,
+    Creative,
+    Adventure,
+    Spectator,
+    /// Value not known by this library.
+    Unknown(i32),
+}
+
+impl From<i32> for GameMode {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => GameMode::Survival,
+            1 => GameMode::Creative,
+            2 => GameMode::Adventure,
+            3 => GameMode::Spectator,
+            other => GameMode::Unknown(other),
+        }
+    }
+}
+
+impl Display for GameMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameMode::Survival => write!(f, "Survival"),
+            GameMode::Creative => write!(f, "Creative"),
+            GameMode::Adventure => write!(f, "Adventure"),
+            GameMode::Spectator => write!(f, "Spectator"),
+            GameMode::Unknown(value) => write!(f, "Unknown ({})", value),
+        }
+    }
+}
@@ -0,0 +1,151 @@
+mod backup;
+mod game_mode;
+
+use std::{
+    cmp::Reverse,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::debug;
+use time::OffsetDateTime;
+
+use crate::{
+    config::Config,
+    errors::{GrindstoneError, GrindstoneResult},
+    nbt::{Compound, Nbt},
+};
+
+pub use game_mode::GameMode;
+
+/// Name of the file holding the world information.
+const LEVEL_DAT: &str = "level.dat";
+
+/// A singleplayer world of an instance.
+#[derive(Clone, Debug)]
+pub struct World {
+    /// Folder of the world.
+    pub path: PathBuf,
+    /// Name of the world displayed in game.
+    pub name: String,
+    /// Default game mode of the world.
+    pub game_mode: GameMode,
+    /// Whether the world is in hardcore mode.
+    pub hardcore: bool,
+    /// Whether cheats are enabled.
+    pub allow_commands: bool,
+    /// Last time the world was played.
+    pub last_played: Option<OffsetDateTime>,
+    /// Version the world was last played with.
+    /// Only present for worlds saved by 1.9 or newer.
+    pub version: Option<WorldVersion>,
+    /// Seed of the world.
+    pub seed: Option<i64>,
+    /// Path of the world icon, if any.
+    pub icon: Option<PathBuf>,
+}
+
+/// Game version a world was saved with.
+#[derive(Clone, Debug)]
+pub struct WorldVersion {
+    /// Version name, for example `1.19.2`.
+    pub name: String,
+    /// Data version number.
+    pub id: i32,
+    /// Whether the version is a snapshot.
+    pub snapshot: bool,
+}
+
+impl World {
+    /// List the worlds of the configured instance.
+    pub fn list_instance(config: &Config) -> GrindstoneResult<Vec<World>> {
+        Self::list(config.saves_path())
+    }
+
+    /// List the worlds of a saves folder.
+    ///
+    /// Folders without a readable `level.dat` are skipped.
+    /// Worlds are sorted from the most recently played.
+    pub fn list(saves_path: impl AsRef<Path>) -> GrindstoneResult<Vec<World>> {
+        if !saves_path.as_ref().exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut worlds = Vec::new();
+
+        for entry in fs::read_dir(saves_path)? {
+            let path = entry?.path();
+
+            if !path.join(LEVEL_DAT).is_file() {
+                continue;
+            }
+
+            match World::read(&path) {
+                Ok(world) => worlds.push(world),
+                Err(err) => debug!("Skipping world {}: {}", path.to_string_lossy(), err),
+            }
+        }
+
+        worlds.sort_by_key(|world| Reverse(world.last_played));
+
+        Ok(worlds)
+    }
+
+    /// Read a world from its folder.
+    pub fn read(path: impl AsRef<Path>) -> GrindstoneResult<World> {
+        let path = path.as_ref().to_path_buf();
+        let level = Nbt::read_file(path.join(LEVEL_DAT))?;
+
+        let data = level
+            .root
+            .get_compound("Data")
+            .ok_or_else(|| GrindstoneError::Nbt("level.dat has no Data tag".to_string()))?;
+
+        let folder_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let icon = Some(path.join("icon.png")).filter(|p| p.is_file());
+
+        Ok(World {
+            name: data
+                .get_str("LevelName")
+                .map(String::from)
+                .unwrap_or(folder_name),
+            game_mode: GameMode::from(data.get_i64("GameType").unwrap_or_default() as i32),
+            hardcore: data.get_bool("hardcore").unwrap_or_default(),
+            allow_commands: data.get_bool("allowCommands").unwrap_or_default(),
+            last_played: data.get_i64("LastPlayed").and_then(|ms| {
+                OffsetDateTime::from_unix_timestamp_nanos(ms as i128 * 1_000_000).ok()
+            }),
+            version: data.get_compound("Version").map(read_version),
+            seed: read_seed(data),
+            icon,
+            path,
+        })
+    }
+
+    /// Name of the world folder.
+    pub fn folder_name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+}
+
+fn read_version(version: &Compound) -> WorldVersion {
+    WorldVersion {
+        name: version.get_str("Name").unwrap_or_default().to_string(),
+        id: version.get_i64("Id").unwrap_or_default() as i32,
+        snapshot: version.get_bool("Snapshot").unwrap_or_default(),
+    }
+}
+
+/// The seed moved to the world generation settings in 1.16.
+fn read_seed(data: &Compound) -> Option<i64> {
+    data.get_compound("WorldGenSettings")
+        .and_then(|settings| settings.get_i64("seed"))
+        .or_else(|| data.get_i64("RandomSeed"))
+}
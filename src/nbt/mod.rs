@@ -0,0 +1,221 @@
+//! Reader and writer for the Named Binary Tag (NBT) format used by Minecraft save files.
+
+mod mutf8;
+mod reader;
+mod tag;
+mod writer;
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read, Write},
+    path::Path,
+};
+
+use flate2::{bufread::GzDecoder, write::GzEncoder, Compression};
+
+use crate::{errors::GrindstoneResult, utils::fs::write_atomic_with};
+
+pub use reader::NbtReader;
+pub use tag::{Compound, Tag};
+pub use writer::NbtWriter;
+
+/// Magic bytes at the start of gzip data.
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+
+/// A complete NBT document: a named root compound.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Nbt {
+    /// Name of the root compound, usually empty.
+    pub name: String,
+    /// The root compound.
+    pub root: Compound,
+}
+
+impl Nbt {
+    pub fn new(root: Compound) -> Self {
+        Self {
+            name: String::new(),
+            root,
+        }
+    }
+
+    /// Read an uncompressed NBT document.
+    pub fn read<R: Read>(reader: R) -> GrindstoneResult<Self> {
+        let (name, root) = NbtReader::new(reader).read_root()?;
+
+        Ok(Self { name, root })
+    }
+
+    /// Write the document without compression.
+    pub fn write<W: Write>(&self, writer: W) -> GrindstoneResult<()> {
+        NbtWriter::new(writer).write_root(&self.name, &self.root)
+    }
+
+    /// Read a gzip compressed NBT document.
+    pub fn read_gzip<R: BufRead>(reader: R) -> GrindstoneResult<Self> {
+        Self::read(GzDecoder::new(reader))
+    }
+
+    /// Write the document with gzip compression.
+    pub fn write_gzip<W: Write>(&self, writer: W) -> GrindstoneResult<()> {
+        let mut encoder = GzEncoder::new(writer, Compression::default());
+        self.write(&mut encoder)?;
+        encoder.finish()?;
+
+        Ok(())
+    }
+
+    /// Read an NBT file.
+    /// Gzip compression is detected automatically.
+    pub fn read_file(path: impl AsRef<Path>) -> GrindstoneResult<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        if reader.fill_buf()?.starts_with(&GZIP_MAGIC) {
+            Self::read_gzip(reader)
+        } else {
+            Self::read(reader)
+        }
+    }
+
+    /// Write the document to an uncompressed NBT file.
+    pub fn write_file(&self, path: impl AsRef<Path>) -> GrindstoneResult<()> {
+        write_atomic_with(path, |writer| self.write(writer))
+    }
+
+    /// Write the document to a gzip compressed NBT file.
+    pub fn write_gzip_file(&self, path: impl AsRef<Path>) -> GrindstoneResult<()> {
+        write_atomic_with(path, |writer| self.write_gzip(writer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Nbt {
+        let mut nested = Compound::new();
+        nested.insert("name", Tag::String("Bananrama".to_string()));
+        nested.insert("null", Tag::String("\0😀".to_string()));
+
+        let root = [
+            ("byte", Tag::Byte(-1)),
+            ("short", Tag::Short(-12345)),
+            ("int", Tag::Int(i32::MIN)),
+            ("long", Tag::Long(i64::MAX)),
+            ("float", Tag::Float(0.5)),
+            ("double", Tag::Double(-1.25)),
+            ("bytes", Tag::ByteArray(vec![-128, 0, 127])),
+            ("list", Tag::List(vec![Tag::Int(1), Tag::Int(2)])),
+            ("empty", Tag::List(Vec::new())),
+            ("nested", Tag::Compound(nested)),
+            ("ints", Tag::IntArray(vec![1, -1])),
+            ("longs", Tag::LongArray(vec![i64::MIN])),
+        ]
+        .into_iter()
+        .map(|(name, tag)| (name.to_string(), tag))
+        .collect();
+
+        Nbt {
+            name: "root".to_string(),
+            root,
+        }
+    }
+
+    #[test]
+    fn known_encoding() {
+        let mut root = Compound::new();
+        root.insert("name", Tag::String("Bananrama".to_string()));
+
+        let mut bytes = Vec::new();
+        Nbt {
+            name: "hello world".to_string(),
+            root,
+        }
+        .write(&mut bytes)
+        .unwrap();
+
+        let mut expected = vec![0x0A, 0x00, 0x0B];
+        expected.extend_from_slice(b"hello world");
+        expected.extend_from_slice(&[0x08, 0x00, 0x04]);
+        expected.extend_from_slice(b"name");
+        expected.extend_from_slice(&[0x00, 0x09]);
+        expected.extend_from_slice(b"Bananrama");
+        expected.push(0x00);
+
+        assert_eq!(bytes, expected);
+        assert_eq!(
+            Nbt::read(bytes.as_slice()).unwrap().root.get_str("name"),
+            Some("Bananrama")
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let nbt = sample();
+
+        let mut bytes = Vec::new();
+        nbt.write(&mut bytes).unwrap();
+
+        let read = Nbt::read(bytes.as_slice()).unwrap();
+        assert_eq!(read, nbt);
+        // Insertion order is kept
+        assert!(read
+            .root
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .eq(nbt.root.iter().map(|(name, _)| name.as_str())));
+    }
+
+    #[test]
+    fn gzip_round_trip() {
+        let nbt = sample();
+
+        let mut bytes = Vec::new();
+        nbt.write_gzip(&mut bytes).unwrap();
+
+        assert!(bytes.starts_with(&GZIP_MAGIC));
+        assert_eq!(Nbt::read_gzip(bytes.as_slice()).unwrap(), nbt);
+    }
+
+    #[test]
+    fn reject_mixed_list() {
+        let mut root = Compound::new();
+        root.insert("list", Tag::List(vec![Tag::Int(1), Tag::Byte(1)]));
+
+        assert!(Nbt::new(root).write(&mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn reject_invalid_data() {
+        // Root is not a compound
+        assert!(Nbt::read(&[0x01, 0x00, 0x00, 0x05][..]).is_err());
+        // Truncated compound
+        assert!(Nbt::read(&[0x0A, 0x00, 0x00, 0x01, 0x00][..]).is_err());
+        // Negative array length
+        assert!(
+            Nbt::read(&[0x0A, 0x00, 0x00, 0x07, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF][..]).is_err()
+        );
+    }
+
+    /// Root compound holding `depth` lists nested in each other.
+    fn nested_lists(depth: usize) -> Vec<u8> {
+        let mut bytes = vec![0x0A, 0x00, 0x00, 0x09, 0x00, 0x00];
+        for _ in 1..depth {
+            bytes.extend_from_slice(&[0x09, 0x00, 0x00, 0x00, 0x01]);
+        }
+        bytes.extend_from_slice(&[0x01, 0x00, 0x00, 0x00, 0x00, 0x00]);
+
+        bytes
+    }
+
+    #[test]
+    fn read_deep_nesting() {
+        // Runs on a test thread, with a 2 MiB stack
+        assert!(Nbt::read(nested_lists(500).as_slice()).is_ok());
+    }
+
+    #[test]
+    fn reject_deep_nesting() {
+        assert!(Nbt::read(nested_lists(1000).as_slice()).is_err());
+    }
+}
@@ -0,0 +1,99 @@
+//! Java "modified UTF-8", used by NBT strings.
+//!
+//! It differs from UTF-8 in two ways: the null character is encoded on two bytes
+//! and supplementary characters are encoded as two 3-byte surrogates.
+
+use crate::errors::{GrindstoneError, GrindstoneResult};
+
+/// Encode a string to modified UTF-8.
+pub fn encode(value: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(value.len());
+
+    for unit in value.encode_utf16() {
+        match unit {
+            0x0001..=0x007F => bytes.push(unit as u8),
+            0x0000 | 0x0080..=0x07FF => {
+                bytes.push(0xC0 | (unit >> 6) as u8);
+                bytes.push(0x80 | (unit & 0x3F) as u8);
+            }
+            _ => {
+                bytes.push(0xE0 | (unit >> 12) as u8);
+                bytes.push(0x80 | ((unit >> 6) & 0x3F) as u8);
+                bytes.push(0x80 | (unit & 0x3F) as u8);
+            }
+        }
+    }
+
+    bytes
+}
+
+/// Decode a modified UTF-8 string.
+pub fn decode(bytes: &[u8]) -> GrindstoneResult<String> {
+    let invalid = || GrindstoneError::Nbt("String is not valid modified UTF-8".to_string());
+
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut iter = bytes.iter();
+
+    while let Some(&byte) = iter.next() {
+        let mut continuation = || match iter.next() {
+            Some(b) if b & 0xC0 == 0x80 => Ok((b & 0x3F) as u16),
+            _ => Err(invalid()),
+        };
+
+        let unit = match byte {
+            0x00..=0x7F => byte as u16,
+            0xC0..=0xDF => ((byte & 0x1F) as u16) << 6 | continuation()?,
+            0xE0..=0xEF => ((byte & 0x0F) as u16) << 12 | continuation()? << 6 | continuation()?,
+            _ => return Err(invalid()),
+        };
+
+        units.push(unit);
+    }
+
+    String::from_utf16(&units).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_is_unchanged() {
+        assert_eq!(encode("hello"), b"hello");
+        assert_eq!(decode(b"hello").unwrap(), "hello");
+    }
+
+    #[test]
+    fn null_is_encoded_on_two_bytes() {
+        assert_eq!(encode("a\0b"), [b'a', 0xC0, 0x80, b'b']);
+        assert_eq!(decode(&[b'a', 0xC0, 0x80, b'b']).unwrap(), "a\0b");
+    }
+
+    #[test]
+    fn supplementary_characters_use_surrogates() {
+        // U+1F600 is the surrogate pair D83D DE00
+        let bytes = encode("😀");
+        assert_eq!(bytes, [0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80]);
+        assert_eq!(decode(&bytes).unwrap(), "😀");
+    }
+
+    #[test]
+    fn round_trip() {
+        for value in ["", "é", "§aGreen", "日本語", "mixed \0 😀 text"] {
+            assert_eq!(decode(&encode(value)).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn reject_invalid_bytes() {
+        // Truncated sequence, missing continuation byte, 4-byte UTF-8 and lone surrogate
+        for bytes in [
+            &[0xC3][..],
+            &[0xE0, 0x41, 0x80],
+            &[0xF0, 0x9F, 0x98, 0x80],
+            &[0xED, 0xA0, 0xBD],
+        ] {
+            assert!(decode(bytes).is_err(), "{:x?}", bytes);
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use std::io::Read;
+
+use crate::errors::{GrindstoneError, GrindstoneResult};
+
+use super::{mutf8, tag::Compound, tag::Tag};
+
+/// Maximum nesting of lists and compounds.
+/// Same limit as the game, it prevents stack overflows on malicious files.
+const MAX_DEPTH: usize = 512;
+
+/// ID of the end tag, closing compounds.
+const TAG_END: u8 = 0;
+
+pub struct NbtReader<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> NbtReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+
+    /// Read the root compound and its name.
+    pub fn read_root(&mut self) -> GrindstoneResult<(String, Compound)> {
+        let id = self.read_u8()?;
+
+        match self.read_tag(id, 0)? {
+            (name, Tag::Compound(compound)) => Ok((name, compound)),
+            _ => Err(GrindstoneError::Nbt(
+                "Root tag is not a compound".to_string(),
+            )),
+        }
+    }
+
+    fn read_tag(&mut self, id: u8, depth: usize) -> GrindstoneResult<(String, Tag)> {
+        let name = self.read_string()?;
+        let tag = self.read_payload(id, depth)?;
+
+        Ok((name, tag))
+    }
+
+    fn read_payload(&mut self, id: u8, depth: usize) -> GrindstoneResult<Tag> {
+        if depth > MAX_DEPTH {
+            return Err(GrindstoneError::Nbt(
+                "Data is nested too deeply".to_string(),
+            ));
+        }
+
+        match id {
+            9 => self.read_list(depth),
+            10 => self.read_compound(depth),
+            _ => self.read_value(id),
+        }
+    }
+
+    /// Read a tag that cannot contain other tags.
+    ///
+    /// Kept out of the recursion so that nested lists and compounds use small stack frames.
+    fn read_value(&mut self, id: u8) -> GrindstoneResult<Tag> {
+        let tag = match id {
+            1 => Tag::Byte(self.read_u8()? as i8),
+            2 => Tag::Short(i16::from_be_bytes(self.read_array()?)),
+            3 => Tag::Int(self.read_i32()?),
+            4 => Tag::Long(i64::from_be_bytes(self.read_array()?)),
+            5 => Tag::Float(f32::from_be_bytes(self.read_array()?)),
+            6 => Tag::Double(f64::from_be_bytes(self.read_array()?)),
+            7 => {
+                let len = self.read_len()?;
+                Tag::ByteArray(self.read_bytes(len)?.into_iter().map(|b| b as i8).collect())
+            }
+            8 => Tag::String(self.read_string()?),
+            11 => {
+                let len = self.read_len()?;
+                let mut values = Vec::with_capacity(len.min(1024));
+
+                for _ in 0..len {
+                    values.push(self.read_i32()?);
+                }
+
+                Tag::IntArray(values)
+            }
+            12 => {
+                let len = self.read_len()?;
+                let mut values = Vec::with_capacity(len.min(1024));
+
+                for _ in 0..len {
+                    values.push(i64::from_be_bytes(self.read_array()?));
+                }
+
+                Tag::LongArray(values)
+            }
+            _ => return Err(GrindstoneError::Nbt(format!("Unknown tag type {}", id))),
+        };
+
+        Ok(tag)
+    }
+
+    fn read_list(&mut self, depth: usize) -> GrindstoneResult<Tag> {
+        let item_id = self.read_u8()?;
+        let len = self.read_len()?;
+        let mut items = Vec::with_capacity(len.min(1024));
+
+        for _ in 0..len {
+            items.push(self.read_payload(item_id, depth + 1)?);
+        }
+
+        Ok(Tag::List(items))
+    }
+
+    fn read_compound(&mut self, depth: usize) -> GrindstoneResult<Tag> {
+        let mut compound = Compound::new();
+
+        loop {
+            let item_id = self.read_u8()?;
+            if item_id == TAG_END {
+                break;
+            }
+
+            let (name, tag) = self.read_tag(item_id, depth + 1)?;
+            compound.insert(name, tag);
+        }
+
+        Ok(Tag::Compound(compound))
+    }
+
+    fn read_array<const N: usize>(&mut self) -> GrindstoneResult<[u8; N]> {
+        let mut buf = [0; N];
+        self.reader.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn read_u8(&mut self) -> GrindstoneResult<u8> {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    fn read_i32(&mut self) -> GrindstoneResult<i32> {
+        Ok(i32::from_be_bytes(self.read_array()?))
+    }
+
+    fn read_len(&mut self) -> GrindstoneResult<usize> {
+        let len = self.read_i32()?;
+        usize::try_from(len).map_err(|_| GrindstoneError::Nbt(format!("Invalid length {}", len)))
+    }
+
+    fn read_bytes(&mut self, len: usize) -> GrindstoneResult<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(len.min(1024 * 1024));
+        (&mut self.reader)
+            .take(len as u64)
+            .read_to_end(&mut bytes)?;
+
+        if bytes.len() != len {
+            return Err(GrindstoneError::Nbt("Unexpected end of data".to_string()));
+        }
+
+        Ok(bytes)
+    }
+
+    fn read_string(&mut self) -> GrindstoneResult<String> {
+        let len = u16::from_be_bytes(self.read_array()?) as usize;
+        let bytes = self.read_bytes(len)?;
+
+        mutf8::decode(&bytes)
+    }
+}
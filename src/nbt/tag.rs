@@ -0,0 +1,178 @@
+/// A single NBT value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    /// List of tags, all of the same type.
+    List(Vec<Tag>),
+    Compound(Compound),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    /// Binary ID of the tag type.
+    pub fn id(&self) -> u8 {
+        match self {
+            Tag::Byte(_) => 1,
+            Tag::Short(_) => 2,
+            Tag::Int(_) => 3,
+            Tag::Long(_) => 4,
+            Tag::Float(_) => 5,
+            Tag::Double(_) => 6,
+            Tag::ByteArray(_) => 7,
+            Tag::String(_) => 8,
+            Tag::List(_) => 9,
+            Tag::Compound(_) => 10,
+            Tag::IntArray(_) => 11,
+            Tag::LongArray(_) => 12,
+        }
+    }
+
+    /// Get the value as an integer, whatever its integer type is.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Tag::Byte(v) => Some(*v as i64),
+            Tag::Short(v) => Some(*v as i64),
+            Tag::Int(v) => Some(*v as i64),
+            Tag::Long(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Get the value as a boolean.
+    /// Minecraft stores booleans as bytes.
+    pub fn as_bool(&self) -> Option<bool> {
+        self.as_i64().map(|v| v != 0)
+    }
+
+    /// Get the value as a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Get the value as a list.
+    pub fn as_list(&self) -> Option<&Vec<Tag>> {
+        match self {
+            Tag::List(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Get the value as a compound.
+    pub fn as_compound(&self) -> Option<&Compound> {
+        match self {
+            Tag::Compound(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+/// A map of named tags.
+///
+/// Entries keep their insertion order, so files are written back the way they were read.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Compound {
+    entries: Vec<(String, Tag)>,
+}
+
+impl Compound {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get a tag by its name.
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        self.entries
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, tag)| tag)
+    }
+
+    /// Get a mutable tag by its name.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Tag> {
+        self.entries
+            .iter_mut()
+            .find(|(key, _)| key == name)
+            .map(|(_, tag)| tag)
+    }
+
+    /// Insert a tag.
+    /// An existing tag with the same name is replaced in place and returned.
+    pub fn insert<S: Into<String>>(&mut self, name: S, tag: Tag) -> Option<Tag> {
+        let name = name.into();
+
+        match self.get_mut(&name) {
+            Some(existing) => Some(std::mem::replace(existing, tag)),
+            None => {
+                self.entries.push((name, tag));
+                None
+            }
+        }
+    }
+
+    /// Remove a tag by its name.
+    pub fn remove(&mut self, name: &str) -> Option<Tag> {
+        let index = self.entries.iter().position(|(key, _)| key == name)?;
+        Some(self.entries.remove(index).1)
+    }
+
+    /// Iterate over the tags in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Tag)> {
+        self.entries.iter().map(|(key, tag)| (key, tag))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Get an integer tag, whatever its integer type is.
+    pub fn get_i64(&self, name: &str) -> Option<i64> {
+        self.get(name).and_then(Tag::as_i64)
+    }
+
+    /// Get a boolean (byte) tag.
+    pub fn get_bool(&self, name: &str) -> Option<bool> {
+        self.get(name).and_then(Tag::as_bool)
+    }
+
+    /// Get a string tag.
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.get(name).and_then(Tag::as_str)
+    }
+
+    /// Get a list tag.
+    pub fn get_list(&self, name: &str) -> Option<&Vec<Tag>> {
+        self.get(name).and_then(Tag::as_list)
+    }
+
+    /// Get a compound tag.
+    pub fn get_compound(&self, name: &str) -> Option<&Compound> {
+        self.get(name).and_then(Tag::as_compound)
+    }
+}
+
+impl FromIterator<(String, Tag)> for Compound {
+    fn from_iter<T: IntoIterator<Item = (String, Tag)>>(iter: T) -> Self {
+        let mut compound = Compound::new();
+
+        for (name, tag) in iter {
+            compound.insert(name, tag);
+        }
+
+        compound
+    }
+}
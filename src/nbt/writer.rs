@@ -0,0 +1,113 @@
+use std::io::Write;
+
+use crate::errors::{GrindstoneError, GrindstoneResult};
+
+use super::{mutf8, tag::Compound, tag::Tag};
+
+/// ID of the end tag, closing compounds and typing empty lists.
+const TAG_END: u8 = 0;
+/// ID of the compound tag.
+const TAG_COMPOUND: u8 = 10;
+
+pub struct NbtWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> NbtWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Write the root compound with its name.
+    pub fn write_root(&mut self, name: &str, root: &Compound) -> GrindstoneResult<()> {
+        self.writer.write_all(&[TAG_COMPOUND])?;
+        self.write_string(name)?;
+        self.write_compound(root)?;
+        self.writer.flush()?;
+
+        Ok(())
+    }
+
+    fn write_named(&mut self, name: &str, tag: &Tag) -> GrindstoneResult<()> {
+        self.writer.write_all(&[tag.id()])?;
+        self.write_string(name)?;
+        self.write_payload(tag)
+    }
+
+    fn write_payload(&mut self, tag: &Tag) -> GrindstoneResult<()> {
+        match tag {
+            Tag::Byte(v) => self.writer.write_all(&v.to_be_bytes())?,
+            Tag::Short(v) => self.writer.write_all(&v.to_be_bytes())?,
+            Tag::Int(v) => self.writer.write_all(&v.to_be_bytes())?,
+            Tag::Long(v) => self.writer.write_all(&v.to_be_bytes())?,
+            Tag::Float(v) => self.writer.write_all(&v.to_be_bytes())?,
+            Tag::Double(v) => self.writer.write_all(&v.to_be_bytes())?,
+            Tag::ByteArray(values) => {
+                self.write_len(values.len())?;
+                let bytes = values.iter().map(|b| *b as u8).collect::<Vec<_>>();
+                self.writer.write_all(&bytes)?;
+            }
+            Tag::String(v) => self.write_string(v)?,
+            Tag::List(items) => {
+                let item_id = items.first().map(Tag::id).unwrap_or(TAG_END);
+
+                if items.iter().any(|item| item.id() != item_id) {
+                    return Err(GrindstoneError::Nbt(
+                        "List contains tags of different types".to_string(),
+                    ));
+                }
+
+                self.writer.write_all(&[item_id])?;
+                self.write_len(items.len())?;
+
+                for item in items {
+                    self.write_payload(item)?;
+                }
+            }
+            Tag::Compound(compound) => self.write_compound(compound)?,
+            Tag::IntArray(values) => {
+                self.write_len(values.len())?;
+                for v in values {
+                    self.writer.write_all(&v.to_be_bytes())?;
+                }
+            }
+            Tag::LongArray(values) => {
+                self.write_len(values.len())?;
+                for v in values {
+                    self.writer.write_all(&v.to_be_bytes())?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_compound(&mut self, compound: &Compound) -> GrindstoneResult<()> {
+        for (name, tag) in compound.iter() {
+            self.write_named(name, tag)?;
+        }
+
+        self.writer.write_all(&[TAG_END])?;
+
+        Ok(())
+    }
+
+    fn write_len(&mut self, len: usize) -> GrindstoneResult<()> {
+        let len = i32::try_from(len)
+            .map_err(|_| GrindstoneError::Nbt("Array or list is too long".to_string()))?;
+        self.writer.write_all(&len.to_be_bytes())?;
+
+        Ok(())
+    }
+
+    fn write_string(&mut self, value: &str) -> GrindstoneResult<()> {
+        let bytes = mutf8::encode(value);
+        let len = u16::try_from(bytes.len())
+            .map_err(|_| GrindstoneError::Nbt("String is too long".to_string()))?;
+
+        self.writer.write_all(&len.to_be_bytes())?;
+        self.writer.write_all(&bytes)?;
+
+        Ok(())
+    }
+}
@@ -1,16 +1,251 @@
-use std::path::{Path, PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use crate::{
+    constants,
     errors::{GrindstoneError, GrindstoneResult},
     version::{MinecraftVersion, MinecraftVersionBuilder},
 };
 
-use super::event::CallbackEvent;
+use super::{download::BandwidthLimiter, event::CallbackEvent, paths::official_minecraft_path};
+
+/// Location of the shared game folder (versions, libraries and assets).
+enum GameRoot {
+    /// `.minecraft` folder inside the output folder.
+    Default,
+    /// Folder used by the official launcher.
+    Official,
+    /// Custom folder.
+    Custom(PathBuf),
+}
+
+/// How legacy assets are placed in `assets/virtual` and in the instance `resources` folder.
+///
+/// Links that cannot be created, for example symlinks on Windows without privileges
+/// or hard links across drives, fall back to a copy.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AssetLinkStrategy {
+    /// Copy the asset files.
+    Copy,
+    /// Hard link the asset files, no extra disk space is used.
+    #[default]
+    HardLink,
+    /// Symlink the asset files.
+    Symlink,
+}
+
+/// Retries of downloads failing with a transient error:
+/// timeouts, server errors, connection resets or checksum mismatches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Maximum attempts for each file, the first one included.
+    pub attempts: u32,
+    /// Delay before the first retry, doubled after each failed attempt.
+    pub initial_backoff: Duration,
+    /// Upper bound of the delay between two attempts.
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 4,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(8),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Never retry, a download fails on its first error.
+    pub fn none() -> Self {
+        Self {
+            attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before the next attempt, with a random jitter so parallel downloads do not retry together.
+    /// * `attempt` - Number of the attempt that failed, starting at 1
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self
+            .initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff);
+
+        // Between half and the whole delay
+        delay / 2 + delay.mul_f64(fastrand::f64() / 2.0)
+    }
+}
+
+/// HTTP settings of the downloads, resolved when the configuration is built.
+#[derive(Clone, Debug)]
+pub struct HttpSettings {
+    /// Client used for every download.
+    pub client: reqwest::Client,
+    /// Maximum files downloaded at the same time.
+    pub max_parallel_downloads: usize,
+    /// Maximum time to wait for data from a server, `None` to wait indefinitely.
+    pub read_timeout: Option<Duration>,
+    /// Maximum download speed, shared by every download of the configuration.
+    pub bandwidth: BandwidthLimiter,
+}
+
+/// Servers the game files are downloaded from.
+///
+/// URLs of the official servers, including the ones read from the version JSON,
+/// are replaced by the configured endpoints, then tried on every mirror serving them
+/// before falling back to the endpoint itself.
+/// An endpoint left on the official servers is skipped when `official_fallback` is disabled.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Endpoints {
+    /// URL of the version manifest.
+    pub version_manifest: String,
+    /// Base URL of the libraries.
+    pub libraries: String,
+    /// URL of the Java runtimes manifest.
+    pub jre_manifest: String,
+    /// Base URL of the assets.
+    pub assets: String,
+    /// Mirrors tried in order before the endpoints.
+    pub mirrors: Vec<Mirror>,
+    /// Whether the official servers are tried when no mirror succeeds.
+    pub official_fallback: bool,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self {
+            version_manifest: constants::MC_VERSION_MANIFEST_URL.to_string(),
+            libraries: constants::MC_LIBRARIES_BASE_URL.to_string(),
+            jre_manifest: constants::JAVA_JRE_MANIFEST_URL.to_string(),
+            assets: constants::MC_ASSETS_BASE_URL.to_string(),
+            mirrors: Vec::new(),
+            official_fallback: true,
+        }
+    }
+}
+
+impl Endpoints {
+    /// URL of a file on the configured endpoints.
+    /// * `url` - URL of the file on the official servers
+    pub fn resolve(&self, url: &str) -> String {
+        if url == constants::MC_VERSION_MANIFEST_URL {
+            return self.version_manifest.clone();
+        }
+        if url == constants::JAVA_JRE_MANIFEST_URL {
+            return self.jre_manifest.clone();
+        }
+
+        let bases = [
+            (constants::MC_LIBRARIES_BASE_URL, &self.libraries),
+            (constants::MC_ASSETS_BASE_URL, &self.assets),
+        ];
+        for (official, base) in bases {
+            if let Some(path) = url.strip_prefix(official) {
+                return format!("{}{}", base.trim_end_matches('/'), path);
+            }
+        }
+
+        url.to_string()
+    }
+
+    /// URLs to try in order for a file: the mirrors serving it, then the endpoint.
+    /// The endpoint is always the last one, unless it is an official server and
+    /// `official_fallback` is disabled, the list can then be empty.
+    /// * `url` - URL of the file on the official servers
+    pub fn candidates(&self, url: &str) -> Vec<String> {
+        let endpoint = self.resolve(url);
+
+        let mut urls = Vec::new();
+        for mirrored in self.mirrors.iter().filter_map(|m| m.rewrite_url(url)) {
+            if mirrored != endpoint && !urls.contains(&mirrored) {
+                urls.push(mirrored);
+            }
+        }
+
+        let official = constants::MC_OFFICIAL_URLS
+            .iter()
+            .any(|prefix| endpoint.starts_with(prefix));
+        if self.official_fallback || !official {
+            urls.push(endpoint);
+        }
+
+        urls
+    }
+}
+
+/// A mirror of the official servers, serving files under other URL prefixes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Mirror {
+    /// Prefixes of the official URLs, with the prefix replacing them on the mirror.
+    pub rewrites: Vec<(String, String)>,
+}
+
+impl Mirror {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serve the URLs starting with a prefix from the mirror.
+    /// * `from` - Prefix of the official URLs, for example `https://libraries.minecraft.net/`
+    /// * `to` - Prefix replacing it
+    pub fn rewrite<F: Into<String>, T: Into<String>>(mut self, from: F, to: T) -> Self {
+        self.rewrites.push((from.into(), to.into()));
+        self
+    }
+
+    /// The [BMCLAPI](https://bmclapidoc.bangbang93.com) mirror.
+    pub fn bmclapi() -> Self {
+        const BMCLAPI: &str = "https://bmclapi2.bangbang93.com";
+
+        Self::new()
+            .rewrite("https://piston-meta.mojang.com/", format!("{}/", BMCLAPI))
+            .rewrite("https://piston-data.mojang.com/", format!("{}/", BMCLAPI))
+            .rewrite("https://launchermeta.mojang.com/", format!("{}/", BMCLAPI))
+            .rewrite("https://launcher.mojang.com/", format!("{}/", BMCLAPI))
+            .rewrite(
+                "https://libraries.minecraft.net/",
+                format!("{}/maven/", BMCLAPI),
+            )
+            .rewrite(
+                "https://resources.download.minecraft.net/",
+                format!("{}/assets/", BMCLAPI),
+            )
+    }
+
+    /// URL of a file on the mirror, `None` if the mirror does not serve it.
+    /// The first matching prefix is used.
+    pub fn rewrite_url(&self, url: &str) -> Option<String> {
+        self.rewrites.iter().find_map(|(from, to)| {
+            url.strip_prefix(from.as_str())
+                .map(|path| format!("{}{}", to, path))
+        })
+    }
+}
 
 pub struct ConfigBuilder {
     event_callback: Box<fn(CallbackEvent)>,
     folder_path: Option<PathBuf>,
+    game_root: GameRoot,
     instance_name: Option<String>,
+    default_options: Vec<(String, String)>,
+    asset_link_strategy: AssetLinkStrategy,
+    retry_policy: RetryPolicy,
+    max_parallel_downloads: usize,
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    proxy: Option<String>,
+    user_agent: String,
+    ca_certificates: Vec<Vec<u8>>,
+    http_client: Option<reqwest::Client>,
+    bandwidth_limit: Option<u64>,
+    endpoints: Endpoints,
+    offline: bool,
+    metadata_max_age: Duration,
     pub version: MinecraftVersion,
 }
 
@@ -18,7 +253,17 @@ pub struct ConfigBuilder {
 pub struct Config {
     pub event_callback: Box<fn(CallbackEvent)>,
     pub folder_path: PathBuf,
+    pub game_root: PathBuf,
     pub instance_name: String,
+    pub default_options: Vec<(String, String)>,
+    pub asset_link_strategy: AssetLinkStrategy,
+    pub retry_policy: RetryPolicy,
+    pub http: HttpSettings,
+    pub endpoints: Endpoints,
+    /// Only use the files already installed, without any network request.
+    pub offline: bool,
+    /// Time during which cached manifests are used without asking the servers if they changed.
+    pub metadata_max_age: Duration,
     pub version: MinecraftVersion,
 }
 
@@ -28,6 +273,21 @@ impl Default for ConfigBuilder {
             instance_name: None,
             event_callback: Box::new(|_| {}),
             folder_path: None,
+            game_root: GameRoot::Default,
+            default_options: Vec::new(),
+            asset_link_strategy: AssetLinkStrategy::default(),
+            retry_policy: RetryPolicy::default(),
+            max_parallel_downloads: constants::MAX_PARALLEL_DOWNLOAD,
+            connect_timeout: Some(Duration::from_secs(30)),
+            read_timeout: Some(Duration::from_secs(60)),
+            proxy: None,
+            user_agent: constants::USER_AGENT.to_string(),
+            ca_certificates: Vec::new(),
+            http_client: None,
+            bandwidth_limit: None,
+            endpoints: Endpoints::default(),
+            offline: false,
+            metadata_max_age: Duration::from_secs(10 * 60),
             version: MinecraftVersionBuilder::default().build(),
         }
     }
@@ -46,14 +306,62 @@ impl ConfigBuilder {
             .clone()
             .ok_or_else(|| GrindstoneError::InvalidConfig("folder_path".to_string()))?;
 
+        let http = HttpSettings {
+            client: match self.http_client.clone() {
+                Some(client) => client,
+                None => self.build_http_client()?,
+            },
+            max_parallel_downloads: self.max_parallel_downloads.max(1),
+            read_timeout: self.read_timeout,
+            bandwidth: BandwidthLimiter::new(self.bandwidth_limit),
+        };
+
+        let game_root = match self.game_root {
+            GameRoot::Default => folder_path.join(".minecraft"),
+            GameRoot::Official => official_minecraft_path()?,
+            GameRoot::Custom(path) => path,
+        };
+
         Ok(Config {
             event_callback: self.event_callback,
             folder_path,
+            game_root,
             instance_name,
+            default_options: self.default_options,
+            asset_link_strategy: self.asset_link_strategy,
+            retry_policy: self.retry_policy,
+            http,
+            endpoints: self.endpoints,
+            offline: self.offline,
+            metadata_max_age: self.metadata_max_age,
             version: self.version,
         })
     }
 
+    fn build_http_client(&self) -> GrindstoneResult<reqwest::Client> {
+        let mut builder = reqwest::Client::builder().user_agent(&self.user_agent);
+
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+
+        if let Some(proxy) = &self.proxy {
+            let proxy = reqwest::Proxy::all(proxy)
+                .map_err(|_| GrindstoneError::InvalidConfig("proxy".to_string()))?;
+            builder = builder.proxy(proxy);
+        }
+
+        for pem in &self.ca_certificates {
+            let certificate = reqwest::Certificate::from_pem(pem)
+                .map_err(|_| GrindstoneError::InvalidConfig("ca_certificates".to_string()))?;
+            builder = builder.add_root_certificate(certificate);
+        }
+
+        builder
+            .build()
+            .map_err(|_| GrindstoneError::InvalidConfig("http".to_string()))
+    }
+
     pub fn name<S: Into<String>>(mut self, instance_name: S) -> Self {
         self.instance_name = Some(instance_name.into());
         self
@@ -73,4 +381,347 @@ impl ConfigBuilder {
         self.folder_path = Some(PathBuf::from(folder.as_ref()));
         self
     }
+
+    /// Set the shared game folder, holding versions, libraries and assets.
+    /// Defaults to a `.minecraft` folder inside the output folder.
+    /// * `folder` - Path to the shared game folder
+    pub fn game_root_path(mut self, folder: impl AsRef<Path>) -> Self {
+        self.game_root = GameRoot::Custom(PathBuf::from(folder.as_ref()));
+        self
+    }
+
+    /// Share versions, libraries and assets with the official Minecraft launcher.
+    /// Building the configuration fails if the official folder cannot be located.
+    pub fn use_official_game_root(mut self) -> Self {
+        self.game_root = GameRoot::Official;
+        self
+    }
+
+    /// Add a default game option, written to the instance `options.txt` if it is not defined yet.
+    /// Options changed by the player are never overwritten.
+    /// * `key` - Option key, for example `lang` or `renderDistance`
+    /// * `value` - Option value, as written in `options.txt`
+    pub fn default_option<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        let key = key.into();
+        let value = value.into();
+
+        match self.default_options.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => *v = value,
+            None => self.default_options.push((key, value)),
+        }
+        self
+    }
+
+    /// Set how legacy assets are placed for versions older than 1.7.
+    /// Defaults to hard links.
+    pub fn asset_link_strategy(mut self, strategy: AssetLinkStrategy) -> Self {
+        self.asset_link_strategy = strategy;
+        self
+    }
+
+    /// Set how downloads failing with a transient error are retried.
+    /// Defaults to 4 attempts, waiting from 0.5 to 8 seconds between them.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Set the maximum files downloaded at the same time.
+    /// Defaults to 50.
+    pub fn max_parallel_downloads(mut self, max: usize) -> Self {
+        self.max_parallel_downloads = max;
+        self
+    }
+
+    /// Set the maximum time to connect to a server, `None` to wait indefinitely.
+    /// Defaults to 30 seconds.
+    pub fn connect_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Set the maximum time to wait for data from a server, `None` to wait indefinitely.
+    /// A download that stalls longer fails and is retried.
+    /// Defaults to 60 seconds.
+    pub fn read_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// Send every request through a proxy.
+    /// * `url` - Proxy URL, with a `http`, `https`, `socks5` or `socks5h` scheme
+    pub fn proxy<S: Into<String>>(mut self, url: S) -> Self {
+        self.proxy = Some(url.into());
+        self
+    }
+
+    /// Set the user agent sent with every request.
+    pub fn user_agent<S: Into<String>>(mut self, user_agent: S) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Trust an extra CA certificate, for example the one of a corporate proxy.
+    /// * `pem` - PEM encoded certificate
+    pub fn add_ca_certificate(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.ca_certificates.push(pem.into());
+        self
+    }
+
+    /// Use a pre-built HTTP client for the downloads.
+    /// The timeout to connect, proxy, user agent and CA certificates are then ignored.
+    pub fn http_client(mut self, client: reqwest::Client) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    /// Limit the speed of all the downloads together.
+    /// It can be changed later with [`BandwidthLimiter::set_limit`].
+    /// * `bytes_per_second` - Maximum speed, `None` for no limit (default)
+    pub fn bandwidth_limit(mut self, bytes_per_second: Option<u64>) -> Self {
+        self.bandwidth_limit = bytes_per_second;
+        self
+    }
+
+    /// Set the URL of the version manifest.
+    pub fn version_manifest_url<S: Into<String>>(mut self, url: S) -> Self {
+        self.endpoints.version_manifest = url.into();
+        self
+    }
+
+    /// Set the base URL of the libraries, also used for the libraries of the version JSON.
+    pub fn libraries_url<S: Into<String>>(mut self, url: S) -> Self {
+        self.endpoints.libraries = url.into();
+        self
+    }
+
+    /// Set the URL of the Java runtimes manifest.
+    pub fn jre_manifest_url<S: Into<String>>(mut self, url: S) -> Self {
+        self.endpoints.jre_manifest = url.into();
+        self
+    }
+
+    /// Set the base URL of the assets.
+    pub fn assets_url<S: Into<String>>(mut self, url: S) -> Self {
+        self.endpoints.assets = url.into();
+        self
+    }
+
+    /// Add a mirror, tried after the previously added ones when a download fails.
+    /// The endpoints are tried last.
+    pub fn add_mirror(mut self, mirror: Mirror) -> Self {
+        self.endpoints.mirrors.push(mirror);
+        self
+    }
+
+    /// Whether the official servers are tried when no mirror succeeds (default `true`).
+    ///
+    /// When disabled, the files only served by the official servers fail to download,
+    /// unless their endpoint is set to another server.
+    pub fn official_fallback(mut self, enabled: bool) -> Self {
+        self.endpoints.official_fallback = enabled;
+        self
+    }
+
+    /// Use only the files already installed, without any network request.
+    ///
+    /// The update then reads the saved version manifest, version JSON, asset index and JRE,
+    /// and fails with the list of missing files instead of downloading them.
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Set the time during which cached manifests are used without asking the servers if they changed.
+    /// Older ones are revalidated, and only downloaded again if they changed.
+    /// Defaults to 10 minutes.
+    pub fn metadata_max_age(mut self, max_age: Duration) -> Self {
+        self.metadata_max_age = max_age;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::version::MinecraftVersionBuilder;
+
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let retry = RetryPolicy {
+            attempts: 10,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5),
+        };
+
+        for (attempt, delay) in [(1, 1), (2, 2), (3, 4), (4, 5), (30, 5)] {
+            let delay = Duration::from_secs(delay);
+
+            for _ in 0..20 {
+                let backoff = retry.backoff(attempt);
+                assert!(backoff >= delay / 2 && backoff <= delay, "{:?}", backoff);
+            }
+        }
+    }
+
+    #[test]
+    fn backoff_does_not_overflow() {
+        let retry = RetryPolicy {
+            attempts: u32::MAX,
+            initial_backoff: Duration::MAX,
+            max_backoff: Duration::MAX,
+        };
+
+        assert!(retry.backoff(u32::MAX) >= Duration::MAX / 2);
+        assert!(RetryPolicy::default().backoff(0) <= Duration::from_millis(500));
+    }
+
+    fn builder() -> ConfigBuilder {
+        let mut builder = ConfigBuilder::default()
+            .name("http")
+            .minecraft_folder_path(std::env::temp_dir());
+        builder.version = MinecraftVersionBuilder::default()
+            .version_id("1.20.1")
+            .build();
+
+        builder
+    }
+
+    #[test]
+    fn http_settings() {
+        let config = builder()
+            .max_parallel_downloads(0)
+            .read_timeout(Some(Duration::from_secs(5)))
+            .proxy("socks5h://127.0.0.1:1080")
+            .bandwidth_limit(Some(1024))
+            .build()
+            .unwrap();
+
+        assert_eq!(config.http.max_parallel_downloads, 1);
+        assert_eq!(config.http.read_timeout, Some(Duration::from_secs(5)));
+        assert_eq!(config.http.bandwidth.limit(), Some(1024));
+    }
+
+    #[test]
+    fn reject_invalid_http_settings() {
+        assert!(matches!(
+            builder().proxy("not a proxy").build(),
+            Err(GrindstoneError::InvalidConfig(field)) if field == "proxy"
+        ));
+        assert!(matches!(
+            builder().add_ca_certificate("not a certificate").build(),
+            Err(GrindstoneError::InvalidConfig(field)) if field == "ca_certificates"
+        ));
+    }
+
+    #[test]
+    fn resolve_endpoints() {
+        let endpoints = Endpoints {
+            version_manifest: "https://meta.example/manifest.json".to_string(),
+            libraries: "https://maven.example/libraries/".to_string(),
+            assets: "https://assets.example".to_string(),
+            ..Endpoints::default()
+        };
+
+        assert_eq!(
+            endpoints.resolve(constants::MC_VERSION_MANIFEST_URL),
+            "https://meta.example/manifest.json"
+        );
+        assert_eq!(
+            endpoints.resolve("https://libraries.minecraft.net/org/lwjgl/lwjgl.jar"),
+            "https://maven.example/libraries/org/lwjgl/lwjgl.jar"
+        );
+        assert_eq!(
+            endpoints.resolve("https://resources.download.minecraft.net/ab/abcdef"),
+            "https://assets.example/ab/abcdef"
+        );
+        assert_eq!(
+            endpoints.resolve(constants::JAVA_JRE_MANIFEST_URL),
+            constants::JAVA_JRE_MANIFEST_URL
+        );
+        assert_eq!(
+            endpoints.resolve("https://cdn.modrinth.com/mod.jar"),
+            "https://cdn.modrinth.com/mod.jar"
+        );
+    }
+
+    #[test]
+    fn mirror_candidates() {
+        let bmclapi = Mirror::bmclapi();
+        assert_eq!(
+            bmclapi
+                .rewrite_url("https://libraries.minecraft.net/org/lwjgl/lwjgl.jar")
+                .as_deref(),
+            Some("https://bmclapi2.bangbang93.com/maven/org/lwjgl/lwjgl.jar")
+        );
+        assert_eq!(
+            bmclapi.rewrite_url("https://cdn.modrinth.com/mod.jar"),
+            None
+        );
+
+        // The first matching prefix wins
+        let mirror = Mirror::new()
+            .rewrite("https://libraries.minecraft.net/org/", "https://a.example/")
+            .rewrite("https://libraries.minecraft.net/", "https://b.example/");
+        assert_eq!(
+            mirror
+                .rewrite_url("https://libraries.minecraft.net/org/lwjgl.jar")
+                .as_deref(),
+            Some("https://a.example/lwjgl.jar")
+        );
+
+        // Mirrors serving the same URL are only tried once
+        let endpoints = Endpoints {
+            mirrors: vec![mirror.clone(), bmclapi, mirror],
+            ..Endpoints::default()
+        };
+        assert_eq!(
+            endpoints.candidates("https://libraries.minecraft.net/com/gson.jar"),
+            [
+                "https://b.example/com/gson.jar",
+                "https://bmclapi2.bangbang93.com/maven/com/gson.jar",
+                "https://libraries.minecraft.net/com/gson.jar",
+            ]
+        );
+        assert_eq!(
+            endpoints.candidates("https://cdn.modrinth.com/mod.jar"),
+            ["https://cdn.modrinth.com/mod.jar"]
+        );
+
+        // Only the official servers are skipped, other servers and endpoints are kept
+        let endpoints = Endpoints {
+            official_fallback: false,
+            assets: "https://assets.example".to_string(),
+            ..endpoints
+        };
+        assert_eq!(
+            endpoints.candidates("https://libraries.minecraft.net/com/gson.jar"),
+            [
+                "https://b.example/com/gson.jar",
+                "https://bmclapi2.bangbang93.com/maven/com/gson.jar",
+            ]
+        );
+        assert!(endpoints
+            .candidates("https://piston-data.mojang.com/v1/objects/abc/client.jar")
+            .iter()
+            .all(|url| url.starts_with("https://bmclapi2.bangbang93.com/")));
+        assert_eq!(
+            endpoints.candidates("https://resources.download.minecraft.net/ab/abcdef"),
+            [
+                "https://bmclapi2.bangbang93.com/assets/ab/abcdef",
+                "https://assets.example/ab/abcdef",
+            ]
+        );
+        assert_eq!(
+            endpoints.candidates("https://cdn.modrinth.com/mod.jar"),
+            ["https://cdn.modrinth.com/mod.jar"]
+        );
+        assert!(Endpoints {
+            mirrors: Vec::new(),
+            ..endpoints
+        }
+        .candidates(constants::MC_VERSION_MANIFEST_URL)
+        .is_empty());
+    }
 }
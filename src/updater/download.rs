@@ -0,0 +1,619 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs, panic,
+    path::PathBuf,
+    pin::pin,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use futures::{stream::FuturesUnordered, StreamExt};
+use log::{debug, trace};
+use serde::de::DeserializeOwned;
+use tokio::sync::{Mutex as AsyncMutex, Notify, Semaphore};
+
+use crate::{
+    config::{Config, Endpoints, HttpSettings, RetryPolicy},
+    errors::{FailedDownload, GrindstoneError, GrindstoneResult},
+    event::CallbackFn,
+    utils::{
+        cache::MetadataCache,
+        download::{
+            download_tracked, ensure_downloaded, fetch, fetch_cached, request_json, DownloadTracker,
+        },
+    },
+};
+
+pub use crate::utils::download::Download;
+
+/// Downloads the files of every installation phase.
+///
+/// The HTTP client and the limit of parallel downloads are shared by all the batches,
+/// including batches running at the same time. Cloning the manager shares them too.
+///
+/// Files are downloaded from the configured mirrors and endpoints, see [`Endpoints`].
+/// In offline mode, nothing is downloaded and the files are only checked to be present.
+#[derive(Clone, Debug)]
+pub struct DownloadManager {
+    http: HttpSettings,
+    permits: Arc<Semaphore>,
+    /// Destinations being downloaded, so batches running at the same time never write the same file.
+    in_flight: Arc<Mutex<HashMap<PathBuf, Arc<AsyncMutex<()>>>>>,
+    retry: RetryPolicy,
+    endpoints: Endpoints,
+    cache: MetadataCache,
+    offline: bool,
+    callback: CallbackFn,
+}
+
+impl DownloadManager {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            http: config.http.clone(),
+            permits: Arc::new(Semaphore::new(config.http.max_parallel_downloads)),
+            in_flight: Arc::default(),
+            retry: config.retry_policy,
+            endpoints: config.endpoints.clone(),
+            cache: MetadataCache::new(config.metadata_cache_path(), config.metadata_max_age),
+            offline: config.offline,
+            callback: *config.event_callback,
+        }
+    }
+
+    /// Limit of the download speed, shared by every batch of the manager.
+    ///
+    /// A clone of the limiter can change the limit while an update is running.
+    pub fn bandwidth(&self) -> &BandwidthLimiter {
+        &self.http.bandwidth
+    }
+
+    /// Change the maximum download speed of all the downloads, `None` to remove the limit.
+    /// * `limit` - Bytes per second
+    pub fn set_bandwidth_limit(&self, limit: Option<u64>) {
+        self.http.bandwidth.set_limit(limit);
+    }
+
+    /// HTTP client used for the downloads.
+    pub fn client(&self) -> &reqwest::Client {
+        &self.http.client
+    }
+
+    /// Check if the manager is in offline mode, where nothing is requested.
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Download a batch of files in parallel, reporting the byte progress of the batch.
+    ///
+    /// Files with the same destination are downloaded once.
+    /// Every file is attempted before failing with the ones that could not be downloaded.
+    /// * `on_done` - Called with the number of files done, the number of files and the URL of the last one
+    pub async fn download(
+        &self,
+        downloads: Vec<Download>,
+        mut on_done: impl FnMut(u32, u32, &str),
+    ) -> GrindstoneResult<()> {
+        let mut destinations = HashSet::new();
+        let downloads = downloads
+            .into_iter()
+            .filter(|d| {
+                let unique = destinations.insert(d.file.clone());
+                if !unique {
+                    debug!(
+                        "Skipping duplicate download of {}",
+                        d.file.to_string_lossy()
+                    );
+                }
+                unique
+            })
+            .collect::<Vec<_>>();
+
+        if self.offline {
+            return check_present(downloads, on_done);
+        }
+
+        let tracker = DownloadTracker::for_downloads(self.callback, &downloads);
+        let max = downloads.len() as u32;
+
+        let mut tasks = FuturesUnordered::new();
+        for d in downloads {
+            let manager = self.clone();
+            let tracker = tracker.clone();
+
+            tasks.push(tokio::spawn(async move {
+                manager.download_exclusive(d, &tracker).await
+            }));
+        }
+
+        let mut count = 0;
+        let mut failed = Vec::new();
+        while let Some(joined) = tasks.next().await {
+            let res = match joined {
+                Ok(res) => res,
+                Err(err) => panic::resume_unwind(err.into_panic()),
+            };
+
+            count += 1;
+            match res {
+                Ok(url) => on_done(count, max, &url),
+                Err(err) => failed.push(err),
+            }
+        }
+
+        ensure_downloaded(failed)
+    }
+
+    /// Download a file once no other batch is downloading the same destination.
+    /// The file is then usually present, and only verified.
+    async fn download_exclusive(
+        &self,
+        download: Download,
+        tracker: &DownloadTracker,
+    ) -> Result<String, FailedDownload> {
+        let file = download.file.clone();
+        let lock = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .entry(file.clone())
+            .or_default()
+            .clone();
+
+        let res = {
+            let _guard = lock.lock().await;
+            let _permit = self.permits.acquire().await.unwrap();
+
+            self.download_mirrored(download, tracker).await
+        };
+
+        // Only the map and this task still hold the lock when nobody is waiting for it
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if Arc::strong_count(&lock) == 2 {
+            in_flight.remove(&file);
+        }
+
+        res
+    }
+
+    /// Download a file from the first mirror or endpoint that succeeds.
+    ///
+    /// The mirrors and endpoint of the URL are tried first, then the ones of the alternate URLs.
+    async fn download_mirrored(
+        &self,
+        download: Download,
+        tracker: &DownloadTracker,
+    ) -> Result<String, FailedDownload> {
+        let mut urls = Vec::new();
+        for url in std::iter::once(&download.url).chain(&download.alternate_urls) {
+            for candidate in self.endpoints.candidates(url) {
+                if !urls.contains(&candidate) {
+                    urls.push(candidate);
+                }
+            }
+        }
+        let Some(endpoint) = urls.pop() else {
+            let error = GrindstoneError::NoMirror(download.url.clone());
+            return Err(FailedDownload {
+                url: download.url,
+                file: download.file,
+                error: Box::new(error),
+            });
+        };
+
+        for url in urls {
+            let mirrored = Download {
+                url,
+                ..download.clone()
+            };
+
+            match download_tracked(&self.http, mirrored, tracker, &self.retry).await {
+                Ok(url) => return Ok(url),
+                Err(err) => debug!(
+                    "Download of {} failed ({}), trying the next URL",
+                    err.url, err.error
+                ),
+            }
+        }
+
+        let download = Download {
+            url: endpoint,
+            ..download
+        };
+        download_tracked(&self.http, download, tracker, &self.retry).await
+    }
+
+    /// Get a JSON document from the first mirror or endpoint that succeeds.
+    /// * `url` - URL of the document on the official servers
+    pub async fn fetch_json<T: DeserializeOwned>(&self, url: &str) -> GrindstoneResult<T> {
+        let body = self.fetch_mirrored(url, false).await?;
+
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    /// Like [`DownloadManager::fetch_json`], through the metadata cache of the updater folder.
+    ///
+    /// A cached document younger than the configured max age is used as is,
+    /// an older one is only downloaded again if the server tells it changed.
+    pub async fn fetch_cached_json<T: DeserializeOwned>(&self, url: &str) -> GrindstoneResult<T> {
+        if let Some(cached) = self.cache.get(url).filter(|c| self.cache.is_fresh(c)) {
+            trace!("Using cached {}", url);
+            return Ok(serde_json::from_slice(&cached.body)?);
+        }
+
+        let body = self.fetch_mirrored(url, true).await?;
+
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    /// Get a document from the first mirror or endpoint that succeeds.
+    async fn fetch_mirrored(&self, url: &str, cached: bool) -> GrindstoneResult<Vec<u8>> {
+        if self.offline {
+            return Err(GrindstoneError::Offline(url.to_string()));
+        }
+
+        let fetch_from = |candidate: String| async move {
+            match cached {
+                true => fetch_cached(&self.http, &self.cache, url, &candidate).await,
+                false => fetch(&self.http, &candidate).await,
+            }
+        };
+
+        let mut urls = self.endpoints.candidates(url);
+        let endpoint = urls
+            .pop()
+            .ok_or_else(|| GrindstoneError::NoMirror(url.to_string()))?;
+
+        for candidate in urls {
+            match fetch_from(candidate.clone()).await {
+                Ok(body) => return Ok(body),
+                Err(err) => debug!(
+                    "Fetching {} failed ({}), trying the next mirror",
+                    candidate, err
+                ),
+            }
+        }
+
+        fetch_from(endpoint).await
+    }
+
+    /// Send an API request to the first mirror or endpoint that succeeds,
+    /// with the retry policy and read timeout of the downloads.
+    /// * `url` - URL of the request on the official servers, without query
+    /// * `build` - Build the request for a URL, called again for each attempt
+    pub async fn request_json<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        build: impl Fn(&reqwest::Client, &str) -> reqwest::RequestBuilder,
+    ) -> GrindstoneResult<T> {
+        if self.offline {
+            return Err(GrindstoneError::Offline(url.to_string()));
+        }
+
+        let mut urls = self.endpoints.candidates(url);
+        let endpoint = urls
+            .pop()
+            .ok_or_else(|| GrindstoneError::NoMirror(url.to_string()))?;
+
+        for candidate in urls {
+            match request_json(&self.http, &self.retry, || {
+                build(&self.http.client, &candidate)
+            })
+            .await
+            {
+                Ok(response) => return Ok(response),
+                Err(err) => debug!(
+                    "Request to {} failed ({}), trying the next mirror",
+                    candidate, err
+                ),
+            }
+        }
+
+        request_json(&self.http, &self.retry, || {
+            build(&self.http.client, &endpoint)
+        })
+        .await
+    }
+
+    /// Download a single file.
+    pub async fn download_one(&self, download: Download) -> GrindstoneResult<()> {
+        self.download(vec![download], |_, _, _| {}).await
+    }
+}
+
+/// Check that the files of a batch are present with their expected size, failing with the missing ones.
+fn check_present(
+    downloads: Vec<Download>,
+    mut on_done: impl FnMut(u32, u32, &str),
+) -> GrindstoneResult<()> {
+    let max = downloads.len() as u32;
+    let mut count = 0;
+    let mut missing = Vec::new();
+
+    for d in downloads {
+        let present = fs::metadata(&d.file)
+            .map(|m| m.is_file() && d.size.is_none_or(|size| m.len() == size))
+            .unwrap_or_default();
+
+        match present {
+            true => {
+                count += 1;
+                on_done(count, max, &d.url);
+            }
+            false => missing.push(d.file),
+        }
+    }
+
+    match missing.is_empty() {
+        true => Ok(()),
+        false => Err(GrindstoneError::MissingFiles(missing)),
+    }
+}
+
+/// Maximum download speed shared by every download using the limiter, as a token bucket.
+///
+/// Clones share the same limit, so it can be changed while downloads are running.
+#[derive(Clone, Debug)]
+pub struct BandwidthLimiter {
+    bucket: Arc<Mutex<TokenBucket>>,
+    /// Wakes the downloads waiting for the limit when it changes.
+    changed: Arc<Notify>,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    /// Bytes per second, `None` when unlimited.
+    rate: Option<u64>,
+    /// Bytes that can be received right away, negative when the downloads are ahead of the limit.
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Add the tokens earned since the last refill, up to one second of data.
+    fn refill(&mut self) {
+        let now = Instant::now();
+        if let Some(rate) = self.rate {
+            let earned = now.duration_since(self.last_refill).as_secs_f64() * rate as f64;
+            self.tokens = (self.tokens + earned).min(rate as f64);
+        }
+        self.last_refill = now;
+    }
+}
+
+impl BandwidthLimiter {
+    /// * `limit` - Bytes per second, `None` or 0 for no limit
+    pub fn new(limit: Option<u64>) -> Self {
+        Self {
+            bucket: Arc::new(Mutex::new(TokenBucket {
+                rate: limit.filter(|l| *l > 0),
+                tokens: 0.0,
+                last_refill: Instant::now(),
+            })),
+            changed: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Current limit in bytes per second, `None` if unlimited.
+    pub fn limit(&self) -> Option<u64> {
+        self.bucket.lock().unwrap().rate
+    }
+
+    /// Change the limit, applied to every download, including the ones already waiting.
+    /// * `limit` - Bytes per second, `None` or 0 for no limit
+    pub fn set_limit(&self, limit: Option<u64>) {
+        {
+            let mut bucket = self.bucket.lock().unwrap();
+            bucket.refill();
+
+            let was_limited = bucket.rate.is_some();
+            bucket.rate = limit.filter(|l| *l > 0);
+            bucket.tokens = match bucket.rate {
+                // Only the debt of the previous limit is kept, at most one second of the new one
+                Some(rate) if was_limited => bucket.tokens.clamp(-(rate as f64), rate as f64),
+                _ => 0.0,
+            };
+        }
+
+        self.changed.notify_waiters();
+    }
+
+    /// Take tokens for received data, waiting until the limit allows it.
+    pub(crate) async fn consume(&self, bytes: u64) {
+        // Registered before reading the limit, so a change cannot be missed
+        let mut changed = pin!(self.changed.notified());
+
+        let (mut rate, mut owed) = {
+            let mut bucket = self.bucket.lock().unwrap();
+            bucket.refill();
+
+            let Some(rate) = bucket.rate else {
+                return;
+            };
+
+            bucket.tokens -= bytes as f64;
+            if bucket.tokens >= 0.0 {
+                return;
+            }
+            (rate, -bucket.tokens)
+        };
+
+        // The remaining debt is waited at the new rate when the limit changes
+        loop {
+            let started = Instant::now();
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs_f64(owed / rate as f64)) => return,
+                _ = changed.as_mut() => {}
+            }
+
+            owed -= started.elapsed().as_secs_f64() * rate as f64;
+            changed.set(self.changed.notified());
+
+            match self.limit() {
+                Some(limit) if owed > 0.0 => {
+                    rate = limit;
+                    owed = owed.min(limit as f64);
+                }
+                _ => return,
+            }
+        }
+    }
+}
+
+impl Default for BandwidthLimiter {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+/// Manager of a test configuration in a folder, without retries.
+#[cfg(test)]
+pub fn test_manager(folder: &std::path::Path, offline: bool) -> DownloadManager {
+    let mut builder = crate::config::ConfigBuilder::default()
+        .name("test")
+        .minecraft_folder_path(folder)
+        .retry_policy(RetryPolicy::none())
+        .offline(offline);
+    builder.version = crate::version::MinecraftVersionBuilder::default()
+        .version_id("1.20.1")
+        .build();
+
+    DownloadManager::new(&builder.build().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::{ConfigBuilder, Mirror},
+        utils::{download::test_server, fs::test_folder},
+        version::MinecraftVersionBuilder,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn alternate_urls_are_mirrored() {
+        let folder = test_folder("alternate-urls");
+        let url = test_server(vec![("/mirror/file", 200, b"mirrored".to_vec())]).await;
+
+        let mut builder = ConfigBuilder::default()
+            .name("download")
+            .minecraft_folder_path(&folder)
+            .retry_policy(RetryPolicy::none())
+            .add_mirror(
+                Mirror::new().rewrite(format!("{}/official/", url), format!("{}/mirror/", url)),
+            );
+        builder.version = MinecraftVersionBuilder::default()
+            .version_id("1.20.1")
+            .build();
+        let manager = DownloadManager::new(&builder.build().unwrap());
+
+        let download = Download {
+            url: format!("{}/official/missing", url),
+            file: folder.join("file"),
+            sha1: None,
+            size: None,
+            alternate_urls: vec![format!("{}/official/file", url)],
+        };
+        manager
+            .download(vec![download], |_, _, _| {})
+            .await
+            .unwrap();
+        assert_eq!(fs::read(folder.join("file")).unwrap(), b"mirrored");
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[tokio::test]
+    async fn official_fallback_disabled() {
+        let folder = test_folder("official-fallback");
+
+        let mut builder = ConfigBuilder::default()
+            .name("download")
+            .minecraft_folder_path(&folder)
+            .retry_policy(RetryPolicy::none())
+            .official_fallback(false);
+        builder.version = MinecraftVersionBuilder::default()
+            .version_id("1.20.1")
+            .build();
+        let manager = DownloadManager::new(&builder.build().unwrap());
+
+        // Nothing is requested from the official servers
+        let download = Download {
+            url: "https://piston-data.mojang.com/v1/objects/abc/client.jar".to_string(),
+            file: folder.join("client.jar"),
+            sha1: None,
+            size: None,
+            alternate_urls: Vec::new(),
+        };
+        let err = manager.download_one(download).await.unwrap_err();
+        assert!(matches!(
+            err,
+            GrindstoneError::DownloadFailed(failed) if matches!(*failed[0].error, GrindstoneError::NoMirror(_))
+        ));
+        assert!(matches!(
+            manager
+                .fetch_json::<serde_json::Value>(crate::constants::MC_VERSION_MANIFEST_URL)
+                .await,
+            Err(GrindstoneError::NoMirror(_))
+        ));
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn bandwidth_limit() {
+        assert_eq!(BandwidthLimiter::new(Some(0)).limit(), None);
+        assert_eq!(BandwidthLimiter::default().limit(), None);
+
+        let limiter = BandwidthLimiter::new(Some(1024));
+        let shared = limiter.clone();
+        shared.set_limit(Some(2048));
+        assert_eq!(limiter.limit(), Some(2048));
+        shared.set_limit(None);
+        assert_eq!(limiter.limit(), None);
+    }
+
+    #[tokio::test]
+    async fn bandwidth_is_shared() {
+        let limiter = BandwidthLimiter::new(Some(10_000));
+        let shared = limiter.clone();
+
+        // 2000 bytes at 10 kB/s, split between two clones
+        let start = Instant::now();
+        limiter.consume(1000).await;
+        shared.consume(1000).await;
+        assert!(start.elapsed() >= Duration::from_millis(150));
+
+        // Removing the limit applies to the next data
+        shared.set_limit(None);
+        let start = Instant::now();
+        limiter.consume(1_000_000).await;
+        assert!(start.elapsed() < Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn bandwidth_change_wakes_waiting_downloads() {
+        let limiter = BandwidthLimiter::new(Some(1000));
+        let shared = limiter.clone();
+
+        // 5 seconds of data at the initial limit
+        let start = Instant::now();
+        let waiting = tokio::spawn(async move { limiter.consume(5000).await });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!waiting.is_finished());
+
+        shared.set_limit(Some(1_000_000));
+        waiting.await.unwrap();
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        // Removing the limit releases waiting downloads right away
+        shared.set_limit(Some(1000));
+        let limiter = shared.clone();
+        let waiting = tokio::spawn(async move { limiter.consume(5000).await });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let start = Instant::now();
+        shared.set_limit(None);
+        waiting.await.unwrap();
+        assert!(start.elapsed() < Duration::from_millis(500));
+    }
+}
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 #[derive(Debug)]
 pub struct Progress {
     pub current: u32,
@@ -19,10 +21,31 @@ pub enum LibraryInstallationUpdate {
 pub enum AssetInstallationUpdate {
     /// Download status
     Downloading,
-    /// Symlinks
+    /// Placing legacy assets in `assets/virtual` or the instance `resources` folder
     Symlink,
 }
 
+/// Byte progress of a download, part of a batch of downloads.
+#[derive(Clone, Debug)]
+pub struct DownloadProgress {
+    /// The URL of the download.
+    pub url: String,
+    /// The path where the file is saved.
+    pub file: PathBuf,
+    /// Number of files of the batch that are done.
+    pub current_file: usize,
+    /// Number of files of the batch.
+    pub total_files: usize,
+    /// Bytes of the file that already got downloaded.
+    pub downloaded_bytes: u64,
+    /// Total bytes of the file, 0 if unknown.
+    pub total_bytes: u64,
+    /// Bytes of the batch that already got downloaded.
+    pub batch_downloaded_bytes: u64,
+    /// Total bytes of the batch, files that were already present are not counted.
+    pub batch_total_bytes: u64,
+}
+
 #[derive(Debug)]
 pub enum EventType {
     Starting,
@@ -35,6 +58,9 @@ pub enum EventType {
     Assets(Progress, AssetInstallationUpdate),
     DownloadLogConfig,
     DownloadClient,
+    ApplyDefaultOptions,
+    /// Byte progress of the current download, sent often, without being logged
+    Download(DownloadProgress),
 }
 
 #[derive(Debug)]
@@ -0,0 +1,72 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::version::MinecraftVersion;
+
+/// Information about an instance, stored in its `instance.json`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Instance {
+    /// Name of the instance, also used as folder name.
+    pub name: String,
+    /// Game version and mod loader of the instance.
+    pub version: MinecraftVersion,
+    /// Java settings used to launch the instance.
+    #[serde(default)]
+    pub jvm: JvmSettings,
+    /// Creation time.
+    #[serde(with = "time::serde::rfc3339")]
+    pub created: OffsetDateTime,
+    /// Last time the instance was played.
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub last_played: Option<OffsetDateTime>,
+    /// Free data attached to the instance by the launcher (icon, group, notes...).
+    #[serde(default)]
+    pub metadata: HashMap<String, serde_json::Value>,
+}
+
+impl Instance {
+    pub fn new<S: Into<String>>(name: S, version: MinecraftVersion) -> Self {
+        Self {
+            name: name.into(),
+            version,
+            jvm: JvmSettings::default(),
+            created: OffsetDateTime::now_utc(),
+            last_played: None,
+            metadata: HashMap::new(),
+        }
+    }
+}
+
+/// Java settings of an instance.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct JvmSettings {
+    /// Java executable to use instead of the runtime required by the version.
+    pub java_path: Option<PathBuf>,
+    /// Initial heap size, in megabytes (`-Xms`).
+    pub min_memory: Option<u32>,
+    /// Maximum heap size, in megabytes (`-Xmx`).
+    pub max_memory: Option<u32>,
+    /// Additional JVM arguments.
+    #[serde(default)]
+    pub arguments: Vec<String>,
+}
+
+impl JvmSettings {
+    /// Builds the JVM arguments for the memory and the additional arguments.
+    pub fn to_arguments(&self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        if let Some(min) = self.min_memory {
+            args.push(format!("-Xms{}M", min));
+        }
+
+        if let Some(max) = self.max_memory {
+            args.push(format!("-Xmx{}M", max));
+        }
+
+        args.extend(self.arguments.iter().cloned());
+        args
+    }
+}
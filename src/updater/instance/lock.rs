@@ -0,0 +1,59 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use log::{trace, warn};
+
+use crate::errors::{GrindstoneError, GrindstoneResult};
+
+/// Name of the lock file inside an instance folder.
+pub const LOCK_FILE: &str = ".lock";
+
+/// Exclusive lock on an instance, released when dropped.
+///
+/// While locked, the instance cannot be renamed, cloned, deleted or locked again.
+#[derive(Debug)]
+pub struct InstanceLock {
+    name: String,
+    path: PathBuf,
+}
+
+impl InstanceLock {
+    /// Acquire the lock of the instance folder.
+    pub(super) fn acquire(name: &str, instance_path: &Path) -> GrindstoneResult<Self> {
+        let path = instance_path.join(LOCK_FILE);
+
+        let mut file = match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                return Err(GrindstoneError::InstanceLocked(name.to_string()));
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        // The process ID helps to find out who holds a stale lock
+        write!(file, "{}", std::process::id())?;
+        trace!("Locked instance {}", name);
+
+        Ok(Self {
+            name: name.to_string(),
+            path,
+        })
+    }
+
+    /// Name of the locked instance.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        match fs::remove_file(&self.path) {
+            Ok(_) => trace!("Unlocked instance {}", self.name),
+            Err(err) => warn!("Could not unlock instance {}: {}", self.name, err),
+        }
+    }
+}
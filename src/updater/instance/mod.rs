@@ -0,0 +1,386 @@
+mod data;
+mod lock;
+mod multimc;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::{debug, trace};
+use time::OffsetDateTime;
+
+use crate::{
+    config::{Config, ConfigBuilder},
+    errors::{GrindstoneError, GrindstoneResult},
+    utils::fs::{copy_folder, write_atomic},
+};
+
+pub use data::{Instance, JvmSettings};
+pub use lock::InstanceLock;
+pub use multimc::MultiMcInstance;
+
+use lock::LOCK_FILE;
+
+/// Name of the file holding the instance information.
+const INSTANCE_FILE: &str = "instance.json";
+
+/// Check if a path, relative to an instance folder, is a file of the instance manager.
+pub(crate) fn is_manager_file(path: &Path) -> bool {
+    path == Path::new(INSTANCE_FILE) || path == Path::new(LOCK_FILE)
+}
+
+/// Characters that cannot be used in an instance name, as it is used as folder name.
+const FORBIDDEN_CHARACTERS: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+/// Manages the instances stored in the `instances` folder of the updater folder.
+#[derive(Clone, Debug)]
+pub struct InstanceManager {
+    folder_path: PathBuf,
+    instances_path: PathBuf,
+}
+
+impl InstanceManager {
+    /// Create a manager for the given updater folder.
+    /// * `folder` - Path to the updater folder, the one given to [`ConfigBuilder::minecraft_folder_path`]
+    pub fn new(folder: impl AsRef<Path>) -> Self {
+        let folder_path = folder.as_ref().to_path_buf();
+
+        Self {
+            instances_path: folder_path.join("instances"),
+            folder_path,
+        }
+    }
+
+    /// Create a manager for the updater folder of a configuration.
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            folder_path: config.updater_folder(),
+            instances_path: config.instances_path(),
+        }
+    }
+
+    /// Path to the folder of an instance.
+    ///
+    /// Fails if the name cannot be used as folder name, so a name never points outside of the instances folder.
+    pub fn instance_path(&self, name: &str) -> GrindstoneResult<PathBuf> {
+        validate_name(name)?;
+
+        Ok(self.instances_path.join(name))
+    }
+
+    /// List all instances, sorted by name.
+    ///
+    /// Folders without a readable `instance.json` are ignored.
+    pub fn list(&self) -> GrindstoneResult<Vec<Instance>> {
+        if !self.instances_path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut instances = Vec::new();
+
+        for entry in fs::read_dir(&self.instances_path)? {
+            let path = entry?.path();
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            if !path.join(INSTANCE_FILE).is_file() {
+                continue;
+            }
+
+            match self.get(&name) {
+                Ok(instance) => instances.push(instance),
+                Err(err) => debug!("Skipping instance {}: {}", name, err),
+            }
+        }
+
+        instances.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(instances)
+    }
+
+    /// Read an instance.
+    pub fn get(&self, name: &str) -> GrindstoneResult<Instance> {
+        let path = self.instance_path(name)?.join(INSTANCE_FILE);
+
+        if !path.is_file() {
+            return Err(GrindstoneError::InstanceNotFound(name.to_string()));
+        }
+
+        let file = fs::File::open(path)?;
+        let mut instance = serde_json::from_reader::<_, Instance>(file)?;
+        // The folder name is authoritative
+        instance.name = name.to_string();
+
+        Ok(instance)
+    }
+
+    /// Check if an instance exists.
+    pub fn exists(&self, name: &str) -> bool {
+        self.instance_path(name)
+            .is_ok_and(|path| path.join(INSTANCE_FILE).is_file())
+    }
+
+    /// Create a new instance.
+    pub fn create(&self, instance: Instance) -> GrindstoneResult<Instance> {
+        let path = self.instance_path(&instance.name)?;
+        if path.exists() {
+            return Err(GrindstoneError::InstanceAlreadyExists(instance.name));
+        }
+
+        trace!("Creating instance {}", instance.name);
+        fs::create_dir_all(&path)?;
+        self.save(&instance)?;
+
+        Ok(instance)
+    }
+
+    /// Write the information of an existing instance.
+    pub fn save(&self, instance: &Instance) -> GrindstoneResult<()> {
+        let path = self.instance_path(&instance.name)?;
+        if !path.is_dir() {
+            return Err(GrindstoneError::InstanceNotFound(instance.name.clone()));
+        }
+
+        let json = serde_json::to_vec_pretty(instance)?;
+        write_atomic(path.join(INSTANCE_FILE), json)
+    }
+
+    /// Rename an instance and its folder.
+    pub fn rename(&self, name: &str, new_name: &str) -> GrindstoneResult<Instance> {
+        let new_path = self.instance_path(new_name)?;
+
+        let mut instance = self.get(name)?;
+        self.check_unlocked(name)?;
+
+        if new_path.exists() {
+            return Err(GrindstoneError::InstanceAlreadyExists(new_name.to_string()));
+        }
+
+        trace!("Renaming instance {} to {}", name, new_name);
+        fs::rename(self.instance_path(name)?, &new_path)?;
+
+        instance.name = new_name.to_string();
+        self.save(&instance)?;
+
+        Ok(instance)
+    }
+
+    /// Copy an instance, with all its game files, under a new name.
+    pub fn clone_instance(&self, name: &str, new_name: &str) -> GrindstoneResult<Instance> {
+        let new_path = self.instance_path(new_name)?;
+
+        let mut instance = self.get(name)?;
+        self.check_unlocked(name)?;
+
+        if new_path.exists() {
+            return Err(GrindstoneError::InstanceAlreadyExists(new_name.to_string()));
+        }
+
+        trace!("Cloning instance {} to {}", name, new_name);
+        copy_folder(self.instance_path(name)?, &new_path, |path| {
+            path == Path::new(LOCK_FILE)
+        })?;
+
+        instance.name = new_name.to_string();
+        instance.created = OffsetDateTime::now_utc();
+        instance.last_played = None;
+        self.save(&instance)?;
+
+        Ok(instance)
+    }
+
+    /// Delete an instance and all its game files.
+    pub fn delete(&self, name: &str) -> GrindstoneResult<()> {
+        let path = self.instance_path(name)?;
+        if !path.join(INSTANCE_FILE).is_file() {
+            return Err(GrindstoneError::InstanceNotFound(name.to_string()));
+        }
+        self.check_unlocked(name)?;
+
+        trace!("Deleting instance {}", name);
+        fs::remove_dir_all(path)?;
+
+        Ok(())
+    }
+
+    /// Lock an instance, for example while it is updated or played.
+    /// The lock is released when the returned guard is dropped.
+    pub fn lock(&self, name: &str) -> GrindstoneResult<InstanceLock> {
+        let path = self.instance_path(name)?;
+        if !path.join(INSTANCE_FILE).is_file() {
+            return Err(GrindstoneError::InstanceNotFound(name.to_string()));
+        }
+
+        InstanceLock::acquire(name, &path)
+    }
+
+    /// Check if an instance is locked.
+    pub fn is_locked(&self, name: &str) -> bool {
+        self.instance_path(name)
+            .is_ok_and(|path| path.join(LOCK_FILE).exists())
+    }
+
+    /// Remove the lock of an instance, left behind by a process that crashed.
+    pub fn force_unlock(&self, name: &str) -> GrindstoneResult<()> {
+        let path = self.instance_path(name)?.join(LOCK_FILE);
+
+        if path.exists() {
+            debug!("Removing lock of instance {}", name);
+            fs::remove_file(path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Builds a configuration for the given instance.
+    pub fn config_builder(&self, name: &str) -> GrindstoneResult<ConfigBuilder> {
+        let instance = self.get(name)?;
+
+        let mut builder = ConfigBuilder::default()
+            .minecraft_folder_path(&self.folder_path)
+            .name(instance.name);
+        builder.version = instance.version;
+
+        Ok(builder)
+    }
+
+    fn check_unlocked(&self, name: &str) -> GrindstoneResult<()> {
+        if self.is_locked(name) {
+            return Err(GrindstoneError::InstanceLocked(name.to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+/// Replace the characters that cannot be used in an instance name.
+pub fn sanitize_name(name: &str) -> String {
+    let name = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if FORBIDDEN_CHARACTERS.contains(&c) || c.is_control() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect::<String>();
+
+    match name.as_str() {
+        "" | "." | ".." => "instance".to_string(),
+        _ => name,
+    }
+}
+
+/// Check that an instance name can be used as folder name.
+fn validate_name(name: &str) -> GrindstoneResult<()> {
+    let valid = !name.trim().is_empty()
+        && name != "."
+        && name != ".."
+        && name.trim() == name
+        && !name.contains(FORBIDDEN_CHARACTERS)
+        && !name.chars().any(char::is_control);
+
+    if !valid {
+        return Err(GrindstoneError::InvalidInstanceName(name.to_string()));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{utils::fs::test_folder, version::MinecraftVersionBuilder};
+
+    use super::*;
+
+    fn instance(name: &str) -> Instance {
+        Instance::new(
+            name,
+            MinecraftVersionBuilder::default()
+                .version_id("1.19.2")
+                .build(),
+        )
+    }
+
+    #[test]
+    fn lifecycle() {
+        let folder = test_folder("instances");
+        let manager = InstanceManager::new(&folder);
+
+        manager.create(instance("Survival")).unwrap();
+        assert!(manager.exists("Survival"));
+        assert!(matches!(
+            manager.create(instance("Survival")),
+            Err(GrindstoneError::InstanceAlreadyExists(_))
+        ));
+
+        let lock = manager.lock("Survival").unwrap();
+        assert!(manager.is_locked("Survival"));
+        assert!(matches!(
+            manager.delete("Survival"),
+            Err(GrindstoneError::InstanceLocked(_))
+        ));
+        drop(lock);
+
+        manager.clone_instance("Survival", "Copy").unwrap();
+        manager.rename("Copy", "Creative").unwrap();
+        let names = manager
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|i| i.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Creative", "Survival"]);
+
+        manager.delete("Survival").unwrap();
+        assert!(!manager.exists("Survival"));
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn reject_names_outside_of_instances_folder() {
+        let folder = test_folder("instance-names");
+        let manager = InstanceManager::new(&folder);
+        // Looks like an instance when seen from the instances folder
+        fs::write(folder.join(INSTANCE_FILE), "{}").unwrap();
+        fs::write(folder.join(LOCK_FILE), "").unwrap();
+        manager.create(instance("Valid")).unwrap();
+
+        fn invalid<T>(res: GrindstoneResult<T>) -> bool {
+            matches!(res, Err(GrindstoneError::InvalidInstanceName(_)))
+        }
+
+        for name in ["..", ".", "", "../Valid", "a/b", " padded"] {
+            assert!(invalid(manager.instance_path(name)), "{}", name);
+            assert!(invalid(manager.get(name)), "{}", name);
+            assert!(invalid(manager.delete(name)), "{}", name);
+            assert!(invalid(manager.lock(name)), "{}", name);
+            assert!(invalid(manager.force_unlock(name)), "{}", name);
+            assert!(invalid(manager.create(instance(name))), "{}", name);
+            assert!(invalid(manager.rename("Valid", name)), "{}", name);
+            assert!(invalid(manager.clone_instance("Valid", name)), "{}", name);
+            assert!(!manager.exists(name));
+            assert!(!manager.is_locked(name));
+        }
+
+        assert!(folder.join(INSTANCE_FILE).exists());
+        assert!(folder.join(LOCK_FILE).exists());
+        assert!(manager.exists("Valid"));
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn sanitize() {
+        assert_eq!(sanitize_name(" My: Pack? "), "My_ Pack_");
+        assert_eq!(sanitize_name("a/b\\c"), "a_b_c");
+        assert_eq!(sanitize_name(".."), "instance");
+        assert!(validate_name(&sanitize_name("../\u{7}x")).is_ok());
+    }
+}
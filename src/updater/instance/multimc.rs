@@ -0,0 +1,356 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::{debug, trace, warn};
+use serde::Deserialize;
+use time::OffsetDateTime;
+
+use crate::{
+    errors::{GrindstoneError, GrindstoneResult},
+    utils::fs::copy_folder,
+    version::{MinecraftVersion, VersionType},
+};
+
+use super::{sanitize_name, Instance, InstanceManager, JvmSettings, INSTANCE_FILE, LOCK_FILE};
+
+/// Instance settings file of MultiMC and Prism Launcher.
+const INSTANCE_CFG: &str = "instance.cfg";
+/// Components (game version and loaders) of the instance.
+const MMC_PACK: &str = "mmc-pack.json";
+/// Game directory names, Prism uses the first one and old MultiMC versions the second.
+const GAME_DIRECTORIES: &[&str] = &[".minecraft", "minecraft"];
+
+/// Component UIDs mapped to Grindstone versions.
+const MINECRAFT_UID: &str = "net.minecraft";
+const FABRIC_UID: &str = "net.fabricmc.fabric-loader";
+const QUILT_UID: &str = "org.quiltmc.quilt-loader";
+const FORGE_UID: &str = "net.minecraftforge";
+const NEOFORGE_UID: &str = "net.neoforged";
+
+/// An instance of MultiMC or Prism Launcher.
+#[derive(Clone, Debug)]
+pub struct MultiMcInstance {
+    /// Name of the instance.
+    pub name: String,
+    /// Game version and mod loader.
+    pub version: MinecraftVersion,
+    /// Java settings, only set when the instance overrides the global ones.
+    pub jvm: JvmSettings,
+    /// Icon key of the launcher.
+    pub icon: Option<String>,
+    /// Notes written by the player.
+    pub notes: Option<String>,
+    /// Last time the instance was played.
+    pub last_played: Option<OffsetDateTime>,
+    /// Game directory of the instance, if it has one.
+    pub game_directory: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct MmcPack {
+    components: Vec<MmcComponent>,
+}
+
+#[derive(Deserialize)]
+struct MmcComponent {
+    uid: String,
+    version: Option<String>,
+    #[serde(alias = "cachedVersion")]
+    cached_version: Option<String>,
+}
+
+impl MmcComponent {
+    fn version(&self) -> Option<String> {
+        self.version.clone().or_else(|| self.cached_version.clone())
+    }
+}
+
+impl MultiMcInstance {
+    /// Read a MultiMC or Prism Launcher instance folder.
+    pub fn read(path: impl AsRef<Path>) -> GrindstoneResult<Self> {
+        let path = path.as_ref();
+
+        let cfg_path = path.join(INSTANCE_CFG);
+        if !cfg_path.is_file() {
+            return Err(GrindstoneError::InvalidImport(format!(
+                "{} has no {}",
+                path.to_string_lossy(),
+                INSTANCE_CFG
+            )));
+        }
+        let cfg = parse_cfg(&fs::read_to_string(cfg_path)?);
+
+        let pack_path = path.join(MMC_PACK);
+        if !pack_path.is_file() {
+            return Err(GrindstoneError::InvalidImport(format!(
+                "{} has no {}, instances from MultiMC 0.6 and older are not supported",
+                path.to_string_lossy(),
+                MMC_PACK
+            )));
+        }
+        let pack = serde_json::from_reader::<_, MmcPack>(fs::File::open(pack_path)?)?;
+
+        let folder_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let game_directory = GAME_DIRECTORIES
+            .iter()
+            .map(|dir| path.join(dir))
+            .find(|dir| dir.is_dir());
+
+        Ok(Self {
+            name: cfg.get("name").cloned().unwrap_or(folder_name),
+            version: map_components(&pack.components)?,
+            jvm: read_jvm_settings(&cfg),
+            icon: cfg.get("iconKey").filter(|k| *k != "default").cloned(),
+            notes: cfg
+                .get("notes")
+                .filter(|n| !n.is_empty())
+                .map(|n| n.replace("\\n", "\n")),
+            last_played: cfg
+                .get("lastLaunchTime")
+                .and_then(|t| t.parse::<i64>().ok())
+                .filter(|t| *t > 0)
+                .and_then(|ms| {
+                    OffsetDateTime::from_unix_timestamp_nanos(ms as i128 * 1_000_000).ok()
+                }),
+            game_directory,
+        })
+    }
+}
+
+impl InstanceManager {
+    /// Import a MultiMC or Prism Launcher instance.
+    ///
+    /// The game directory is copied into a new instance, the original one is left untouched.
+    /// * `path` - Folder of the instance to import
+    /// * `name` - Name of the new instance, defaults to the name of the imported one
+    pub fn import_multimc(
+        &self,
+        path: impl AsRef<Path>,
+        name: Option<&str>,
+    ) -> GrindstoneResult<Instance> {
+        let source = MultiMcInstance::read(&path)?;
+        let name = name
+            .map(String::from)
+            .unwrap_or_else(|| sanitize_name(&source.name));
+
+        trace!("Importing MultiMC instance {} as {}", source.name, name);
+
+        let mut instance = Instance::new(name, source.version);
+        instance.jvm = source.jvm;
+        instance.last_played = source.last_played;
+        instance
+            .metadata
+            .insert("imported_from".to_string(), "multimc".into());
+        if let Some(icon) = source.icon {
+            instance.metadata.insert("icon".to_string(), icon.into());
+        }
+        if let Some(notes) = source.notes {
+            instance.metadata.insert("notes".to_string(), notes.into());
+        }
+
+        let instance = self.create(instance)?;
+
+        if let Some(game_directory) = &source.game_directory {
+            let dest = self.instance_path(&instance.name)?;
+
+            let skip =
+                |path: &Path| path == Path::new(INSTANCE_FILE) || path == Path::new(LOCK_FILE);
+            if let Err(err) = copy_folder(game_directory, &dest, skip) {
+                warn!("Import of {} failed, removing it", instance.name);
+                fs::remove_dir_all(&dest)?;
+                return Err(err);
+            }
+        } else {
+            debug!("Imported instance has no game directory");
+        }
+
+        Ok(instance)
+    }
+}
+
+/// Parse the `key=value` lines of `instance.cfg`.
+fn parse_cfg(content: &str) -> HashMap<String, String> {
+    content
+        .lines()
+        .filter(|line| !line.starts_with('[') && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| {
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .unwrap_or(value);
+
+            (key.trim().to_string(), value.to_string())
+        })
+        .collect()
+}
+
+fn read_jvm_settings(cfg: &HashMap<String, String>) -> JvmSettings {
+    let enabled = |key: &str| cfg.get(key).map(|v| v == "true").unwrap_or_default();
+    let memory = |key: &str| cfg.get(key).and_then(|v| v.parse::<u32>().ok());
+
+    let mut jvm = JvmSettings::default();
+
+    if enabled("OverrideMemory") {
+        jvm.min_memory = memory("MinMemAlloc");
+        jvm.max_memory = memory("MaxMemAlloc");
+    }
+
+    if enabled("OverrideJavaArgs") {
+        jvm.arguments = cfg
+            .get("JvmArgs")
+            .map(|args| args.split_whitespace().map(String::from).collect())
+            .unwrap_or_default();
+    }
+
+    if enabled("OverrideJavaLocation") {
+        jvm.java_path = cfg
+            .get("JavaPath")
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from);
+    }
+
+    jvm
+}
+
+/// Map the components of the pack to a game version and a mod loader.
+fn map_components(components: &[MmcComponent]) -> GrindstoneResult<MinecraftVersion> {
+    let find = |uid: &str| {
+        components
+            .iter()
+            .find(|c| c.uid == uid)
+            .and_then(MmcComponent::version)
+    };
+
+    let id = find(MINECRAFT_UID).ok_or_else(|| {
+        GrindstoneError::InvalidImport("The instance has no Minecraft component".to_string())
+    })?;
+
+    let version_type = if let Some(version) = find(FABRIC_UID) {
+        VersionType::Fabric(version)
+    } else if let Some(version) = find(QUILT_UID) {
+        VersionType::Quilt(version)
+    } else if let Some(version) = find(FORGE_UID) {
+        VersionType::Forge(version)
+    } else if let Some(version) = find(NEOFORGE_UID) {
+        VersionType::NeoForge(version)
+    } else {
+        VersionType::Vanilla
+    };
+
+    Ok(MinecraftVersion { id, version_type })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::fs::test_folder;
+
+    use super::*;
+
+    const CFG: &str = r#"[General]
+ConfigVersion=1.2
+name="Better Survival"
+iconKey=flame
+notes=First line\nSecond line
+lastLaunchTime=1695312000000
+OverrideMemory=true
+MinMemAlloc=1024
+MaxMemAlloc=4096
+OverrideJavaArgs=false
+JvmArgs=-XX:+UseG1GC
+OverrideJavaLocation=true
+JavaPath=
+"#;
+
+    const PACK: &str = r#"{
+        "formatVersion": 1,
+        "components": [
+            {"uid": "org.lwjgl3", "version": "3.3.1"},
+            {"uid": "net.minecraft", "version": "1.20.1", "important": true},
+            {"uid": "net.fabricmc.intermediary", "version": "1.20.1"},
+            {"uid": "net.fabricmc.fabric-loader", "cachedVersion": "0.14.21"}
+        ]
+    }"#;
+
+    fn write_instance(path: &Path) {
+        fs::create_dir_all(path.join(".minecraft/mods")).unwrap();
+        fs::write(path.join(INSTANCE_CFG), CFG).unwrap();
+        fs::write(path.join(MMC_PACK), PACK).unwrap();
+        fs::write(path.join(".minecraft/mods/sodium.jar"), b"jar").unwrap();
+        fs::write(path.join(".minecraft").join(INSTANCE_FILE), b"{}").unwrap();
+    }
+
+    #[test]
+    fn read_instance() {
+        let folder = test_folder("multimc");
+        write_instance(&folder);
+
+        let instance = MultiMcInstance::read(&folder).unwrap();
+        assert_eq!(instance.name, "Better Survival");
+        assert_eq!(instance.version.id, "1.20.1");
+        assert_eq!(
+            instance.version.version_type,
+            VersionType::Fabric("0.14.21".to_string())
+        );
+        assert_eq!(instance.icon.as_deref(), Some("flame"));
+        assert_eq!(instance.notes.as_deref(), Some("First line\nSecond line"));
+        assert_eq!(
+            instance.last_played.unwrap().unix_timestamp(),
+            1_695_312_000
+        );
+        assert_eq!(instance.game_directory, Some(folder.join(".minecraft")));
+
+        // Only the overridden settings are kept
+        assert_eq!(instance.jvm.min_memory, Some(1024));
+        assert_eq!(instance.jvm.max_memory, Some(4096));
+        assert!(instance.jvm.arguments.is_empty());
+        assert!(instance.jvm.java_path.is_none());
+
+        fs::remove_file(folder.join(MMC_PACK)).unwrap();
+        assert!(matches!(
+            MultiMcInstance::read(&folder),
+            Err(GrindstoneError::InvalidImport(_))
+        ));
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn import_instance() {
+        let folder = test_folder("multimc-import");
+        let source = folder.join("source");
+        write_instance(&source);
+
+        let manager = InstanceManager::new(folder.join("instances"));
+        let instance = manager.import_multimc(&source, None).unwrap();
+        assert_eq!(instance.name, "Better Survival");
+        assert_eq!(instance.jvm.max_memory, Some(4096));
+
+        let path = manager.instance_path(&instance.name).unwrap();
+        assert!(path.join("mods/sodium.jar").is_file());
+        // The instance file of the copy is not overwritten by the imported one
+        assert_eq!(manager.get("Better Survival").unwrap().version.id, "1.20.1");
+        assert!(source.join(".minecraft/mods/sodium.jar").is_file());
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn missing_minecraft_component() {
+        let components = serde_json::from_str::<MmcPack>(
+            r#"{"components": [{"uid": "net.fabricmc.fabric-loader", "version": "0.14.21"}]}"#,
+        )
+        .unwrap()
+        .components;
+
+        assert!(map_components(&components).is_err());
+    }
+}
@@ -1,34 +1,54 @@
 use std::{fs, path::PathBuf};
 
 use crate::{
-    errors::GrindstoneResult,
+    errors::{GrindstoneError, GrindstoneResult},
     event::EventType,
     invoke_callback,
-    minecraft::{java::Java, Client, Library, LoggingInfo, VersionData, VersionsManifest},
+    minecraft::{
+        java::Java, options::GameOptions, Client, Library, LoaderProfile, LoggingInfo, VersionData,
+        VersionsManifest,
+    },
 };
 
-use self::config::Config;
+use self::{
+    config::Config,
+    download::DownloadManager,
+    version::{MinecraftVersion, VersionType},
+};
 
 pub mod config;
+pub mod download;
 pub mod event;
+pub mod instance;
+pub mod modpack;
+pub mod mods;
 mod paths;
 pub mod version;
 
 pub struct GrindstoneUpdater {
     pub config: Config,
     pub java_runtime_path: PathBuf,
+    /// Downloads of every installation phase.
+    pub downloads: DownloadManager,
 }
 
 impl GrindstoneUpdater {
     pub fn new(config: Config) -> Self {
         Self {
+            downloads: DownloadManager::new(&config),
             config,
             java_runtime_path: PathBuf::new(),
         }
     }
 
+    /// Install the configured version, or only check that it is installed in offline mode.
+    ///
+    /// Fabric and Quilt are installed with the game.
+    /// Fails with [`GrindstoneError::UnsupportedModLoader`] for versions using Forge or NeoForge,
+    /// which cannot be installed yet.
     pub async fn update(&mut self) -> GrindstoneResult<()> {
         invoke_callback!(self.config, EventType::Starting, "Starting Updater !");
+        check_mod_loader(&self.config.version)?;
 
         invoke_callback!(
             self.config,
@@ -37,14 +57,88 @@ impl GrindstoneUpdater {
         );
         fs::create_dir_all(self.config.dot_minecraft_path())?;
         fs::create_dir_all(self.config.updater_folder())?;
+        fs::create_dir_all(self.config.game_directory())?;
+
+        let v_data = match self.config.offline {
+            true => self.read_version_data()?,
+            false => self.fetch_version_data().await?,
+        };
+
+        // Offline, every phase only checks its files, so all the missing ones are reported together
+        let mut missing = Vec::new();
+
+        // Check if a compatible version of java is available
+        let java = Java::new(self.config.clone());
+        if let Some(path) = missing_files(
+            &mut missing,
+            java.install(v_data.clone(), &self.downloads).await,
+        )? {
+            self.java_runtime_path = path;
+        }
+
+        // Download libraries
+        missing_files(
+            &mut missing,
+            Library::install_libraries(&self.config, v_data.clone(), &self.downloads).await,
+        )?;
+
+        // Download asset index
+        invoke_callback!(
+            &self.config,
+            crate::event::EventType::DownloadAssetIndex,
+            "Downloading assets index"
+        );
+        let asset_index = missing_files(
+            &mut missing,
+            v_data
+                .asset_index
+                .install_index(&self.config, &self.downloads)
+                .await,
+        )?;
+
+        if let Some(asset_index) = asset_index {
+            missing_files(
+                &mut missing,
+                asset_index
+                    .install_assets(&self.config, &v_data.asset_index.id, &self.downloads)
+                    .await,
+            )?;
+        }
+
+        missing_files(
+            &mut missing,
+            LoggingInfo::install_log_patch(&self.config, &v_data, &self.downloads).await,
+        )?;
+
+        missing_files(
+            &mut missing,
+            Client::install(&self.config, &v_data, &self.downloads).await,
+        )?;
+
+        if !missing.is_empty() {
+            return Err(GrindstoneError::MissingFiles(missing));
+        }
+
+        invoke_callback!(
+            &self.config,
+            EventType::ApplyDefaultOptions,
+            "Applying default game options"
+        );
+        GameOptions::apply_defaults(&self.config)?;
+
+        Ok(())
+    }
 
+    /// Get the version data from Minecraft servers and save it, with the version manifest.
+    async fn fetch_version_data(&mut self) -> GrindstoneResult<VersionData> {
         // Check versions on server and download version manifest
         invoke_callback!(
             &self.config,
             crate::event::EventType::DownloadManifest,
             "Downloading version manifest"
         );
-        let manifest = VersionsManifest::fetch().await?;
+        let manifest = VersionsManifest::fetch(&self.downloads).await?;
+        manifest.save(&self.config)?;
 
         // The dev asked for "latest" version, then change it to the latest available
         // game version in the manifest
@@ -56,35 +150,95 @@ impl GrindstoneUpdater {
         let summary = manifest.get_version(&self.config.version)?;
 
         // Fetch version data
-        let v_data = VersionData::fetch(&summary.url).await?;
+        let mut v_data = VersionData::fetch(&self.downloads, &summary.url).await?;
+
+        // Mod loaders patch the version data of the game they run on
+        if let Some(profile) = LoaderProfile::fetch(&self.downloads, &self.config.version).await? {
+            profile.apply(&mut v_data);
+        }
+
         // Save version data
         v_data.save(&self.config).await?;
 
-        // Check if a compatible version of java is available
-        let java = Java::new(self.config.clone());
-        self.java_runtime_path = java.install(v_data.clone()).await?;
+        Ok(v_data)
+    }
 
-        // Download libraries
-        Library::install_libraries(&self.config, v_data.clone()).await?;
+    /// Read the version data saved by a previous online update.
+    fn read_version_data(&mut self) -> GrindstoneResult<VersionData> {
+        if self.config.version.id == "latest" {
+            invoke_callback!(
+                &self.config,
+                crate::event::EventType::DownloadManifest,
+                "Reading saved version manifest"
+            );
+            let manifest = VersionsManifest::read(&self.config)?;
+            self.config.version.id = manifest.latest.release.clone();
+        }
 
-        // Download asset index
-        invoke_callback!(
-            &self.config,
-            crate::event::EventType::DownloadAssetIndex,
-            "Downloading assets index"
-        );
-        let asset_index = v_data.asset_index.fetch_index().await?;
+        let path = self.config.version_data_path();
+        if !path.is_file() {
+            return Err(GrindstoneError::MissingFiles(vec![path]));
+        }
 
-        asset_index.install_assets(&self.config).await?;
+        VersionData::read_version_data(&self.config)
+    }
 
-        LoggingInfo::install_log_patch(&self.config, &v_data).await?;
+    pub fn launch() -> GrindstoneResult<()> {
+        Ok(())
+    }
+}
 
-        Client::install(&self.config, &v_data).await?;
+/// Fail for versions using a mod loader that cannot be installed,
+/// instead of installing the vanilla game in their place.
+fn check_mod_loader(version: &MinecraftVersion) -> GrindstoneResult<()> {
+    let loader = match &version.version_type {
+        VersionType::Vanilla
+        | VersionType::MCP
+        | VersionType::Fabric(_)
+        | VersionType::Quilt(_) => return Ok(()),
+        VersionType::Forge(v) => format!("Forge {}", v),
+        VersionType::NeoForge(v) => format!("NeoForge {}", v),
+    };
+
+    Err(GrindstoneError::UnsupportedModLoader(loader))
+}
 
-        Ok(())
+/// Add the files reported missing by an offline phase, other errors are returned.
+fn missing_files<T>(
+    missing: &mut Vec<PathBuf>,
+    res: GrindstoneResult<T>,
+) -> GrindstoneResult<Option<T>> {
+    match res {
+        Ok(value) => Ok(Some(value)),
+        Err(GrindstoneError::MissingFiles(files)) => {
+            missing.extend(files);
+            Ok(None)
+        }
+        Err(err) => Err(err),
     }
+}
 
-    pub fn launch() -> GrindstoneResult<()> {
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::version::MinecraftVersionBuilder;
+
+    #[test]
+    fn mod_loaders_are_rejected() {
+        let version = |version_type| {
+            MinecraftVersionBuilder::default()
+                .version_id("1.20.1")
+                .version_type(version_type)
+                .build()
+        };
+
+        assert!(check_mod_loader(&version(VersionType::Vanilla)).is_ok());
+        assert!(check_mod_loader(&version(VersionType::Fabric("0.14.21".to_string()))).is_ok());
+        assert!(check_mod_loader(&version(VersionType::Quilt("0.19.1".to_string()))).is_ok());
+        assert!(matches!(
+            check_mod_loader(&version(VersionType::Forge("47.1.0".to_string()))),
+            Err(GrindstoneError::UnsupportedModLoader(loader)) if loader == "Forge 47.1.0"
+        ));
+        assert!(check_mod_loader(&version(VersionType::NeoForge("47.1.79".to_string()))).is_err());
     }
 }
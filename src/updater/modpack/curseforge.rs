@@ -0,0 +1,645 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::{debug, trace, warn};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    constants,
+    download::{Download, DownloadManager},
+    errors::{GrindstoneError, GrindstoneResult},
+    instance::{sanitize_name, Instance, InstanceManager},
+    utils::{
+        archive::{extract_zip, read_entry},
+        fs::safe_relative_path,
+    },
+    version::{MinecraftVersion, VersionType},
+};
+
+use super::{is_override_skipped, OVERRIDES};
+
+/// Name of the manifest inside a CurseForge pack archive.
+pub const MANIFEST: &str = "manifest.json";
+/// Header holding the API key.
+const API_KEY_HEADER: &str = "x-api-key";
+/// Hash algorithm identifier of SHA1 in the API.
+const SHA1_ALGORITHM: u32 = 1;
+
+/// Class identifiers of the API, used to find where a file is installed.
+const RESOURCE_PACK_CLASS: u32 = 12;
+const SHADER_PACK_CLASS: u32 = 6552;
+
+/// Content of `manifest.json`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CurseForgeManifest {
+    /// Game version and mod loaders.
+    pub minecraft: CurseForgeMinecraft,
+    /// Name of the pack.
+    pub name: String,
+    /// Version of the pack.
+    #[serde(default)]
+    pub version: String,
+    /// Author of the pack.
+    #[serde(default)]
+    pub author: String,
+    /// Files to download.
+    #[serde(default)]
+    pub files: Vec<CurseForgeFile>,
+    /// Folder of the archive copied into the instance.
+    #[serde(default = "default_overrides")]
+    pub overrides: String,
+}
+
+/// Game version and mod loaders of a pack.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CurseForgeMinecraft {
+    /// Minecraft version.
+    pub version: String,
+    /// Mod loaders, identified as `<loader>-<version>`.
+    #[serde(default, alias = "modLoaders")]
+    pub mod_loaders: Vec<CurseForgeModLoader>,
+}
+
+/// A mod loader of a pack.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CurseForgeModLoader {
+    /// Loader and version, e.g. `forge-47.2.0`.
+    pub id: String,
+    /// Whether it is the loader used by the pack.
+    #[serde(default)]
+    pub primary: bool,
+}
+
+/// A file of a pack, identified by its project and file IDs.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CurseForgeFile {
+    #[serde(alias = "projectID")]
+    pub project_id: u32,
+    #[serde(alias = "fileID")]
+    pub file_id: u32,
+    /// Optional files are not installed.
+    #[serde(default = "default_required")]
+    pub required: bool,
+}
+
+fn default_overrides() -> String {
+    OVERRIDES.to_string()
+}
+
+fn default_required() -> bool {
+    true
+}
+
+impl CurseForgeManifest {
+    /// Game version and mod loader required by the pack.
+    pub fn version(&self) -> GrindstoneResult<MinecraftVersion> {
+        let loader = self
+            .minecraft
+            .mod_loaders
+            .iter()
+            .find(|l| l.primary)
+            .or_else(|| self.minecraft.mod_loaders.first());
+
+        let version_type = match loader.map(|l| l.id.split_once('-')) {
+            None => VersionType::Vanilla,
+            Some(Some(("forge", version))) => VersionType::Forge(version.to_string()),
+            Some(Some(("neoforge", version))) => VersionType::NeoForge(version.to_string()),
+            Some(Some(("fabric", version))) => VersionType::Fabric(version.to_string()),
+            Some(Some(("quilt", version))) => VersionType::Quilt(version.to_string()),
+            Some(_) => {
+                return Err(GrindstoneError::UnsupportedModLoader(
+                    loader.map(|l| l.id.clone()).unwrap_or_default(),
+                ))
+            }
+        };
+
+        Ok(MinecraftVersion {
+            id: self.minecraft.version.clone(),
+            version_type,
+        })
+    }
+}
+
+/// Client of the CurseForge API, used to resolve the files of a pack.
+///
+/// When its download manager is offline, requests fail with [`GrindstoneError::Offline`].
+#[derive(Clone, Debug)]
+pub struct CurseForgeApi {
+    downloads: DownloadManager,
+    base_url: String,
+    api_key: String,
+}
+
+/// File information returned by the API.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CurseForgeFileInfo {
+    pub id: u32,
+    #[serde(alias = "modId")]
+    pub mod_id: u32,
+    #[serde(alias = "fileName")]
+    pub file_name: String,
+    /// Missing when the author disabled third party downloads.
+    #[serde(alias = "downloadUrl")]
+    pub download_url: Option<String>,
+    /// Size of the file in bytes.
+    #[serde(alias = "fileLength")]
+    pub file_length: Option<u64>,
+    #[serde(default)]
+    pub hashes: Vec<CurseForgeHash>,
+}
+
+/// Hash of a file returned by the API.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CurseForgeHash {
+    pub value: String,
+    pub algo: u32,
+}
+
+/// Project information returned by the API.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CurseForgeMod {
+    pub id: u32,
+    pub name: String,
+    #[serde(alias = "classId")]
+    pub class_id: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct ApiResponse<T> {
+    data: T,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FileIds<'a> {
+    file_ids: &'a [u32],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ModIds<'a> {
+    mod_ids: &'a [u32],
+}
+
+impl CurseForgeFileInfo {
+    /// SHA1 of the file, as hexadecimal string.
+    pub fn sha1(&self) -> Option<&str> {
+        self.hashes
+            .iter()
+            .find(|h| h.algo == SHA1_ALGORITHM)
+            .map(|h| h.value.as_str())
+    }
+}
+
+impl CurseForgeApi {
+    /// Create a client for the official API.
+    /// * `downloads` - Manager whose HTTP client is used, also downloading the files of the packs
+    /// * `api_key` - Key given by the CurseForge console
+    pub fn new<S: Into<String>>(downloads: &DownloadManager, api_key: S) -> Self {
+        Self {
+            downloads: downloads.clone(),
+            base_url: constants::CURSEFORGE_API_URL.to_string(),
+            api_key: api_key.into(),
+        }
+    }
+
+    /// Use another endpoint than the official API.
+    pub fn base_url<S: Into<String>>(mut self, base_url: S) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Fetch the information of several files.
+    pub async fn get_files(&self, file_ids: &[u32]) -> GrindstoneResult<Vec<CurseForgeFileInfo>> {
+        self.post("/v1/mods/files", &FileIds { file_ids }).await
+    }
+
+    /// Fetch the information of several projects.
+    pub async fn get_mods(&self, mod_ids: &[u32]) -> GrindstoneResult<Vec<CurseForgeMod>> {
+        self.post("/v1/mods", &ModIds { mod_ids }).await
+    }
+
+    async fn post<B: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> GrindstoneResult<T> {
+        let url = format!("{}{}", self.base_url, path);
+        trace!("Querying CurseForge API: {}", url);
+
+        let response = self
+            .downloads
+            .request_json::<ApiResponse<T>>(&url, |client, url| {
+                client
+                    .post(url)
+                    .header(API_KEY_HEADER, &self.api_key)
+                    .json(body)
+            })
+            .await?;
+
+        Ok(response.data)
+    }
+}
+
+/// A CurseForge modpack archive.
+#[derive(Clone, Debug)]
+pub struct CurseForgePack {
+    /// Path of the archive.
+    pub path: PathBuf,
+    /// Manifest of the pack.
+    pub manifest: CurseForgeManifest,
+}
+
+impl CurseForgePack {
+    /// Open a CurseForge pack archive and read its manifest.
+    pub fn open(path: impl AsRef<Path>) -> GrindstoneResult<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        let content = read_entry(&path, MANIFEST)?.ok_or_else(|| {
+            GrindstoneError::InvalidImport(format!(
+                "{} has no {}",
+                path.to_string_lossy(),
+                MANIFEST
+            ))
+        })?;
+
+        let manifest = serde_json::from_slice::<CurseForgeManifest>(&content)?;
+
+        Ok(Self { path, manifest })
+    }
+
+    /// Install the pack into a new instance.
+    ///
+    /// The download URLs of the files are resolved with the API, then the files are
+    /// downloaded and checked against their SHA1 and the overrides are extracted.
+    /// The mod loader is recorded in the instance version and installed with the game by
+    /// [`crate::GrindstoneUpdater::update`], which fails with
+    /// [`GrindstoneError::UnsupportedModLoader`] for Forge and NeoForge.
+    /// * `manager` - Manager of the instances
+    /// * `api` - Client of the CurseForge API, its download manager downloads the files
+    /// * `name` - Name of the new instance, defaults to the name of the pack
+    pub async fn install(
+        &self,
+        manager: &InstanceManager,
+        api: &CurseForgeApi,
+        name: Option<&str>,
+    ) -> GrindstoneResult<Instance> {
+        let version = self.manifest.version()?;
+        let name = name
+            .map(String::from)
+            .unwrap_or_else(|| sanitize_name(&self.manifest.name));
+
+        // Resolved before creating the instance, most failures come from the API
+        let downloads = self.resolve_downloads(api).await?;
+
+        trace!("Installing pack {} as {}", self.manifest.name, name);
+
+        let mut instance = Instance::new(name, version);
+        instance
+            .metadata
+            .insert("imported_from".to_string(), "curseforge".into());
+        instance
+            .metadata
+            .insert("pack_name".to_string(), self.manifest.name.clone().into());
+        instance.metadata.insert(
+            "pack_version".to_string(),
+            self.manifest.version.clone().into(),
+        );
+
+        let instance = manager.create(instance)?;
+        let instance_path = manager.instance_path(&instance.name)?;
+
+        if let Err(err) = self.install_files(&instance_path, api, downloads).await {
+            warn!("Installation of {} failed, removing it", instance.name);
+            fs::remove_dir_all(&instance_path)?;
+            return Err(err);
+        }
+
+        Ok(instance)
+    }
+
+    async fn install_files(
+        &self,
+        instance_path: &Path,
+        api: &CurseForgeApi,
+        downloads: Vec<(PathBuf, CurseForgeFileInfo)>,
+    ) -> GrindstoneResult<()> {
+        let downloads = downloads
+            .into_iter()
+            .map(|(folder, file)| {
+                let file_name = safe_relative_path(&file.file_name)
+                    .filter(|p| p.components().count() == 1)
+                    .ok_or_else(|| {
+                        GrindstoneError::InvalidImport(format!(
+                            "Unsafe file name {}",
+                            file.file_name
+                        ))
+                    })?;
+
+                Ok(Download {
+                    // Files without URL were rejected when resolving them
+                    url: file.download_url.clone().unwrap_or_default(),
+                    file: instance_path.join(folder).join(file_name),
+                    sha1: file.sha1().map(hex::decode).transpose()?,
+                    size: file.file_length,
+                    alternate_urls: Vec::new(),
+                })
+            })
+            .collect::<GrindstoneResult<Vec<_>>>()?;
+
+        api.downloads
+            .download(downloads, |_, _, url| {
+                trace!("Downloaded pack file {}", url)
+            })
+            .await?;
+
+        debug!("Extracting overrides");
+        extract_zip(
+            &self.path,
+            instance_path,
+            &self.manifest.overrides,
+            is_override_skipped,
+        )?;
+
+        Ok(())
+    }
+
+    /// Resolve the files of the pack, with the folder they are installed in.
+    async fn resolve_downloads(
+        &self,
+        api: &CurseForgeApi,
+    ) -> GrindstoneResult<Vec<(PathBuf, CurseForgeFileInfo)>> {
+        let file_ids = self
+            .manifest
+            .files
+            .iter()
+            .filter(|f| f.required)
+            .map(|f| f.file_id)
+            .collect::<Vec<_>>();
+
+        if file_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let files = api.get_files(&file_ids).await?;
+
+        let missing = file_ids
+            .iter()
+            .filter(|id| !files.iter().any(|f| f.id == **id))
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            return Err(GrindstoneError::InvalidImport(format!(
+                "Unknown files: {}",
+                missing.join(", ")
+            )));
+        }
+
+        let mut mod_ids = files.iter().map(|f| f.mod_id).collect::<Vec<_>>();
+        mod_ids.sort_unstable();
+        mod_ids.dedup();
+        let mods = api
+            .get_mods(&mod_ids)
+            .await?
+            .into_iter()
+            .map(|m| (m.id, m))
+            .collect::<HashMap<_, _>>();
+
+        // Files of authors who disabled third party downloads must be added by hand
+        let blocked = files
+            .iter()
+            .filter(|f| f.download_url.is_none())
+            .map(|f| {
+                mods.get(&f.mod_id)
+                    .map(|m| format!("{} ({})", m.name, f.file_name))
+                    .unwrap_or_else(|| f.file_name.clone())
+            })
+            .collect::<Vec<_>>();
+        if !blocked.is_empty() {
+            return Err(GrindstoneError::InvalidImport(format!(
+                "Files cannot be downloaded: {}",
+                blocked.join(", ")
+            )));
+        }
+
+        let downloads = files
+            .into_iter()
+            .map(|file| {
+                let folder = match mods.get(&file.mod_id).and_then(|m| m.class_id) {
+                    Some(RESOURCE_PACK_CLASS) => "resourcepacks",
+                    Some(SHADER_PACK_CLASS) => "shaderpacks",
+                    _ => "mods",
+                };
+
+                (PathBuf::from(folder), file)
+            })
+            .collect();
+
+        Ok(downloads)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        download::test_manager,
+        utils::{download::test_server, fs::test_folder},
+    };
+
+    use super::*;
+
+    const MANIFEST_JSON: &str = r#"{
+        "minecraft": {
+            "version": "1.20.1",
+            "modLoaders": [
+                {"id": "forge-47.1.0", "primary": false},
+                {"id": "neoforge-47.1.79", "primary": true}
+            ]
+        },
+        "manifestType": "minecraftModpack",
+        "manifestVersion": 1,
+        "name": "Example Pack",
+        "version": "1.0.0",
+        "author": "Someone",
+        "files": [
+            {"projectID": 238222, "fileID": 4712633, "required": true},
+            {"projectID": 32274, "fileID": 3872822}
+        ],
+        "overrides": "overrides"
+    }"#;
+
+    fn manifest() -> CurseForgeManifest {
+        serde_json::from_str(MANIFEST_JSON).unwrap()
+    }
+
+    #[test]
+    fn parse_manifest() {
+        let manifest = manifest();
+
+        assert_eq!(manifest.name, "Example Pack");
+        assert_eq!(manifest.files[0].project_id, 238222);
+        assert_eq!(manifest.files[0].file_id, 4712633);
+        // Files are required unless stated otherwise
+        assert!(manifest.files[1].required);
+
+        let version = manifest.version().unwrap();
+        assert_eq!(version.id, "1.20.1");
+        assert_eq!(
+            version.version_type,
+            VersionType::NeoForge("47.1.79".to_string())
+        );
+    }
+
+    #[test]
+    fn loader_without_primary() {
+        let mut manifest = manifest();
+        manifest
+            .minecraft
+            .mod_loaders
+            .iter_mut()
+            .for_each(|l| l.primary = false);
+        assert_eq!(
+            manifest.version().unwrap().version_type,
+            VersionType::Forge("47.1.0".to_string())
+        );
+
+        manifest.minecraft.mod_loaders.clear();
+        assert_eq!(
+            manifest.version().unwrap().version_type,
+            VersionType::Vanilla
+        );
+    }
+
+    #[test]
+    fn unknown_loader() {
+        let mut manifest = manifest();
+        manifest.minecraft.mod_loaders = vec![CurseForgeModLoader {
+            id: "liteloader-1.12".to_string(),
+            primary: true,
+        }];
+
+        assert!(matches!(
+            manifest.version(),
+            Err(GrindstoneError::UnsupportedModLoader(id)) if id == "liteloader-1.12"
+        ));
+    }
+
+    #[test]
+    fn minimal_manifest() {
+        let manifest = serde_json::from_str::<CurseForgeManifest>(
+            r#"{"minecraft": {"version": "1.12.2"}, "name": "Pack"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.overrides, OVERRIDES);
+        assert!(manifest.files.is_empty());
+    }
+
+    #[test]
+    fn file_info_sha1() {
+        let info = serde_json::from_str::<CurseForgeFileInfo>(
+            r#"{
+                "id": 4712633, "modId": 238222, "fileName": "jei.jar", "downloadUrl": null,
+                "hashes": [{"value": "md5hash", "algo": 2}, {"value": "sha1hash", "algo": 1}]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(info.sha1(), Some("sha1hash"));
+        assert!(info.download_url.is_none());
+    }
+
+    #[tokio::test]
+    async fn offline_install() {
+        let folder = test_folder("curseforge-offline");
+        let path = folder.join("pack.zip");
+
+        let mut zip = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+        zip.start_file(MANIFEST, zip::write::FileOptions::default())
+            .unwrap();
+        std::io::Write::write_all(&mut zip, MANIFEST_JSON.as_bytes()).unwrap();
+        zip.finish().unwrap();
+
+        let manager = InstanceManager::new(folder.join("instances"));
+        let api = CurseForgeApi::new(&test_manager(&folder, true), "key");
+        let res = CurseForgePack::open(&path)
+            .unwrap()
+            .install(&manager, &api, None)
+            .await;
+
+        assert!(matches!(res, Err(GrindstoneError::Offline(_))));
+        assert!(!manager.exists("Example Pack"));
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[tokio::test]
+    async fn install_pack() {
+        let folder = test_folder("curseforge-install");
+        let path = folder.join("pack.zip");
+
+        let mut zip = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+        for (name, content) in [
+            (
+                MANIFEST,
+                r#"{
+                    "minecraft": {"version": "1.20.1"},
+                    "name": "Test Pack",
+                    "files": [
+                        {"projectID": 1, "fileID": 10},
+                        {"projectID": 2, "fileID": 20, "required": false}
+                    ]
+                }"#,
+            ),
+            ("overrides/config/mod.toml", "enabled = true"),
+        ] {
+            zip.start_file(name, zip::write::FileOptions::default())
+                .unwrap();
+            std::io::Write::write_all(&mut zip, content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let files_url = test_server(vec![("/mod.jar", 200, b"test".to_vec())]).await;
+        let files = format!(
+            r#"{{"data": [{{
+                "id": 10, "modId": 1, "fileName": "mod.jar", "downloadUrl": "{}/mod.jar",
+                "fileLength": 4,
+                "hashes": [{{"value": "a94a8fe5ccb19ba61c4c0873d391e987982fbbd3", "algo": 1}}]
+            }}]}}"#,
+            files_url
+        );
+        let api_url = test_server(vec![
+            ("/v1/mods/files", 200, files.into_bytes()),
+            (
+                "/v1/mods",
+                200,
+                br#"{"data": [{"id": 1, "name": "Mod", "classId": 6}]}"#.to_vec(),
+            ),
+        ])
+        .await;
+
+        let manager = InstanceManager::new(folder.join("instances"));
+        let api = CurseForgeApi::new(&test_manager(&folder, false), "key").base_url(api_url);
+        let instance = CurseForgePack::open(&path)
+            .unwrap()
+            .install(&manager, &api, None)
+            .await
+            .unwrap();
+
+        let instance_path = manager.instance_path(&instance.name).unwrap();
+        assert_eq!(instance.name, "Test Pack");
+        assert_eq!(
+            fs::read(instance_path.join("mods").join("mod.jar")).unwrap(),
+            b"test"
+        );
+        assert_eq!(
+            fs::read_to_string(instance_path.join("config").join("mod.toml")).unwrap(),
+            "enabled = true"
+        );
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
@@ -0,0 +1,88 @@
+use std::path::Path;
+
+use crate::{instance::is_manager_file, mods::MODS_LOCKFILE};
+
+pub mod curseforge;
+pub mod mrpack;
+
+pub use curseforge::{CurseForgeApi, CurseForgeManifest, CurseForgePack};
+pub use mrpack::{EnvSupport, MrPack, MrPackEnv, MrPackFile, MrPackIndex};
+
+/// Folder of a pack archive whose content is copied into the instance.
+const OVERRIDES: &str = "overrides";
+
+/// Files and folders of an instance that are never exported in a pack.
+const EXPORT_SKIP: &[&str] = &[
+    "instance.json",
+    ".lock",
+    "logs",
+    "crash-reports",
+    "screenshots",
+    "saves",
+    "resources",
+    MODS_LOCKFILE,
+];
+
+/// Check if a path of an instance, relative to its folder, is left out of exported packs.
+///
+/// Besides [`EXPORT_SKIP`], unfinished downloads (`.part`) and worlds being restored
+/// (`.<name>.restore`) are skipped wherever they are.
+fn is_export_skipped(path: &Path) -> bool {
+    let skipped_root = path
+        .components()
+        .next()
+        .is_some_and(|c| EXPORT_SKIP.iter().any(|s| c.as_os_str() == *s));
+
+    skipped_root
+        || path.iter().any(|c| {
+            let name = c.to_string_lossy();
+            name.ends_with(".part") || (name.starts_with('.') && name.ends_with(".restore"))
+        })
+}
+
+/// Check if a file of the overrides of a pack, relative to the instance folder,
+/// is left out when installing it: it would replace the state of the instance.
+fn is_override_skipped(path: &Path) -> bool {
+    is_manager_file(path) || path == Path::new(MODS_LOCKFILE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_skipped_paths() {
+        for path in [
+            "instance.json",
+            ".lock",
+            "mods.lock.json",
+            "saves/World/level.dat",
+            "logs/latest.log",
+            "mods/sodium.jar.part",
+            "config/.World.restore/level.dat",
+        ] {
+            assert!(is_export_skipped(Path::new(path)), "{}", path);
+        }
+
+        for path in [
+            "options.txt",
+            "mods/sodium.jar",
+            "config/saves/a.toml",
+            "config/.hidden",
+            "restore",
+        ] {
+            assert!(!is_export_skipped(Path::new(path)), "{}", path);
+        }
+    }
+
+    #[test]
+    fn override_skipped_paths() {
+        for path in ["instance.json", ".lock", "mods.lock.json"] {
+            assert!(is_override_skipped(Path::new(path)), "{}", path);
+        }
+
+        for path in ["options.txt", "config/instance.json", "mods/.lock"] {
+            assert!(!is_override_skipped(Path::new(path)), "{}", path);
+        }
+    }
+}
@@ -0,0 +1,636 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use log::{debug, trace, warn};
+use serde::{Deserialize, Serialize};
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::{
+    download::{Download, DownloadManager},
+    errors::{GrindstoneError, GrindstoneResult},
+    instance::{sanitize_name, Instance, InstanceManager},
+    utils::{
+        archive::{add_folder, extract_zip, read_entry},
+        fs::{safe_relative_path, write_atomic, write_atomic_with},
+        sha1::get_sha1,
+    },
+    version::{MinecraftVersion, VersionType},
+};
+
+use super::{is_export_skipped, is_override_skipped, OVERRIDES};
+
+/// Name of the index file inside a `.mrpack` archive.
+pub const MRPACK_INDEX: &str = "modrinth.index.json";
+/// Overrides only applied on the client side.
+const CLIENT_OVERRIDES: &str = "client-overrides";
+/// Only supported version of the format.
+const FORMAT_VERSION: u32 = 1;
+/// Game of the packs, Modrinth only supports Minecraft.
+const GAME: &str = "minecraft";
+/// Domains the files of a pack may be downloaded from, as required by the format.
+const ALLOWED_DOMAINS: &[&str] = &[
+    "cdn.modrinth.com",
+    "github.com",
+    "raw.githubusercontent.com",
+    "gitlab.com",
+];
+
+/// Dependency keys of the index.
+const MINECRAFT_DEPENDENCY: &str = "minecraft";
+const FORGE_DEPENDENCY: &str = "forge";
+const NEOFORGE_DEPENDENCY: &str = "neoforge";
+const FABRIC_DEPENDENCY: &str = "fabric-loader";
+const QUILT_DEPENDENCY: &str = "quilt-loader";
+
+/// Content of `modrinth.index.json`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MrPackIndex {
+    /// Version of the format, always 1.
+    pub format_version: u32,
+    /// Game of the pack, always `minecraft`.
+    pub game: String,
+    /// Version of the pack.
+    pub version_id: String,
+    /// Name of the pack.
+    pub name: String,
+    /// Short description of the pack.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// Files to download.
+    pub files: Vec<MrPackFile>,
+    /// Game version and mod loader, by dependency key.
+    pub dependencies: BTreeMap<String, String>,
+}
+
+/// A file of the pack that is downloaded.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MrPackFile {
+    /// Destination, relative to the instance folder.
+    pub path: String,
+    /// Hashes of the file, by algorithm (`sha1`, `sha512`).
+    pub hashes: BTreeMap<String, String>,
+    /// Sides where the file is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<MrPackEnv>,
+    /// Download URLs, tried in order.
+    pub downloads: Vec<String>,
+    /// Size of the file in bytes.
+    pub file_size: u64,
+}
+
+/// Support of a file on each side.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MrPackEnv {
+    pub client: EnvSupport,
+    pub server: EnvSupport,
+}
+
+/// Support of a file on a side.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EnvSupport {
+    Required,
+    Optional,
+    Unsupported,
+}
+
+impl MrPackFile {
+    /// SHA1 of the file, as hexadecimal string.
+    pub fn sha1(&self) -> Option<&str> {
+        self.hashes.get("sha1").map(String::as_str)
+    }
+
+    /// Check if the file is used on the client, optional files included.
+    pub fn is_client_side(&self) -> bool {
+        self.env
+            .as_ref()
+            .map(|env| env.client != EnvSupport::Unsupported)
+            .unwrap_or(true)
+    }
+}
+
+impl MrPackIndex {
+    /// Game version and mod loader required by the pack.
+    pub fn version(&self) -> GrindstoneResult<MinecraftVersion> {
+        let id = self
+            .dependencies
+            .get(MINECRAFT_DEPENDENCY)
+            .cloned()
+            .ok_or_else(|| {
+                GrindstoneError::InvalidImport("The pack has no minecraft dependency".to_string())
+            })?;
+
+        let loader = |key: &str| self.dependencies.get(key).cloned();
+
+        let version_type = if let Some(version) = loader(FABRIC_DEPENDENCY) {
+            VersionType::Fabric(version)
+        } else if let Some(version) = loader(QUILT_DEPENDENCY) {
+            VersionType::Quilt(version)
+        } else if let Some(version) = loader(FORGE_DEPENDENCY) {
+            VersionType::Forge(version)
+        } else if let Some(version) = loader(NEOFORGE_DEPENDENCY) {
+            VersionType::NeoForge(version)
+        } else {
+            VersionType::Vanilla
+        };
+
+        Ok(MinecraftVersion { id, version_type })
+    }
+
+    /// Build the dependencies of a game version.
+    fn dependencies(version: &MinecraftVersion) -> BTreeMap<String, String> {
+        let mut dependencies = BTreeMap::new();
+        dependencies.insert(MINECRAFT_DEPENDENCY.to_string(), version.id.clone());
+
+        let loader = match &version.version_type {
+            VersionType::Fabric(v) => Some((FABRIC_DEPENDENCY, v)),
+            VersionType::Quilt(v) => Some((QUILT_DEPENDENCY, v)),
+            VersionType::Forge(v) => Some((FORGE_DEPENDENCY, v)),
+            VersionType::NeoForge(v) => Some((NEOFORGE_DEPENDENCY, v)),
+            VersionType::Vanilla | VersionType::MCP => None,
+        };
+
+        if let Some((key, version)) = loader {
+            dependencies.insert(key.to_string(), version.clone());
+        }
+
+        dependencies
+    }
+
+    fn validate(&self) -> GrindstoneResult<()> {
+        if self.format_version != FORMAT_VERSION || self.game != GAME {
+            return Err(GrindstoneError::InvalidImport(format!(
+                "Unsupported pack format {} for game {}",
+                self.format_version, self.game
+            )));
+        }
+
+        for file in &self.files {
+            if safe_relative_path(&file.path).is_none() {
+                return Err(GrindstoneError::InvalidImport(format!(
+                    "Unsafe file path {}",
+                    file.path
+                )));
+            }
+
+            if file.sha1().is_none() || file.downloads.is_empty() {
+                return Err(GrindstoneError::InvalidImport(format!(
+                    "File {} has no SHA1 or download URL",
+                    file.path
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A Modrinth modpack archive (`.mrpack`).
+#[derive(Clone, Debug)]
+pub struct MrPack {
+    /// Path of the archive.
+    pub path: PathBuf,
+    /// Index of the pack.
+    pub index: MrPackIndex,
+    /// Domains the files may be downloaded from, the ones allowed by the format by default.
+    pub allowed_domains: Vec<String>,
+}
+
+impl MrPack {
+    /// Open a `.mrpack` archive and read its index.
+    pub fn open(path: impl AsRef<Path>) -> GrindstoneResult<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        let content = read_entry(&path, MRPACK_INDEX)?.ok_or_else(|| {
+            GrindstoneError::InvalidImport(format!(
+                "{} has no {}",
+                path.to_string_lossy(),
+                MRPACK_INDEX
+            ))
+        })?;
+
+        let index = serde_json::from_slice::<MrPackIndex>(&content)?;
+        index.validate()?;
+
+        Ok(Self {
+            path,
+            index,
+            allowed_domains: ALLOWED_DOMAINS.iter().map(|d| d.to_string()).collect(),
+        })
+    }
+
+    /// Also allow the files to be downloaded from a domain.
+    pub fn allow_domain<S: Into<String>>(mut self, domain: S) -> Self {
+        self.allowed_domains.push(domain.into());
+        self
+    }
+
+    /// Install the pack into a new instance.
+    ///
+    /// The files are downloaded and checked against their SHA1, then the overrides are extracted.
+    /// Fails before creating the instance if a file is downloaded from a domain that is not allowed.
+    /// The mod loader is recorded in the instance version and installed with the game by
+    /// [`crate::GrindstoneUpdater::update`], which fails with
+    /// [`GrindstoneError::UnsupportedModLoader`] for Forge and NeoForge.
+    /// * `manager` - Manager of the instances
+    /// * `downloads` - Manager of the downloads
+    /// * `name` - Name of the new instance, defaults to the name of the pack
+    pub async fn install(
+        &self,
+        manager: &InstanceManager,
+        downloads: &DownloadManager,
+        name: Option<&str>,
+    ) -> GrindstoneResult<Instance> {
+        let version = self.index.version()?;
+        let name = name
+            .map(String::from)
+            .unwrap_or_else(|| sanitize_name(&self.index.name));
+
+        self.check_domains()?;
+
+        trace!("Installing pack {} as {}", self.index.name, name);
+
+        let mut instance = Instance::new(name, version);
+        instance
+            .metadata
+            .insert("imported_from".to_string(), "modrinth".into());
+        instance
+            .metadata
+            .insert("pack_name".to_string(), self.index.name.clone().into());
+        instance.metadata.insert(
+            "pack_version".to_string(),
+            self.index.version_id.clone().into(),
+        );
+
+        let instance = manager.create(instance)?;
+        let instance_path = manager.instance_path(&instance.name)?;
+
+        if let Err(err) = self.install_files(&instance_path, downloads).await {
+            warn!("Installation of {} failed, removing it", instance.name);
+            fs::remove_dir_all(&instance_path)?;
+            return Err(err);
+        }
+
+        Ok(instance)
+    }
+
+    async fn install_files(
+        &self,
+        instance_path: &Path,
+        downloads: &DownloadManager,
+    ) -> GrindstoneResult<()> {
+        let batch = self
+            .index
+            .files
+            .iter()
+            .filter(|f| f.is_client_side())
+            .map(|file| {
+                let (url, alternate_urls) = file.downloads.split_first().ok_or_else(|| {
+                    GrindstoneError::InvalidImport(format!(
+                        "File {} has no download URL",
+                        file.path
+                    ))
+                })?;
+
+                Ok(Download {
+                    url: url.clone(),
+                    // Checked when the pack was opened
+                    file: instance_path.join(safe_relative_path(&file.path).unwrap_or_default()),
+                    sha1: file.sha1().map(hex::decode).transpose()?,
+                    size: Some(file.file_size),
+                    alternate_urls: alternate_urls.to_vec(),
+                })
+            })
+            .collect::<GrindstoneResult<Vec<_>>>()?;
+
+        downloads
+            .download(batch, |_, _, url| trace!("Downloaded pack file {}", url))
+            .await?;
+
+        debug!("Extracting overrides");
+        extract_zip(&self.path, instance_path, OVERRIDES, is_override_skipped)?;
+        extract_zip(
+            &self.path,
+            instance_path,
+            CLIENT_OVERRIDES,
+            is_override_skipped,
+        )?;
+
+        // Kept to export the pack again without embedding downloadable files
+        let json = serde_json::to_vec_pretty(&self.index)?;
+        write_atomic(instance_path.join(MRPACK_INDEX), json)
+    }
+
+    /// Check that the files used on the client are only downloaded from allowed domains.
+    fn check_domains(&self) -> GrindstoneResult<()> {
+        let files = self.index.files.iter().filter(|f| f.is_client_side());
+
+        for (file, url) in files.flat_map(|f| f.downloads.iter().map(move |url| (f, url))) {
+            let host = reqwest::Url::parse(url)
+                .ok()
+                .and_then(|url| url.host_str().map(String::from));
+
+            if !host.is_some_and(|host| self.allowed_domains.contains(&host)) {
+                return Err(GrindstoneError::InvalidImport(format!(
+                    "File {} is downloaded from a domain that is not allowed: {}",
+                    file.path, url
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Export an instance to a `.mrpack` archive.
+    ///
+    /// Files of the pack the instance was installed from are referenced when unchanged,
+    /// every other file of the instance is added to the overrides.
+    /// * `manager` - Manager of the instances
+    /// * `name` - Name of the instance to export
+    /// * `dest` - Path of the archive
+    /// * `version_id` - Version of the exported pack
+    pub fn export(
+        manager: &InstanceManager,
+        name: &str,
+        dest: impl AsRef<Path>,
+        version_id: &str,
+    ) -> GrindstoneResult<MrPackIndex> {
+        let instance = manager.get(name)?;
+        let instance_path = manager.instance_path(name)?;
+
+        let previous = instance_path.join(MRPACK_INDEX);
+        let previous = if previous.is_file() {
+            Some(serde_json::from_reader::<_, MrPackIndex>(File::open(
+                previous,
+            )?)?)
+        } else {
+            None
+        };
+
+        let mut files = Vec::new();
+        for file in previous.iter().flat_map(|p| p.files.iter()) {
+            let path = match safe_relative_path(&file.path) {
+                Some(path) => instance_path.join(path),
+                None => continue,
+            };
+
+            if !path.is_file() {
+                debug!("Pack file {} was removed", file.path);
+                continue;
+            }
+
+            let unchanged = file
+                .sha1()
+                .and_then(|sha1| hex::decode(sha1).ok())
+                .map(|sha1| get_sha1(&path).map(|local| local == sha1))
+                .transpose()?
+                .unwrap_or_default();
+
+            if unchanged {
+                files.push(file.clone());
+            } else {
+                debug!(
+                    "Pack file {} was modified, adding it to overrides",
+                    file.path
+                );
+            }
+        }
+
+        let index = MrPackIndex {
+            format_version: FORMAT_VERSION,
+            game: GAME.to_string(),
+            version_id: version_id.to_string(),
+            name: instance.name.clone(),
+            summary: previous.and_then(|p| p.summary),
+            dependencies: MrPackIndex::dependencies(&instance.version),
+            files,
+        };
+
+        let referenced = index
+            .files
+            .iter()
+            .filter_map(|f| safe_relative_path(&f.path))
+            .collect::<HashSet<_>>();
+
+        trace!("Exporting instance {} to pack", name);
+
+        if let Some(parent) = dest.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        write_atomic_with(dest, |writer| {
+            let mut zip = ZipWriter::new(writer);
+            let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+            zip.start_file(MRPACK_INDEX, options)?;
+            zip.write_all(&serde_json::to_vec_pretty(&index)?)?;
+
+            add_folder(&mut zip, &instance_path, OVERRIDES, |path| {
+                path == Path::new(MRPACK_INDEX)
+                    || is_export_skipped(path)
+                    || referenced.contains(path)
+            })?;
+
+            zip.finish()?;
+
+            Ok(())
+        })?;
+
+        Ok(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        download::test_manager,
+        utils::{download::test_server, fs::test_folder},
+    };
+
+    use super::*;
+
+    const INDEX: &str = r#"{
+        "formatVersion": 1,
+        "game": "minecraft",
+        "versionId": "1.2.0",
+        "name": "Example Pack",
+        "summary": "A pack",
+        "files": [
+            {
+                "path": "mods/sodium.jar",
+                "hashes": {"sha1": "a94a8fe5ccb19ba61c4c0873d391e987982fbbd3", "sha512": "00"},
+                "env": {"client": "required", "server": "unsupported"},
+                "downloads": ["https://cdn.modrinth.com/sodium.jar", "https://github.com/sodium.jar"],
+                "fileSize": 4
+            },
+            {
+                "path": "mods/server-only.jar",
+                "hashes": {"sha1": "a94a8fe5ccb19ba61c4c0873d391e987982fbbd3"},
+                "env": {"client": "unsupported", "server": "required"},
+                "downloads": ["https://cdn.modrinth.com/server-only.jar"],
+                "fileSize": 4
+            }
+        ],
+        "dependencies": {"minecraft": "1.20.1", "fabric-loader": "0.14.21"}
+    }"#;
+
+    fn index() -> MrPackIndex {
+        serde_json::from_str(INDEX).unwrap()
+    }
+
+    #[test]
+    fn parse_index() {
+        let index = index();
+        index.validate().unwrap();
+
+        assert_eq!(index.name, "Example Pack");
+        assert_eq!(
+            index.files[0].sha1(),
+            Some("a94a8fe5ccb19ba61c4c0873d391e987982fbbd3")
+        );
+        assert_eq!(index.files[0].downloads.len(), 2);
+        assert!(index.files[0].is_client_side());
+        assert!(!index.files[1].is_client_side());
+
+        let version = index.version().unwrap();
+        assert_eq!(version.id, "1.20.1");
+        assert_eq!(
+            version.version_type,
+            VersionType::Fabric("0.14.21".to_string())
+        );
+        assert_eq!(MrPackIndex::dependencies(&version), index.dependencies);
+    }
+
+    #[test]
+    fn reject_invalid_index() {
+        let invalid = |change: fn(&mut MrPackIndex)| {
+            let mut index = index();
+            change(&mut index);
+            index.validate().is_err()
+        };
+
+        assert!(invalid(|i| i.format_version = 2));
+        assert!(invalid(|i| i.game = "terraria".to_string()));
+        assert!(invalid(|i| i.files[0].path = "../../.bashrc".to_string()));
+        assert!(invalid(|i| i.files[0].path = "/etc/passwd".to_string()));
+        assert!(invalid(|i| i.files[0].hashes.clear()));
+        assert!(invalid(|i| i.files[0].downloads.clear()));
+
+        let mut index = index();
+        index.dependencies.remove(MINECRAFT_DEPENDENCY);
+        assert!(index.version().is_err());
+    }
+
+    #[test]
+    fn open_archive() {
+        let folder = test_folder("mrpack");
+        let path = folder.join("pack.mrpack");
+
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        zip.start_file(MRPACK_INDEX, FileOptions::default())
+            .unwrap();
+        zip.write_all(INDEX.as_bytes()).unwrap();
+        zip.finish().unwrap();
+
+        let pack = MrPack::open(&path).unwrap();
+        assert_eq!(pack.index.version_id, "1.2.0");
+
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        zip.start_file("other.json", FileOptions::default())
+            .unwrap();
+        zip.finish().unwrap();
+        assert!(matches!(
+            MrPack::open(&path),
+            Err(GrindstoneError::InvalidImport(_))
+        ));
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[tokio::test]
+    async fn install_pack() {
+        let folder = test_folder("mrpack-install");
+        let url = test_server(vec![("/sodium.jar", 200, b"test".to_vec())]).await;
+
+        let mut index = index();
+        index.files[0].downloads = vec![format!("{}/missing", url), format!("{}/sodium.jar", url)];
+
+        let path = folder.join("pack.mrpack");
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        zip.start_file(MRPACK_INDEX, FileOptions::default())
+            .unwrap();
+        zip.write_all(&serde_json::to_vec(&index).unwrap()).unwrap();
+        for (name, content) in [
+            ("overrides/options.txt", "lang:en_us"),
+            // Files of the instance are never replaced
+            ("overrides/instance.json", "{}"),
+            ("overrides/.lock", ""),
+            ("client-overrides/mods.lock.json", "{}"),
+        ] {
+            zip.start_file(name, FileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let manager = InstanceManager::new(folder.join("instances"));
+        let host = url.trim_start_matches("http://").split(':').next().unwrap();
+        let instance = MrPack::open(&path)
+            .unwrap()
+            .allow_domain(host)
+            .install(&manager, &test_manager(&folder, false), None)
+            .await
+            .unwrap();
+
+        let instance_path = manager.instance_path(&instance.name).unwrap();
+        assert_eq!(
+            fs::read(instance_path.join("mods/sodium.jar")).unwrap(),
+            b"test"
+        );
+        assert!(instance_path.join("options.txt").is_file());
+        assert!(!instance_path.join("mods/server-only.jar").exists());
+        assert_eq!(manager.get(&instance.name).unwrap().name, instance.name);
+        assert!(!instance_path.join(".lock").exists());
+        assert!(!instance_path.join("mods.lock.json").exists());
+
+        // A file that cannot be downloaded removes the instance
+        index.files[0].downloads = vec![format!("{}/missing", url)];
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        zip.start_file(MRPACK_INDEX, FileOptions::default())
+            .unwrap();
+        zip.write_all(&serde_json::to_vec(&index).unwrap()).unwrap();
+        zip.finish().unwrap();
+
+        let res = MrPack::open(&path)
+            .unwrap()
+            .allow_domain(host)
+            .install(&manager, &test_manager(&folder, false), Some("Broken"))
+            .await;
+        assert!(matches!(res, Err(GrindstoneError::DownloadFailed(_))));
+        assert!(!manager.exists("Broken"));
+
+        // Offline, the files are only checked
+        let res = MrPack::open(&path)
+            .unwrap()
+            .allow_domain(host)
+            .install(&manager, &test_manager(&folder, true), Some("Offline"))
+            .await;
+        assert!(matches!(res, Err(GrindstoneError::MissingFiles(files)) if files.len() == 1));
+        assert!(!manager.exists("Offline"));
+
+        // Only the domains allowed by the format are used by default
+        let res = MrPack::open(&path)
+            .unwrap()
+            .install(&manager, &test_manager(&folder, false), Some("Untrusted"))
+            .await;
+        assert!(matches!(res, Err(GrindstoneError::InvalidImport(_))));
+        assert!(!manager.exists("Untrusted"));
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
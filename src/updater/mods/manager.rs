@@ -0,0 +1,558 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::{debug, trace, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    download::Download,
+    errors::{GrindstoneError, GrindstoneResult},
+    instance::InstanceManager,
+    utils::{fs::write_atomic, sha1::get_sha1},
+    version::MinecraftVersion,
+};
+
+use super::modrinth::{ModrinthApi, ModrinthHashes, ModrinthVersion};
+
+/// Name of the file tracking the installed mods, inside the instance folder.
+pub const MODS_LOCKFILE: &str = "mods.lock.json";
+
+/// Mods installed in an instance, stored in its lockfile.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ModLockfile {
+    pub mods: Vec<InstalledMod>,
+}
+
+/// A mod installed from Modrinth.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct InstalledMod {
+    pub project_id: String,
+    pub version_id: String,
+    pub version_number: String,
+    /// Name of the file in the `mods` folder.
+    pub file_name: String,
+    pub hashes: ModrinthHashes,
+    /// Whether the mod was installed as a dependency of another mod.
+    #[serde(default)]
+    pub dependency: bool,
+}
+
+/// A newer version of an installed mod.
+#[derive(Clone, Debug)]
+pub struct ModUpdate {
+    pub installed: InstalledMod,
+    pub latest: ModrinthVersion,
+}
+
+/// Installs, updates and removes the mods of an instance.
+#[derive(Debug)]
+pub struct ModManager {
+    api: ModrinthApi,
+    version: MinecraftVersion,
+    mods_path: PathBuf,
+    lockfile_path: PathBuf,
+    lockfile: ModLockfile,
+}
+
+impl ModManager {
+    /// Create a manager for the mods of an instance.
+    /// * `manager` - Manager of the instances
+    /// * `name` - Name of the instance
+    /// * `api` - Client of the Modrinth API, its download manager downloads the mods
+    pub fn new(manager: &InstanceManager, name: &str, api: ModrinthApi) -> GrindstoneResult<Self> {
+        let instance = manager.get(name)?;
+        let instance_path = manager.instance_path(name)?;
+
+        let lockfile_path = instance_path.join(MODS_LOCKFILE);
+        let lockfile = if lockfile_path.is_file() {
+            serde_json::from_reader(fs::File::open(&lockfile_path)?)?
+        } else {
+            ModLockfile::default()
+        };
+
+        Ok(Self {
+            api,
+            version: instance.version,
+            mods_path: instance_path.join("mods"),
+            lockfile_path,
+            lockfile,
+        })
+    }
+
+    /// Mods installed in the instance.
+    pub fn installed(&self) -> &[InstalledMod] {
+        &self.lockfile.mods
+    }
+
+    /// Get an installed mod by project ID.
+    pub fn get(&self, project_id: &str) -> Option<&InstalledMod> {
+        self.lockfile
+            .mods
+            .iter()
+            .find(|m| m.project_id == project_id)
+    }
+
+    /// Install the latest compatible version of a mod and its required dependencies.
+    ///
+    /// The mod and its dependencies are downloaded together, none of them is installed
+    /// if one fails or is incompatible with another mod.
+    /// Returns the installed mods, empty if the mod was already installed.
+    /// * `project` - ID or slug of the project
+    pub async fn install(&mut self, project: &str) -> GrindstoneResult<Vec<InstalledMod>> {
+        let version = self.api.latest_version(project, &self.version).await?;
+
+        if self.get(&version.project_id).is_some() {
+            debug!("Mod {} is already installed", project);
+            return Ok(Vec::new());
+        }
+
+        let installed_ids = self.installed_ids();
+        let dependencies = self
+            .api
+            .resolve_dependencies(&version, &self.version, &installed_ids)
+            .await?;
+
+        let versions = std::iter::once((version, false))
+            .chain(dependencies.into_iter().map(|d| (d, true)))
+            .collect::<Vec<_>>();
+        self.check_compatibility(&versions).await?;
+
+        self.install_versions(&versions).await
+    }
+
+    /// Remove an installed mod.
+    ///
+    /// Its dependencies are kept, as other mods may use them.
+    pub fn remove(&mut self, project_id: &str) -> GrindstoneResult<InstalledMod> {
+        let position = self
+            .lockfile
+            .mods
+            .iter()
+            .position(|m| m.project_id == project_id)
+            .ok_or_else(|| GrindstoneError::ModNotInstalled(project_id.to_string()))?;
+
+        let removed = self.lockfile.mods.remove(position);
+
+        let path = self.mods_path.join(&removed.file_name);
+        if path.is_file() {
+            trace!("Removing mod file {}", removed.file_name);
+            fs::remove_file(path)?;
+        }
+
+        self.save()?;
+
+        Ok(removed)
+    }
+
+    /// List the installed mods that have a newer compatible version.
+    pub async fn check_updates(&self) -> GrindstoneResult<Vec<ModUpdate>> {
+        let mut updates = Vec::new();
+
+        for installed in &self.lockfile.mods {
+            let latest = match self
+                .api
+                .latest_version(&installed.project_id, &self.version)
+                .await
+            {
+                Ok(latest) => latest,
+                Err(GrindstoneError::NoCompatibleModVersion(_)) => continue,
+                Err(err) => return Err(err),
+            };
+
+            if latest.id != installed.version_id {
+                updates.push(ModUpdate {
+                    installed: installed.clone(),
+                    latest,
+                });
+            }
+        }
+
+        Ok(updates)
+    }
+
+    /// Update an installed mod to its latest compatible version.
+    ///
+    /// The required dependencies of the new version that are missing are installed with it.
+    /// Fails if the new version or a dependency is incompatible with another installed mod.
+    /// Returns the new version, `None` if the mod is up to date.
+    pub async fn update(&mut self, project_id: &str) -> GrindstoneResult<Option<InstalledMod>> {
+        let installed = self
+            .get(project_id)
+            .cloned()
+            .ok_or_else(|| GrindstoneError::ModNotInstalled(project_id.to_string()))?;
+
+        let latest = self.api.latest_version(project_id, &self.version).await?;
+        if latest.id == installed.version_id {
+            return Ok(None);
+        }
+
+        let installed_ids = self.installed_ids();
+        let dependencies = self
+            .api
+            .resolve_dependencies(&latest, &self.version, &installed_ids)
+            .await?;
+
+        let versions = std::iter::once((latest, installed.dependency))
+            .chain(dependencies.into_iter().map(|d| (d, true)))
+            .collect::<Vec<_>>();
+        self.check_compatibility(&versions).await?;
+        let updated = self.install_versions(&versions).await?.remove(0);
+
+        if updated.file_name != installed.file_name {
+            let old_path = self.mods_path.join(&installed.file_name);
+            if old_path.is_file() {
+                fs::remove_file(old_path)?;
+            }
+        }
+
+        Ok(Some(updated))
+    }
+
+    /// List the installed mods whose file is missing or modified.
+    pub fn verify(&self) -> GrindstoneResult<Vec<&InstalledMod>> {
+        let mut invalid = Vec::new();
+
+        for installed in &self.lockfile.mods {
+            let path = self.mods_path.join(&installed.file_name);
+
+            let valid = path.is_file() && hex::decode(&installed.hashes.sha1)? == get_sha1(&path)?;
+
+            if !valid {
+                invalid.push(installed);
+            }
+        }
+
+        Ok(invalid)
+    }
+
+    fn installed_ids(&self) -> HashSet<String> {
+        self.lockfile
+            .mods
+            .iter()
+            .map(|m| m.project_id.clone())
+            .collect()
+    }
+
+    /// Check that no version to install declares another mod as incompatible,
+    /// and that no other mod declares it as incompatible.
+    ///
+    /// The mods replaced by the versions are not checked.
+    async fn check_compatibility(
+        &self,
+        versions: &[(ModrinthVersion, bool)],
+    ) -> GrindstoneResult<()> {
+        let installed_ids = self
+            .lockfile
+            .mods
+            .iter()
+            .filter(|m| !versions.iter().any(|(v, _)| v.project_id == m.project_id))
+            .map(|m| m.version_id.clone())
+            .collect::<Vec<_>>();
+        let installed = self.api.get_versions(&installed_ids).await?;
+
+        for (i, (version, _)) in versions.iter().enumerate() {
+            let others = versions
+                .iter()
+                .skip(i + 1)
+                .map(|(v, _)| (v, v.project_id.as_str()))
+                .chain(installed.iter().map(|v| {
+                    let name = self
+                        .get(&v.project_id)
+                        .map(|m| m.file_name.as_str())
+                        .unwrap_or(&v.project_id);
+                    (v, name)
+                }));
+
+            for (other, name) in others {
+                if version.is_incompatible_with(other) || other.is_incompatible_with(version) {
+                    return Err(GrindstoneError::IncompatibleMod(
+                        version.project_id.clone(),
+                        name.to_string(),
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Download versions in one batch and record them in the lockfile,
+    /// replacing the previous versions of their mods.
+    ///
+    /// The files created for the versions are removed if a download fails.
+    /// * `versions` - Versions, with whether they are installed as a dependency
+    async fn install_versions(
+        &mut self,
+        versions: &[(ModrinthVersion, bool)],
+    ) -> GrindstoneResult<Vec<InstalledMod>> {
+        let mut installed = Vec::new();
+        let mut batch = Vec::new();
+
+        for (version, dependency) in versions {
+            let file = version.primary_file().ok_or_else(|| {
+                GrindstoneError::NoCompatibleModVersion(version.project_id.clone())
+            })?;
+
+            let file_name = Path::new(&file.filename)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .ok_or_else(|| {
+                    GrindstoneError::NoCompatibleModVersion(version.project_id.clone())
+                })?;
+
+            trace!("Installing mod {} {}", version.project_id, file_name);
+            batch.push(Download {
+                url: file.url.clone(),
+                file: self.mods_path.join(&file_name),
+                sha1: Some(hex::decode(&file.hashes.sha1)?),
+                size: Some(file.size),
+                alternate_urls: Vec::new(),
+            });
+
+            installed.push(InstalledMod {
+                project_id: version.project_id.clone(),
+                version_id: version.id.clone(),
+                version_number: version.version_number.clone(),
+                file_name,
+                hashes: file.hashes.clone(),
+                dependency: *dependency,
+            });
+        }
+
+        let created = batch
+            .iter()
+            .map(|d| d.file.clone())
+            .filter(|f| !f.exists())
+            .collect::<Vec<_>>();
+
+        let previous = self.lockfile.clone();
+        for installed_mod in &installed {
+            self.lockfile
+                .mods
+                .retain(|m| m.project_id != installed_mod.project_id);
+            self.lockfile.mods.push(installed_mod.clone());
+        }
+
+        let res = match self
+            .api
+            .downloads
+            .download(batch, |_, _, url| trace!("Downloaded mod {}", url))
+            .await
+        {
+            Ok(()) => self.save(),
+            Err(err) => Err(err),
+        };
+
+        if let Err(err) = res {
+            warn!("Installation of mods failed, removing their files");
+            self.lockfile = previous;
+            for file in created.iter().filter(|f| f.is_file()) {
+                fs::remove_file(file)?;
+            }
+            return Err(err);
+        }
+
+        Ok(installed)
+    }
+
+    fn save(&self) -> GrindstoneResult<()> {
+        let json = serde_json::to_vec_pretty(&self.lockfile)?;
+        write_atomic(&self.lockfile_path, json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        download::test_manager,
+        instance::Instance,
+        utils::{download::test_server, fs::test_folder},
+        version::{MinecraftVersionBuilder, VersionType},
+    };
+
+    use super::*;
+
+    /// SHA1 of `test`, the content of every file.
+    const SHA1: &str = "a94a8fe5ccb19ba61c4c0873d391e987982fbbd3";
+
+    fn version(project: &str, files_url: &str, dependencies: &str) -> String {
+        format!(
+            r#"{{
+                "id": "{project}-1", "project_id": "{project}", "name": "{project}",
+                "version_number": "1.0", "version_type": "release",
+                "date_published": "2023-09-21T18:31:38Z",
+                "files": [{{
+                    "url": "{files_url}/{project}.jar", "filename": "{project}.jar",
+                    "hashes": {{"sha1": "{SHA1}"}}, "primary": true, "size": 4
+                }}],
+                "dependencies": [{dependencies}]
+            }}"#
+        )
+    }
+
+    #[tokio::test]
+    async fn install_mods() {
+        let folder = test_folder("mods");
+        let files_url = test_server(vec![
+            ("/lib.jar", 200, b"test".to_vec()),
+            ("/root.jar", 200, b"test".to_vec()),
+            ("/clash.jar", 200, b"test".to_vec()),
+            ("/other.jar", 200, b"test".to_vec()),
+        ])
+        .await;
+
+        let lib = version(
+            "lib",
+            &files_url,
+            r#"{"project_id": "other", "dependency_type": "incompatible"}"#,
+        );
+        let list = |v: &str| format!("[{}]", v).into_bytes();
+        let api_url = test_server(vec![
+            ("/v2/project/lib/version", 200, list(&lib)),
+            ("/v2/versions", 200, list(&lib)),
+            (
+                "/v2/project/root/version",
+                200,
+                list(&version(
+                    "root",
+                    &files_url,
+                    r#"{"project_id": "broken", "dependency_type": "required"}"#,
+                )),
+            ),
+            (
+                "/v2/project/broken/version",
+                200,
+                list(&version("broken", &files_url, "")),
+            ),
+            (
+                "/v2/project/clash/version",
+                200,
+                list(&version(
+                    "clash",
+                    &files_url,
+                    r#"{"project_id": "lib", "dependency_type": "incompatible"}"#,
+                )),
+            ),
+            (
+                "/v2/project/other/version",
+                200,
+                list(&version("other", &files_url, "")),
+            ),
+        ])
+        .await;
+
+        let instances = InstanceManager::new(folder.join("instances"));
+        instances
+            .create(Instance::new(
+                "Mods",
+                MinecraftVersionBuilder::default()
+                    .version_id("1.20.1")
+                    .version_type(VersionType::Fabric("0.14.21".to_string()))
+                    .build(),
+            ))
+            .unwrap();
+        let api = ModrinthApi::new(&test_manager(&folder, false)).base_url(api_url);
+        let mut mods = ModManager::new(&instances, "Mods", api.clone()).unwrap();
+
+        // The file of a dependency is missing, nothing is installed
+        assert!(matches!(
+            mods.install("root").await,
+            Err(GrindstoneError::DownloadFailed(_))
+        ));
+        assert!(mods.installed().is_empty());
+        assert!(!mods.mods_path.join("root.jar").exists());
+
+        let installed = mods.install("lib").await.unwrap();
+        assert_eq!(installed.len(), 1);
+        assert_eq!(fs::read(mods.mods_path.join("lib.jar")).unwrap(), b"test");
+        assert!(mods.install("lib").await.unwrap().is_empty());
+
+        // Incompatible in both directions
+        assert!(matches!(
+            mods.install("clash").await,
+            Err(GrindstoneError::IncompatibleMod(new, installed)) if new == "clash" && installed == "lib.jar"
+        ));
+        assert!(matches!(
+            mods.install("other").await,
+            Err(GrindstoneError::IncompatibleMod(new, _)) if new == "other"
+        ));
+        assert!(!mods.mods_path.join("clash.jar").exists());
+
+        // The lockfile is read again
+        let mods = ModManager::new(&instances, "Mods", api).unwrap();
+        assert_eq!(mods.installed().len(), 1);
+        assert!(mods.verify().unwrap().is_empty());
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[tokio::test]
+    async fn update_with_dependencies() {
+        let folder = test_folder("mods-update");
+        let files_url = test_server(vec![
+            ("/app.jar", 200, b"test".to_vec()),
+            ("/dep.jar", 200, b"test".to_vec()),
+        ])
+        .await;
+
+        let list = |v: &str| format!("[{}]", v).into_bytes();
+        let api_url = test_server(vec![
+            (
+                "/v2/project/app/version",
+                200,
+                list(&version(
+                    "app",
+                    &files_url,
+                    r#"{"project_id": "dep", "dependency_type": "required"}"#,
+                )),
+            ),
+            (
+                "/v2/project/dep/version",
+                200,
+                list(&version("dep", &files_url, "")),
+            ),
+            ("/v2/versions", 200, list("")),
+        ])
+        .await;
+
+        let instances = InstanceManager::new(folder.join("instances"));
+        instances
+            .create(Instance::new(
+                "Mods",
+                MinecraftVersionBuilder::default()
+                    .version_id("1.20.1")
+                    .version_type(VersionType::Fabric("0.14.21".to_string()))
+                    .build(),
+            ))
+            .unwrap();
+        let api = ModrinthApi::new(&test_manager(&folder, false)).base_url(api_url);
+        let mut mods = ModManager::new(&instances, "Mods", api).unwrap();
+
+        // An older version, without the dependency
+        fs::create_dir_all(&mods.mods_path).unwrap();
+        fs::write(mods.mods_path.join("app-0.jar"), b"test").unwrap();
+        mods.lockfile.mods.push(InstalledMod {
+            project_id: "app".to_string(),
+            version_id: "app-0".to_string(),
+            version_number: "0.9".to_string(),
+            file_name: "app-0.jar".to_string(),
+            hashes: ModrinthHashes {
+                sha1: SHA1.to_string(),
+                sha512: None,
+            },
+            dependency: false,
+        });
+
+        let updated = mods.update("app").await.unwrap().unwrap();
+        assert_eq!(updated.version_id, "app-1");
+        assert!(!mods.mods_path.join("app-0.jar").exists());
+        assert!(mods.mods_path.join("dep.jar").is_file());
+        assert!(mods.get("dep").unwrap().dependency);
+        assert!(mods.update("app").await.unwrap().is_none());
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
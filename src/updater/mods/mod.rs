@@ -0,0 +1,7 @@
+mod manager;
+pub mod modrinth;
+mod scanner;
+
+pub use manager::{InstalledMod, ModLockfile, ModManager, ModUpdate, MODS_LOCKFILE};
+pub use modrinth::{ModrinthApi, ModrinthVersion, SearchQuery};
+pub use scanner::{LocalMod, ModDependency, ModDependencyKind, ModIssue, ModLoader, ModScan};
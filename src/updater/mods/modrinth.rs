@@ -0,0 +1,423 @@
+use std::collections::{HashSet, VecDeque};
+
+use log::{debug, trace};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::{
+    constants,
+    download::DownloadManager,
+    errors::{GrindstoneError, GrindstoneResult},
+    version::{MinecraftVersion, VersionType},
+};
+
+/// Client of the Modrinth API.
+///
+/// When its download manager is offline, requests fail with [`GrindstoneError::Offline`].
+#[derive(Clone, Debug)]
+pub struct ModrinthApi {
+    /// Also downloads the files of the installed mods.
+    pub(super) downloads: DownloadManager,
+    base_url: String,
+}
+
+/// Parameters of a project search.
+#[derive(Clone, Debug, Default)]
+pub struct SearchQuery {
+    /// Text to search.
+    pub query: String,
+    /// Only return projects available for this game version.
+    pub game_version: Option<String>,
+    /// Only return projects available for this mod loader (`fabric`, `forge`...).
+    pub loader: Option<String>,
+    /// Type of project (`mod`, `resourcepack`, `shader`...).
+    pub project_type: Option<String>,
+    /// Maximum number of results.
+    pub limit: Option<u32>,
+    /// Number of results to skip.
+    pub offset: Option<u32>,
+}
+
+/// Results of a search.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SearchResults {
+    pub hits: Vec<SearchHit>,
+    pub offset: u32,
+    pub limit: u32,
+    pub total_hits: u32,
+}
+
+/// A project found by a search.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SearchHit {
+    pub project_id: String,
+    pub slug: Option<String>,
+    pub title: String,
+    pub description: String,
+    pub project_type: String,
+    pub author: String,
+    pub downloads: u64,
+    pub icon_url: Option<String>,
+    #[serde(default)]
+    pub versions: Vec<String>,
+}
+
+/// A project hosted on Modrinth.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ModrinthProject {
+    pub id: String,
+    pub slug: String,
+    pub title: String,
+    pub description: String,
+    pub project_type: String,
+    pub icon_url: Option<String>,
+    #[serde(default)]
+    pub loaders: Vec<String>,
+    #[serde(default)]
+    pub game_versions: Vec<String>,
+}
+
+/// A version of a project.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ModrinthVersion {
+    pub id: String,
+    pub project_id: String,
+    pub name: String,
+    pub version_number: String,
+    #[serde(default)]
+    pub game_versions: Vec<String>,
+    #[serde(default)]
+    pub loaders: Vec<String>,
+    /// `release`, `beta` or `alpha`.
+    pub version_type: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub date_published: OffsetDateTime,
+    pub files: Vec<ModrinthFile>,
+    #[serde(default)]
+    pub dependencies: Vec<ModrinthDependency>,
+}
+
+/// A file of a version.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ModrinthFile {
+    pub url: String,
+    pub filename: String,
+    pub hashes: ModrinthHashes,
+    #[serde(default)]
+    pub primary: bool,
+    pub size: u64,
+}
+
+/// Hashes of a file, as hexadecimal strings.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ModrinthHashes {
+    pub sha1: String,
+    pub sha512: Option<String>,
+}
+
+/// A dependency of a version.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ModrinthDependency {
+    pub version_id: Option<String>,
+    pub project_id: Option<String>,
+    pub file_name: Option<String>,
+    pub dependency_type: DependencyType,
+}
+
+/// Kind of dependency.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyType {
+    Required,
+    Optional,
+    Incompatible,
+    Embedded,
+}
+
+impl ModrinthVersion {
+    /// Check if the version declares another version, or its project, as incompatible.
+    pub fn is_incompatible_with(&self, other: &ModrinthVersion) -> bool {
+        self.dependencies
+            .iter()
+            .filter(|d| d.dependency_type == DependencyType::Incompatible)
+            .any(|d| {
+                d.project_id.as_ref() == Some(&other.project_id)
+                    || d.version_id.as_ref() == Some(&other.id)
+            })
+    }
+
+    /// Primary file of the version, the first one if none is marked as primary.
+    pub fn primary_file(&self) -> Option<&ModrinthFile> {
+        self.files
+            .iter()
+            .find(|f| f.primary)
+            .or_else(|| self.files.first())
+    }
+}
+
+impl ModrinthApi {
+    /// Create a client for the official API.
+    /// * `downloads` - Manager whose HTTP client is used, also downloading the files of the mods
+    pub fn new(downloads: &DownloadManager) -> Self {
+        Self {
+            downloads: downloads.clone(),
+            base_url: constants::MODRINTH_API_URL.to_string(),
+        }
+    }
+
+    /// Use another endpoint than the official API.
+    pub fn base_url<S: Into<String>>(mut self, base_url: S) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Search projects.
+    pub async fn search(&self, query: &SearchQuery) -> GrindstoneResult<SearchResults> {
+        let mut facets = Vec::new();
+        if let Some(game_version) = &query.game_version {
+            facets.push(vec![format!("versions:{}", game_version)]);
+        }
+        if let Some(loader) = &query.loader {
+            facets.push(vec![format!("categories:{}", loader)]);
+        }
+        if let Some(project_type) = &query.project_type {
+            facets.push(vec![format!("project_type:{}", project_type)]);
+        }
+
+        let mut params = vec![("query", query.query.clone())];
+        if !facets.is_empty() {
+            params.push(("facets", serde_json::to_string(&facets)?));
+        }
+        if let Some(limit) = query.limit {
+            params.push(("limit", limit.to_string()));
+        }
+        if let Some(offset) = query.offset {
+            params.push(("offset", offset.to_string()));
+        }
+
+        self.get("/v2/search", &params).await
+    }
+
+    /// Fetch a project by ID or slug.
+    pub async fn get_project(&self, id: &str) -> GrindstoneResult<ModrinthProject> {
+        self.get(&format!("/v2/project/{}", id), &[]).await
+    }
+
+    /// Fetch the versions of a project, newest first.
+    /// * `id` - ID or slug of the project
+    /// * `game_version` - Only return versions for this game version
+    /// * `loader` - Only return versions for this mod loader
+    pub async fn get_project_versions(
+        &self,
+        id: &str,
+        game_version: Option<&str>,
+        loader: Option<&str>,
+    ) -> GrindstoneResult<Vec<ModrinthVersion>> {
+        let mut params = Vec::new();
+        if let Some(game_version) = game_version {
+            params.push(("game_versions", serde_json::to_string(&[game_version])?));
+        }
+        if let Some(loader) = loader {
+            params.push(("loaders", serde_json::to_string(&[loader])?));
+        }
+
+        self.get(&format!("/v2/project/{}/version", id), &params)
+            .await
+    }
+
+    /// Fetch a version by ID.
+    pub async fn get_version(&self, id: &str) -> GrindstoneResult<ModrinthVersion> {
+        self.get(&format!("/v2/version/{}", id), &[]).await
+    }
+
+    /// Fetch several versions by ID.
+    pub async fn get_versions(&self, ids: &[String]) -> GrindstoneResult<Vec<ModrinthVersion>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.get("/v2/versions", &[("ids", serde_json::to_string(ids)?)])
+            .await
+    }
+
+    /// Find the version a file belongs to, from its SHA1.
+    pub async fn get_version_from_hash(&self, sha1: &str) -> GrindstoneResult<ModrinthVersion> {
+        self.get(
+            &format!("/v2/version_file/{}", sha1),
+            &[("algorithm", "sha1".to_string())],
+        )
+        .await
+    }
+
+    /// Latest version of a project compatible with a game version.
+    pub async fn latest_version(
+        &self,
+        id: &str,
+        version: &MinecraftVersion,
+    ) -> GrindstoneResult<ModrinthVersion> {
+        self.get_project_versions(id, Some(&version.id), loader_name(&version.version_type))
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| GrindstoneError::NoCompatibleModVersion(id.to_string()))
+    }
+
+    /// Resolve the required dependencies of a version, recursively.
+    ///
+    /// Returns the versions to install, the given one excluded.
+    /// * `skip` - Projects that are already installed
+    pub async fn resolve_dependencies(
+        &self,
+        root: &ModrinthVersion,
+        version: &MinecraftVersion,
+        skip: &HashSet<String>,
+    ) -> GrindstoneResult<Vec<ModrinthVersion>> {
+        let mut seen = skip.clone();
+        seen.insert(root.project_id.clone());
+
+        let mut resolved = Vec::new();
+        let mut queue = VecDeque::from([root.clone()]);
+
+        while let Some(current) = queue.pop_front() {
+            for dependency in &current.dependencies {
+                if dependency.dependency_type != DependencyType::Required {
+                    continue;
+                }
+
+                if let Some(project_id) = &dependency.project_id {
+                    if seen.contains(project_id) {
+                        continue;
+                    }
+                }
+
+                let dependency_version = match (&dependency.version_id, &dependency.project_id) {
+                    (Some(version_id), _) => self.get_version(version_id).await?,
+                    (None, Some(project_id)) => self.latest_version(project_id, version).await?,
+                    (None, None) => {
+                        debug!(
+                            "Skipping dependency of {} without project: {:?}",
+                            current.project_id, dependency.file_name
+                        );
+                        continue;
+                    }
+                };
+
+                if !seen.insert(dependency_version.project_id.clone()) {
+                    continue;
+                }
+
+                trace!(
+                    "{} depends on {} {}",
+                    current.project_id,
+                    dependency_version.project_id,
+                    dependency_version.version_number
+                );
+                queue.push_back(dependency_version.clone());
+                resolved.push(dependency_version);
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    async fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        params: &[(&str, String)],
+    ) -> GrindstoneResult<T> {
+        let url = format!("{}{}", self.base_url, path);
+        trace!("Querying Modrinth API: {}", url);
+
+        self.downloads
+            .request_json(&url, |client, url| client.get(url).query(params))
+            .await
+    }
+}
+
+/// Name of the mod loader in the API, `None` for vanilla.
+pub fn loader_name(version_type: &VersionType) -> Option<&'static str> {
+    match version_type {
+        VersionType::Forge(_) => Some("forge"),
+        VersionType::NeoForge(_) => Some("neoforge"),
+        VersionType::Fabric(_) => Some("fabric"),
+        VersionType::Quilt(_) => Some("quilt"),
+        VersionType::Vanilla | VersionType::MCP => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{download::test_manager, utils::fs::test_folder};
+
+    use super::*;
+
+    const VERSION: &str = r#"{
+        "id": "IZskON6d",
+        "project_id": "AANobbMI",
+        "name": "Sodium 0.5.3",
+        "version_number": "mc1.20.1-0.5.3",
+        "game_versions": ["1.20.1"],
+        "loaders": ["fabric", "quilt"],
+        "version_type": "release",
+        "date_published": "2023-09-21T18:31:38.916045Z",
+        "files": [
+            {
+                "url": "https://cdn.modrinth.com/sodium-sources.jar",
+                "filename": "sodium-sources.jar",
+                "hashes": {"sha1": "00"},
+                "primary": false,
+                "size": 10
+            },
+            {
+                "url": "https://cdn.modrinth.com/sodium.jar",
+                "filename": "sodium.jar",
+                "hashes": {"sha1": "a94a8fe5ccb19ba61c4c0873d391e987982fbbd3", "sha512": "00"},
+                "primary": true,
+                "size": 4
+            }
+        ],
+        "dependencies": [
+            {"project_id": "P7dR8mSH", "dependency_type": "required"},
+            {"project_id": "YL57xq9U", "dependency_type": "incompatible"}
+        ]
+    }"#;
+
+    #[test]
+    fn parse_version() {
+        let mut version = serde_json::from_str::<ModrinthVersion>(VERSION).unwrap();
+
+        assert_eq!(version.primary_file().unwrap().filename, "sodium.jar");
+        assert_eq!(
+            version.dependencies[1].dependency_type,
+            DependencyType::Incompatible
+        );
+
+        version.files.iter_mut().for_each(|f| f.primary = false);
+        assert_eq!(
+            version.primary_file().unwrap().filename,
+            "sodium-sources.jar"
+        );
+    }
+
+    #[test]
+    fn loader_names() {
+        assert_eq!(
+            loader_name(&VersionType::NeoForge("47.1.79".to_string())),
+            Some("neoforge")
+        );
+        assert_eq!(loader_name(&VersionType::Vanilla), None);
+    }
+
+    #[tokio::test]
+    async fn offline_requests() {
+        let folder = test_folder("modrinth-offline");
+        let api = ModrinthApi::new(&test_manager(&folder, true));
+
+        assert!(matches!(
+            api.get_project("sodium").await,
+            Err(GrindstoneError::Offline(url)) if url.ends_with("/v2/project/sodium")
+        ));
+
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+}
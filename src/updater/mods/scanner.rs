@@ -0,0 +1,920 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    io::{Cursor, Read, Seek},
+    path::{Path, PathBuf},
+};
+
+use log::{debug, trace};
+use serde::Deserialize;
+use serde_json::Value;
+use zip::ZipArchive;
+
+use crate::{
+    errors::GrindstoneResult,
+    utils::archive::read_zip_entry,
+    version::{MinecraftVersion, VersionType},
+};
+
+const FABRIC_MOD_JSON: &str = "fabric.mod.json";
+const QUILT_MOD_JSON: &str = "quilt.mod.json";
+const FORGE_MODS_TOML: &str = "META-INF/mods.toml";
+const NEOFORGE_MODS_TOML: &str = "META-INF/neoforge.mods.toml";
+const MCMOD_INFO: &str = "mcmod.info";
+const JAR_MANIFEST: &str = "META-INF/MANIFEST.MF";
+
+/// How deep embedded jars are read, jars nested further are ignored.
+const MAX_NESTED_DEPTH: usize = 4;
+
+/// Dependencies provided by the game or the mod loader, never installed as mods.
+const PLATFORM_IDS: &[&str] = &[
+    "minecraft",
+    "java",
+    "fabricloader",
+    "quilt_loader",
+    "forge",
+    "neoforge",
+    "fml",
+    "mcp",
+];
+
+/// Mod loader a mod is made for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ModLoader {
+    Fabric,
+    Quilt,
+    Forge,
+    NeoForge,
+    /// Forge before 1.13, using `mcmod.info`.
+    LegacyForge,
+}
+
+/// Kind of a declared dependency.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModDependencyKind {
+    Required,
+    Optional,
+    Incompatible,
+}
+
+/// A dependency declared by a mod.
+#[derive(Clone, Debug)]
+pub struct ModDependency {
+    pub id: String,
+    /// Version requirement, in the format of the mod loader.
+    pub version: Option<String>,
+    pub kind: ModDependencyKind,
+}
+
+/// A mod found in a jar of the `mods` folder.
+#[derive(Clone, Debug)]
+pub struct LocalMod {
+    /// Path of the jar.
+    pub path: PathBuf,
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub loader: ModLoader,
+    pub dependencies: Vec<ModDependency>,
+    /// IDs also provided by the mod, including its embedded jars.
+    pub provides: Vec<String>,
+}
+
+/// Problem found while scanning the mods of an instance.
+#[derive(Clone, Debug)]
+pub enum ModIssue {
+    /// Several jars contain the same mod.
+    Duplicate { id: String, paths: Vec<PathBuf> },
+    /// The mod is made for another mod loader than the instance one.
+    WrongLoader { id: String, loader: ModLoader },
+    /// A required dependency is not installed.
+    MissingDependency { id: String, dependency: String },
+    /// The mod declares an installed mod as incompatible.
+    Incompatible { id: String, other: String },
+}
+
+/// Result of a scan of a `mods` folder.
+#[derive(Clone, Debug, Default)]
+pub struct ModScan {
+    /// Mods found, sorted by file name.
+    pub mods: Vec<LocalMod>,
+    /// Jars without readable metadata.
+    pub unknown: Vec<PathBuf>,
+    pub issues: Vec<ModIssue>,
+}
+
+impl fmt::Display for ModLoader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModLoader::Fabric => write!(f, "Fabric"),
+            ModLoader::Quilt => write!(f, "Quilt"),
+            ModLoader::Forge => write!(f, "Forge"),
+            ModLoader::NeoForge => write!(f, "NeoForge"),
+            ModLoader::LegacyForge => write!(f, "Forge (legacy)"),
+        }
+    }
+}
+
+impl ModLoader {
+    /// Check if a mod made for this loader can be loaded by the instance.
+    pub fn is_compatible(&self, version_type: &VersionType) -> bool {
+        matches!(
+            (self, version_type),
+            (ModLoader::Fabric, VersionType::Fabric(_))
+                | (ModLoader::Fabric | ModLoader::Quilt, VersionType::Quilt(_))
+                | (
+                    ModLoader::Forge | ModLoader::LegacyForge,
+                    VersionType::Forge(_)
+                )
+                | (
+                    ModLoader::Forge | ModLoader::NeoForge,
+                    VersionType::NeoForge(_)
+                )
+        )
+    }
+}
+
+impl LocalMod {
+    /// Read the mods contained in a jar.
+    ///
+    /// Returns an empty list when the jar has no known metadata.
+    pub fn read(path: impl AsRef<Path>) -> GrindstoneResult<Vec<Self>> {
+        let path = path.as_ref();
+        let mut zip = ZipArchive::new(fs::File::open(path)?)?;
+
+        let mods = read_jar(&mut zip, 0)?
+            .into_iter()
+            .map(|info| LocalMod {
+                path: path.to_path_buf(),
+                id: info.id,
+                name: info.name,
+                version: info.version,
+                loader: info.loader,
+                dependencies: info.dependencies,
+                provides: info.provides,
+            })
+            .collect();
+
+        Ok(mods)
+    }
+}
+
+impl ModScan {
+    /// Scan the jars of a `mods` folder.
+    ///
+    /// Disabled jars (`.jar.disabled`) are ignored.
+    /// * `mods_path` - Folder to scan
+    /// * `version` - Version of the instance, used to check the mod loader
+    pub fn scan(mods_path: impl AsRef<Path>, version: &MinecraftVersion) -> GrindstoneResult<Self> {
+        let mut scan = ModScan::default();
+
+        if !mods_path.as_ref().is_dir() {
+            return Ok(scan);
+        }
+
+        let mut jars = fs::read_dir(mods_path)?
+            .map(|e| e.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        jars.retain(|p| p.is_file() && p.extension().map(|e| e == "jar").unwrap_or_default());
+        jars.sort();
+
+        for jar in jars {
+            trace!("Scanning {}", jar.to_string_lossy());
+
+            match LocalMod::read(&jar) {
+                Ok(mods) if !mods.is_empty() => scan.mods.extend(mods),
+                Ok(_) => scan.unknown.push(jar),
+                Err(err) => {
+                    debug!("Could not read {}: {}", jar.to_string_lossy(), err);
+                    scan.unknown.push(jar);
+                }
+            }
+        }
+
+        scan.issues = find_issues(&scan.mods, version);
+
+        Ok(scan)
+    }
+}
+
+fn find_issues(mods: &[LocalMod], version: &MinecraftVersion) -> Vec<ModIssue> {
+    let mut issues = Vec::new();
+
+    let mut by_id = HashMap::<&str, Vec<PathBuf>>::new();
+    for m in mods {
+        by_id.entry(&m.id).or_default().push(m.path.clone());
+    }
+
+    let mut duplicates = by_id
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .collect::<Vec<_>>();
+    duplicates.sort();
+    issues.extend(
+        duplicates
+            .into_iter()
+            .map(|(id, paths)| ModIssue::Duplicate {
+                id: id.to_string(),
+                paths,
+            }),
+    );
+
+    let installed = mods
+        .iter()
+        .flat_map(|m| std::iter::once(&m.id).chain(m.provides.iter()))
+        .map(String::as_str)
+        .collect::<HashSet<_>>();
+
+    for m in mods {
+        if !m.loader.is_compatible(&version.version_type) {
+            issues.push(ModIssue::WrongLoader {
+                id: m.id.clone(),
+                loader: m.loader,
+            });
+        }
+
+        for dependency in &m.dependencies {
+            let id = dependency.id.as_str();
+
+            match dependency.kind {
+                ModDependencyKind::Required
+                    if !installed.contains(id) && !PLATFORM_IDS.contains(&id) =>
+                {
+                    issues.push(ModIssue::MissingDependency {
+                        id: m.id.clone(),
+                        dependency: dependency.id.clone(),
+                    })
+                }
+                ModDependencyKind::Incompatible if installed.contains(id) => {
+                    issues.push(ModIssue::Incompatible {
+                        id: m.id.clone(),
+                        other: dependency.id.clone(),
+                    })
+                }
+                _ => {}
+            }
+        }
+    }
+
+    issues
+}
+
+/// Metadata of a mod, independent of the jar location.
+struct ModInfo {
+    id: String,
+    name: String,
+    version: String,
+    loader: ModLoader,
+    dependencies: Vec<ModDependency>,
+    provides: Vec<String>,
+}
+
+/// Read the mods of a jar, `depth` being the number of jars it is embedded in.
+///
+/// Mods without an ID are ignored.
+fn read_jar<R: Read + Seek>(
+    zip: &mut ZipArchive<R>,
+    depth: usize,
+) -> GrindstoneResult<Vec<ModInfo>> {
+    let mut mods = read_metadata(zip, depth)?;
+    mods.retain(|m| !m.id.is_empty());
+
+    Ok(mods)
+}
+
+fn read_metadata<R: Read + Seek>(
+    zip: &mut ZipArchive<R>,
+    depth: usize,
+) -> GrindstoneResult<Vec<ModInfo>> {
+    if let Some(content) = read_string(zip, FABRIC_MOD_JSON)? {
+        let mut info = parse_fabric(&serde_json::from_str(&content)?);
+        info.info
+            .provides
+            .extend(nested_ids(zip, &info.jars, depth)?);
+        return Ok(vec![info.info]);
+    }
+
+    if let Some(content) = read_string(zip, QUILT_MOD_JSON)? {
+        let mut info = parse_quilt(&serde_json::from_str(&content)?);
+        info.info
+            .provides
+            .extend(nested_ids(zip, &info.jars, depth)?);
+        return Ok(vec![info.info]);
+    }
+
+    for (file, loader) in [
+        (NEOFORGE_MODS_TOML, ModLoader::NeoForge),
+        (FORGE_MODS_TOML, ModLoader::Forge),
+    ] {
+        if let Some(content) = read_string(zip, file)? {
+            let manifest_version = read_string(zip, JAR_MANIFEST)?
+                .as_deref()
+                .and_then(implementation_version);
+
+            return parse_mods_toml(&content, loader, manifest_version);
+        }
+    }
+
+    if let Some(content) = read_string(zip, MCMOD_INFO)? {
+        return Ok(parse_mcmod_info(&serde_json::from_str(&content)?));
+    }
+
+    Ok(Vec::new())
+}
+
+/// IDs of the mods embedded in a jar.
+fn nested_ids<R: Read + Seek>(
+    zip: &mut ZipArchive<R>,
+    jars: &[String],
+    depth: usize,
+) -> GrindstoneResult<Vec<String>> {
+    let mut ids = Vec::new();
+
+    if depth >= MAX_NESTED_DEPTH {
+        debug!(
+            "Ignoring jars embedded more than {} times",
+            MAX_NESTED_DEPTH
+        );
+        return Ok(ids);
+    }
+
+    for jar in jars {
+        let content = match read_zip_entry(zip, jar) {
+            Ok(Some(content)) => content,
+            Ok(None) => continue,
+            Err(err) => {
+                debug!("Could not read embedded jar {}: {}", jar, err);
+                continue;
+            }
+        };
+
+        let mut nested = match ZipArchive::new(Cursor::new(content)) {
+            Ok(nested) => nested,
+            Err(err) => {
+                debug!("Could not read embedded jar {}: {}", jar, err);
+                continue;
+            }
+        };
+
+        for info in read_jar(&mut nested, depth + 1)? {
+            ids.push(info.id);
+            ids.extend(info.provides);
+        }
+    }
+
+    Ok(ids)
+}
+
+fn read_string<R: Read + Seek>(
+    zip: &mut ZipArchive<R>,
+    name: &str,
+) -> GrindstoneResult<Option<String>> {
+    let content = match read_zip_entry(zip, name)? {
+        Some(content) => content,
+        None => return Ok(None),
+    };
+
+    // Some mods write a BOM or invalid UTF-8 in their metadata
+    let content = String::from_utf8_lossy(&content);
+    Ok(Some(content.trim_start_matches('\u{feff}').to_string()))
+}
+
+/// Read `Implementation-Version` of a jar manifest, used by `${file.jarVersion}`.
+fn implementation_version(manifest: &str) -> Option<String> {
+    manifest
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| key.trim() == "Implementation-Version")
+        .map(|(_, value)| value.trim().to_string())
+}
+
+/// Metadata of a Fabric or Quilt mod, with its embedded jars.
+struct JsonModInfo {
+    info: ModInfo,
+    jars: Vec<String>,
+}
+
+fn str_field(value: &Value, key: &str) -> Option<String> {
+    value.get(key).and_then(Value::as_str).map(String::from)
+}
+
+/// Version requirement of a dependency, a string or a list of strings.
+fn version_requirement(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Array(values) => Some(
+            values
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join(" || "),
+        ),
+        _ => None,
+    }
+}
+
+fn parse_fabric(json: &Value) -> JsonModInfo {
+    let id = str_field(json, "id").unwrap_or_default();
+
+    let mut dependencies = Vec::new();
+    for (key, kind) in [
+        ("depends", ModDependencyKind::Required),
+        ("recommends", ModDependencyKind::Optional),
+        ("suggests", ModDependencyKind::Optional),
+        ("breaks", ModDependencyKind::Incompatible),
+    ] {
+        if let Some(map) = json.get(key).and_then(Value::as_object) {
+            dependencies.extend(map.iter().map(|(id, version)| ModDependency {
+                id: id.clone(),
+                version: version_requirement(version),
+                kind,
+            }));
+        }
+    }
+
+    let provides = json
+        .get("provides")
+        .and_then(Value::as_array)
+        .map(|p| {
+            p.iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let jars = json
+        .get("jars")
+        .and_then(Value::as_array)
+        .map(|jars| jars.iter().filter_map(|j| str_field(j, "file")).collect())
+        .unwrap_or_default();
+
+    JsonModInfo {
+        info: ModInfo {
+            name: str_field(json, "name").unwrap_or_else(|| id.clone()),
+            version: str_field(json, "version").unwrap_or_default(),
+            id,
+            loader: ModLoader::Fabric,
+            dependencies,
+            provides,
+        },
+        jars,
+    }
+}
+
+fn parse_quilt(json: &Value) -> JsonModInfo {
+    let loader = json.get("quilt_loader").unwrap_or(&Value::Null);
+    let id = str_field(loader, "id").unwrap_or_default();
+
+    // Entries are either an ID or an object with an ID
+    let entry_id = |entry: &Value| match entry {
+        Value::String(id) => Some(id.clone()),
+        _ => str_field(entry, "id"),
+    };
+
+    let mut dependencies = Vec::new();
+    for (key, kind) in [
+        ("depends", ModDependencyKind::Required),
+        ("breaks", ModDependencyKind::Incompatible),
+    ] {
+        let entries = loader.get(key).and_then(Value::as_array);
+
+        for entry in entries.into_iter().flatten() {
+            let dep_id = match entry_id(entry) {
+                Some(dep_id) => dep_id,
+                None => continue,
+            };
+
+            let optional = entry
+                .get("optional")
+                .and_then(Value::as_bool)
+                .unwrap_or_default();
+
+            dependencies.push(ModDependency {
+                id: dep_id,
+                version: entry.get("versions").and_then(version_requirement),
+                kind: if optional && kind == ModDependencyKind::Required {
+                    ModDependencyKind::Optional
+                } else {
+                    kind
+                },
+            });
+        }
+    }
+
+    let list = |key: &str| -> Vec<String> {
+        loader
+            .get(key)
+            .and_then(Value::as_array)
+            .map(|entries| entries.iter().filter_map(entry_id).collect())
+            .unwrap_or_default()
+    };
+
+    let jars = loader
+        .get("jars")
+        .and_then(Value::as_array)
+        .map(|jars| {
+            jars.iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    JsonModInfo {
+        info: ModInfo {
+            name: loader
+                .get("metadata")
+                .and_then(|m| str_field(m, "name"))
+                .unwrap_or_else(|| id.clone()),
+            version: str_field(loader, "version").unwrap_or_default(),
+            id,
+            loader: ModLoader::Quilt,
+            dependencies,
+            provides: list("provides"),
+        },
+        jars,
+    }
+}
+
+#[derive(Deserialize)]
+struct ModsToml {
+    #[serde(default)]
+    mods: Vec<ModsTomlMod>,
+    #[serde(default)]
+    dependencies: HashMap<String, Vec<ModsTomlDependency>>,
+}
+
+#[derive(Deserialize)]
+struct ModsTomlMod {
+    #[serde(alias = "modId")]
+    mod_id: String,
+    version: Option<String>,
+    #[serde(alias = "displayName")]
+    display_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ModsTomlDependency {
+    #[serde(alias = "modId")]
+    mod_id: String,
+    /// Used by Forge.
+    mandatory: Option<bool>,
+    /// Used by NeoForge: `required`, `optional`, `incompatible` or `discouraged`.
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    #[serde(alias = "versionRange")]
+    version_range: Option<String>,
+}
+
+fn parse_mods_toml(
+    content: &str,
+    loader: ModLoader,
+    manifest_version: Option<String>,
+) -> GrindstoneResult<Vec<ModInfo>> {
+    let mut toml = match toml::from_str::<ModsToml>(content) {
+        Ok(toml) => toml,
+        Err(err) => {
+            debug!("Invalid mods.toml: {}", err);
+            return Ok(Vec::new());
+        }
+    };
+
+    let mods = toml
+        .mods
+        .into_iter()
+        .map(|m| {
+            let version = match m.version {
+                Some(v) if v == "${file.jarVersion}" => manifest_version.clone().unwrap_or(v),
+                Some(v) => v,
+                None => manifest_version.clone().unwrap_or_default(),
+            };
+
+            let dependencies = toml
+                .dependencies
+                .remove(&m.mod_id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|d| {
+                    let kind = match (d.kind.as_deref(), d.mandatory) {
+                        (Some("required"), _) | (None, Some(true)) => ModDependencyKind::Required,
+                        (Some("incompatible"), _) => ModDependencyKind::Incompatible,
+                        _ => ModDependencyKind::Optional,
+                    };
+
+                    ModDependency {
+                        id: d.mod_id,
+                        version: d.version_range,
+                        kind,
+                    }
+                })
+                .collect();
+
+            ModInfo {
+                name: m.display_name.unwrap_or_else(|| m.mod_id.clone()),
+                id: m.mod_id,
+                version,
+                loader,
+                dependencies,
+                provides: Vec::new(),
+            }
+        })
+        .collect();
+
+    Ok(mods)
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum McModInfo {
+    List(Vec<McModInfoMod>),
+    V2 {
+        #[serde(alias = "modList")]
+        mod_list: Vec<McModInfoMod>,
+    },
+}
+
+#[derive(Deserialize)]
+struct McModInfoMod {
+    modid: String,
+    name: Option<String>,
+    version: Option<String>,
+    #[serde(default, alias = "requiredMods")]
+    required_mods: Vec<String>,
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+fn parse_mcmod_info(json: &Value) -> Vec<ModInfo> {
+    let mods = match McModInfo::deserialize(json) {
+        Ok(McModInfo::List(mods)) | Ok(McModInfo::V2 { mod_list: mods }) => mods,
+        Err(err) => {
+            debug!("Invalid mcmod.info: {}", err);
+            return Vec::new();
+        }
+    };
+
+    mods.into_iter()
+        .map(|m| {
+            // Entries may carry a version, like `modid@[1.0,)`
+            let dependencies = m
+                .required_mods
+                .iter()
+                .map(|d| (d, ModDependencyKind::Required))
+                .chain(
+                    m.dependencies
+                        .iter()
+                        .filter(|d| !m.required_mods.contains(d))
+                        .map(|d| (d, ModDependencyKind::Optional)),
+                )
+                .map(|(d, kind)| {
+                    let (id, version) = match d.split_once('@') {
+                        Some((id, version)) => (id, Some(version.to_string())),
+                        None => (d.as_str(), None),
+                    };
+
+                    ModDependency {
+                        id: id.to_lowercase(),
+                        version,
+                        kind,
+                    }
+                })
+                .collect();
+
+            // Forge compares legacy IDs without case, like the dependencies
+            ModInfo {
+                name: m.name.unwrap_or_else(|| m.modid.clone()),
+                id: m.modid.to_lowercase(),
+                version: m.version.unwrap_or_default(),
+                loader: ModLoader::LegacyForge,
+                dependencies,
+                provides: Vec::new(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::{write::FileOptions, ZipWriter};
+
+    use crate::{utils::fs::test_folder, version::MinecraftVersionBuilder};
+
+    use super::*;
+
+    const FABRIC: &str = r#"{
+        "schemaVersion": 1,
+        "id": "sodium",
+        "name": "Sodium",
+        "version": "0.5.3",
+        "depends": {"minecraft": "1.20.1", "fabric-api": ["*"]},
+        "suggests": {"modmenu": "*"},
+        "breaks": {"optifabric": "*"},
+        "provides": ["indium"],
+        "jars": [{"file": "META-INF/jars/lib.jar"}]
+    }"#;
+
+    const MODS_TOML: &str = r#"
+        modLoader = "javafml"
+        loaderVersion = "[47,)"
+
+        [[mods]]
+        modId = "jei"
+        version = "${file.jarVersion}"
+        displayName = "Just Enough Items"
+
+        [[dependencies.jei]]
+        modId = "forge"
+        mandatory = true
+        versionRange = "[47,)"
+
+        [[dependencies.jei]]
+        modId = "rei"
+        type = "incompatible"
+    "#;
+
+    fn jar(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in entries {
+            zip.start_file(*name, FileOptions::default()).unwrap();
+            zip.write_all(content).unwrap();
+        }
+
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn read_fabric() {
+        let folder = test_folder("scan-fabric");
+        let path = folder.join("sodium.jar");
+        let nested = jar(&[(FABRIC_MOD_JSON, br#"{"id": "lib", "version": "1.0"}"#)]);
+        fs::write(
+            &path,
+            jar(&[
+                (FABRIC_MOD_JSON, format!("\u{feff}{}", FABRIC).as_bytes()),
+                ("META-INF/jars/lib.jar", &nested),
+            ]),
+        )
+        .unwrap();
+
+        let mods = LocalMod::read(&path).unwrap();
+        assert_eq!(mods.len(), 1);
+
+        let sodium = &mods[0];
+        assert_eq!(
+            (sodium.id.as_str(), sodium.name.as_str()),
+            ("sodium", "Sodium")
+        );
+        assert_eq!(sodium.loader, ModLoader::Fabric);
+        assert_eq!(sodium.provides, ["indium", "lib"]);
+
+        let dependency = |id: &str| sodium.dependencies.iter().find(|d| d.id == id).unwrap();
+        assert_eq!(dependency("fabric-api").kind, ModDependencyKind::Required);
+        assert_eq!(dependency("fabric-api").version.as_deref(), Some("*"));
+        assert_eq!(dependency("modmenu").kind, ModDependencyKind::Optional);
+        assert_eq!(
+            dependency("optifabric").kind,
+            ModDependencyKind::Incompatible
+        );
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn nested_depth() {
+        let mut nested = jar(&[(FABRIC_MOD_JSON, br#"{"id": "lib0"}"#)]);
+        for depth in 1..=MAX_NESTED_DEPTH + 1 {
+            let metadata = format!(
+                r#"{{"id": "lib{}", "jars": [{{"file": "nested.jar"}}]}}"#,
+                depth
+            );
+            nested = jar(&[
+                (FABRIC_MOD_JSON, metadata.as_bytes()),
+                ("nested.jar", &nested),
+            ]);
+        }
+
+        let mods = read_jar(&mut ZipArchive::new(Cursor::new(nested)).unwrap(), 0).unwrap();
+        assert_eq!(mods[0].id, format!("lib{}", MAX_NESTED_DEPTH + 1));
+        assert_eq!(mods[0].provides.len(), MAX_NESTED_DEPTH);
+    }
+
+    #[test]
+    fn read_forge() {
+        let mods =
+            parse_mods_toml(MODS_TOML, ModLoader::Forge, Some("15.2.0".to_string())).unwrap();
+        assert_eq!(mods.len(), 1);
+        assert_eq!(mods[0].version, "15.2.0");
+        assert_eq!(mods[0].name, "Just Enough Items");
+        assert_eq!(mods[0].dependencies[0].kind, ModDependencyKind::Required);
+        assert_eq!(
+            mods[0].dependencies[1].kind,
+            ModDependencyKind::Incompatible
+        );
+
+        assert!(parse_mods_toml("not = [toml", ModLoader::Forge, None)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            implementation_version("Manifest-Version: 1.0\r\nImplementation-Version: 15.2.0\r\n"),
+            Some("15.2.0".to_string())
+        );
+
+        let legacy = parse_mcmod_info(
+            &serde_json::from_str(
+                r#"{"modListVersion": 2, "modList": [{
+                    "modid": "JourneyMap", "version": "5.7.1",
+                    "requiredMods": ["Forge@[14.23,)"], "dependencies": ["Forge@[14.23,)", "Baubles"]
+                }]}"#,
+            )
+            .unwrap(),
+        );
+        assert_eq!(legacy[0].id, "journeymap");
+        assert_eq!(legacy[0].name, "JourneyMap");
+        assert_eq!(legacy[0].dependencies.len(), 2);
+        assert_eq!(legacy[0].dependencies[0].id, "forge");
+        assert_eq!(
+            legacy[0].dependencies[0].version.as_deref(),
+            Some("[14.23,)")
+        );
+        assert_eq!(legacy[0].dependencies[1].kind, ModDependencyKind::Optional);
+    }
+
+    #[test]
+    fn read_quilt() {
+        let info = parse_quilt(
+            &serde_json::from_str(
+                r#"{"quilt_loader": {
+                    "id": "qsl", "version": "6.1.1",
+                    "metadata": {"name": "Quilt Standard Libraries"},
+                    "depends": ["minecraft", {"id": "owo", "optional": true}],
+                    "breaks": [{"id": "optifabric", "versions": ["<1.0", ">2.0"]}],
+                    "provides": [{"id": "fabric-api"}]
+                }}"#,
+            )
+            .unwrap(),
+        );
+
+        assert_eq!(info.info.name, "Quilt Standard Libraries");
+        assert_eq!(info.info.provides, ["fabric-api"]);
+        assert_eq!(info.info.dependencies[1].kind, ModDependencyKind::Optional);
+        assert_eq!(
+            info.info.dependencies[2].version.as_deref(),
+            Some("<1.0 || >2.0")
+        );
+    }
+
+    #[test]
+    fn scan_issues() {
+        let folder = test_folder("scan");
+        let fabric = jar(&[(FABRIC_MOD_JSON, FABRIC.as_bytes())]);
+        let optifabric = jar(&[(FABRIC_MOD_JSON, br#"{"id": "optifabric"}"#)]);
+        fs::write(folder.join("a-sodium.jar"), &fabric).unwrap();
+        fs::write(folder.join("b-sodium.jar"), &fabric).unwrap();
+        fs::write(folder.join("optifabric.jar"), optifabric).unwrap();
+        fs::write(
+            folder.join("jei.jar"),
+            jar(&[(FORGE_MODS_TOML, MODS_TOML.as_bytes())]),
+        )
+        .unwrap();
+        fs::write(folder.join("readme.jar"), jar(&[("README", b"")])).unwrap();
+        fs::write(
+            folder.join("no-id.jar"),
+            jar(&[(FABRIC_MOD_JSON, br#"{"name": "No ID"}"#)]),
+        )
+        .unwrap();
+        fs::write(folder.join("broken.jar"), b"not a zip").unwrap();
+        fs::write(folder.join("disabled.jar.disabled"), &fabric).unwrap();
+
+        let version = MinecraftVersionBuilder::default()
+            .version_id("1.20.1")
+            .version_type(VersionType::Fabric("0.14.21".to_string()))
+            .build();
+        let scan = ModScan::scan(&folder, &version).unwrap();
+
+        assert_eq!(scan.mods.len(), 4);
+        assert_eq!(scan.unknown.len(), 3);
+
+        let issue = |check: fn(&ModIssue) -> bool| scan.issues.iter().any(check);
+        assert!(issue(
+            |i| matches!(i, ModIssue::Duplicate { id, paths } if id == "sodium" && paths.len() == 2)
+        ));
+        assert!(issue(
+            |i| matches!(i, ModIssue::WrongLoader { id, .. } if id == "jei")
+        ));
+        assert!(issue(
+            |i| matches!(i, ModIssue::MissingDependency { dependency, .. } if dependency == "fabric-api")
+        ));
+        assert!(issue(
+            |i| matches!(i, ModIssue::Incompatible { other, .. } if other == "optifabric")
+        ));
+        // Provided by the game or the loader
+        assert!(!issue(
+            |i| matches!(i, ModIssue::MissingDependency { dependency, .. } if dependency == "minecraft" || dependency == "forge")
+        ));
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
@@ -1,6 +1,10 @@
 use std::path::PathBuf;
 
-use crate::config::Config;
+use crate::{
+    config::Config,
+    errors::{GrindstoneError, GrindstoneResult},
+    version::VersionType,
+};
 
 impl Config {
     /// Path to the updater folder
@@ -9,35 +13,113 @@ impl Config {
         PathBuf::from(&self.folder_path)
     }
 
-    /// Path to the instance folder
-    pub fn current_instance(&self) -> PathBuf {
+    /// Path to the version manifest saved by the last online update
+    pub fn version_manifest_path(&self) -> PathBuf {
+        let mut path = self.updater_folder();
+        path.push("version_manifest.json");
+        path
+    }
+
+    /// Path to the cached responses of the manifests
+    pub fn metadata_cache_path(&self) -> PathBuf {
+        let mut path = self.updater_folder();
+        path.push("cache");
+        path
+    }
+
+    /// Path to the folder holding all the instances
+    pub fn instances_path(&self) -> PathBuf {
         let mut path = self.updater_folder();
         path.push("instances");
+        path
+    }
+
+    /// Path to the instance folder
+    pub fn current_instance(&self) -> PathBuf {
+        let mut path = self.instances_path();
         path.push(self.instance_name.clone());
         path
     }
 
-    /// Path to the .minecraft folder
-    pub fn dot_minecraft_path(&self) -> PathBuf {
-        use cfg_if::cfg_if;
+    /// Path to the game directory, passed to the game as `${game_directory}`
+    /// Everything specific to the instance (saves, mods, options, logs...) lives here,
+    /// while libraries, assets and versions stay shared in the .minecraft folder
+    pub fn game_directory(&self) -> PathBuf {
+        self.current_instance()
+    }
 
-        cfg_if! {
-            if #[cfg(windows)] {
-                let mut path = dirs::data_dir().unwrap();
+    /// Path to the multiplayer server list of the instance
+    pub fn servers_dat_path(&self) -> PathBuf {
+        let mut path = self.game_directory();
+        path.push("servers.dat");
+        path
+    }
 
-            } else if #[cfg(any(unix,macos))] {
-                let mut path = dirs::home_dir().unwrap();
-            } else {
-                compile_error!("Unknown platform {}", env::consts::OS)
-            }
-        }
+    /// Path to the worlds of the instance
+    pub fn saves_path(&self) -> PathBuf {
+        let mut path = self.game_directory();
+        path.push("saves");
+        path
+    }
+
+    /// Path to the game options of the instance
+    pub fn options_path(&self) -> PathBuf {
+        let mut path = self.game_directory();
+        path.push("options.txt");
+        path
+    }
+
+    /// Path to the mods of the instance
+    pub fn mods_path(&self) -> PathBuf {
+        let mut path = self.game_directory();
+        path.push("mods");
+        path
+    }
+
+    /// Path to the mods configuration files of the instance
+    pub fn mods_config_path(&self) -> PathBuf {
+        let mut path = self.game_directory();
+        path.push("config");
+        path
+    }
 
-        path.push(".minecraft");
+    /// Path to the resource packs of the instance
+    pub fn resource_packs_path(&self) -> PathBuf {
+        let mut path = self.game_directory();
+        path.push("resourcepacks");
         path
     }
 
+    /// Path to the shader packs of the instance
+    pub fn shader_packs_path(&self) -> PathBuf {
+        let mut path = self.game_directory();
+        path.push("shaderpacks");
+        path
+    }
+
+    /// Path to the game logs of the instance
+    pub fn logs_path(&self) -> PathBuf {
+        let mut path = self.game_directory();
+        path.push("logs");
+        path
+    }
+
+    /// Path to the assets mapped as resources of the instance
+    /// Used by versions older than 1.6
+    pub fn resources_path(&self) -> PathBuf {
+        let mut path = self.game_directory();
+        path.push("resources");
+        path
+    }
+
+    /// Path to the shared game folder
+    /// Versions, libraries and assets are shared between instances
+    pub fn dot_minecraft_path(&self) -> PathBuf {
+        PathBuf::from(&self.game_root)
+    }
+
     /// Path to the game versions
-    /// Version are shared with the official Minecraft launcher
+    /// Versions are shared between instances
     pub fn versions_path(&self) -> PathBuf {
         let mut path = self.dot_minecraft_path();
         path.push("versions");
@@ -54,14 +136,27 @@ impl Config {
     }
 
     /// Path to the version manifest file
+    /// Versions using a mod loader have their own, patched by the mod loader
     pub fn version_data_path(&self) -> PathBuf {
         let mut path = self.versions_path();
-        let v = self.version.id.clone();
+        let v = match &self.version.version_type {
+            VersionType::Fabric(loader) => format!("fabric-loader-{}-{}", loader, self.version.id),
+            VersionType::Quilt(loader) => format!("quilt-loader-{}-{}", loader, self.version.id),
+            _ => self.version.id.clone(),
+        };
         path.push(&v);
         path.push(format!("{}.json", v));
         path
     }
 
+    /// Path to the Java runtimes
+    /// Runtimes are shared between instances
+    pub fn runtimes_path(&self) -> PathBuf {
+        let mut path = self.dot_minecraft_path();
+        path.push("runtime");
+        path
+    }
+
     /// Path to the game assets.
     pub fn assets_path(&self) -> PathBuf {
         let mut path = self.dot_minecraft_path();
@@ -69,15 +164,31 @@ impl Config {
         path
     }
 
-    /// Path to the asset index JSON file.
+    /// Path to the asset index JSON files.
     pub fn asset_index_path(&self) -> PathBuf {
         let mut path = self.assets_path();
         path.push("indexes");
         path
     }
 
+    /// Path to the JSON file of an asset index.
+    pub fn asset_index_file_path(&self, index_id: &str) -> PathBuf {
+        let mut path = self.asset_index_path();
+        path.push(format!("{}.json", index_id));
+        path
+    }
+
+    /// Path to the virtual assets of an asset index, as named files.
+    /// Used by versions older than 1.7 (`legacy` index)
+    pub fn virtual_assets_path(&self, index_id: &str) -> PathBuf {
+        let mut path = self.assets_path();
+        path.push("virtual");
+        path.push(index_id);
+        path
+    }
+
     /// Path to the game libraries
-    /// Libraries are shared with the official Minecraft launcher
+    /// Libraries are shared between instances
     pub fn libraries_path(&self) -> PathBuf {
         let mut path = self.dot_minecraft_path();
         path.push("libraries");
@@ -85,7 +196,7 @@ impl Config {
     }
 
     /// Path to the game libraries
-    /// Libraries are shared with the official Minecraft launcher
+    /// Libraries are shared between instances
     pub fn natives_path(&self) -> PathBuf {
         let mut path = self.dot_minecraft_path();
         path.push("libraries");
@@ -99,3 +210,22 @@ impl Config {
         path
     }
 }
+
+/// Path to the folder used by the official Minecraft launcher
+pub fn official_minecraft_path() -> GrindstoneResult<PathBuf> {
+    use cfg_if::cfg_if;
+
+    cfg_if! {
+        if #[cfg(windows)] {
+            let path = dirs::data_dir().map(|p| p.join(".minecraft"));
+        } else if #[cfg(target_os = "macos")] {
+            let path = dirs::data_dir().map(|p| p.join("minecraft"));
+        } else if #[cfg(unix)] {
+            let path = dirs::home_dir().map(|p| p.join(".minecraft"));
+        } else {
+            compile_error!("Unknown platform {}", env::consts::OS)
+        }
+    }
+
+    path.ok_or(GrindstoneError::HomeDirectoryNotFound)
+}
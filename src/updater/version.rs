@@ -1,7 +1,12 @@
-#[derive(Debug, PartialEq, Eq, Clone)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
 pub enum VersionType {
     Vanilla,
     Forge(String),
+    NeoForge(String),
+    Fabric(String),
+    Quilt(String),
     MCP,
 }
 
@@ -10,7 +15,7 @@ pub struct MinecraftVersionBuilder {
     version_type: VersionType,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct MinecraftVersion {
     pub id: String,
     pub version_type: VersionType,
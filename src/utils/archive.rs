@@ -0,0 +1,265 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read, Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use log::trace;
+use zip::{result::ZipError, write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::errors::{GrindstoneError, GrindstoneResult};
+
+use super::fs::write_atomic_with;
+
+/// Compress the content of a folder into a zip archive.
+///
+/// * `source` - Folder to compress
+/// * `dest` - Path of the zip archive
+/// * `prefix` - Folder inside the archive where the content is placed
+/// * `skip` - Returns `true` for files that must not be archived
+pub fn zip_folder(
+    source: impl AsRef<Path>,
+    dest: impl AsRef<Path>,
+    prefix: &str,
+    skip: impl Fn(&Path) -> bool,
+) -> GrindstoneResult<()> {
+    if let Some(parent) = dest.as_ref().parent() {
+        trace!("Creating parent folder for archive");
+        fs::create_dir_all(parent)?;
+    }
+
+    write_atomic_with(dest, |writer| {
+        let mut zip = ZipWriter::new(writer);
+        add_folder(&mut zip, source, prefix, skip)?;
+        zip.finish()?;
+
+        Ok(())
+    })
+}
+
+/// Add the content of a folder to a zip archive.
+///
+/// * `zip` - Archive being written
+/// * `source` - Folder to add
+/// * `prefix` - Folder inside the archive where the content is placed
+/// * `skip` - Returns `true` for files that must not be archived
+pub fn add_folder<W: Write + Seek>(
+    zip: &mut ZipWriter<W>,
+    source: impl AsRef<Path>,
+    prefix: &str,
+    skip: impl Fn(&Path) -> bool,
+) -> GrindstoneResult<()> {
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let mut folders = vec![source.as_ref().to_path_buf()];
+    while let Some(folder) = folders.pop() {
+        for entry in fs::read_dir(&folder)? {
+            let entry = entry?;
+            let path = entry.path();
+            let relative = path.strip_prefix(source.as_ref()).unwrap_or(&path);
+
+            if skip(relative) {
+                continue;
+            }
+
+            let name = archive_name(prefix, relative);
+
+            // `DirEntry::file_type` does not follow symlinks, so a link back to a
+            // parent folder cannot make this loop forever
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                zip.add_directory(name, options)?;
+                folders.push(path);
+            } else if file_type.is_symlink() && path.is_dir() {
+                trace!("Ignoring symlinked folder {}", name);
+            } else {
+                trace!("Archiving {}", name);
+                zip.start_file(name, options)?;
+                io::copy(&mut File::open(&path)?, zip)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Extract a zip archive into a folder.
+///
+/// Only the entries under `prefix` are extracted, with the prefix removed.
+/// Entries pointing outside of the destination are ignored.
+/// Returns the paths of the extracted files.
+/// * `skip` - Returns `true` for the paths, relative to `dest`, that must not be extracted
+pub fn extract_zip(
+    archive: impl AsRef<Path>,
+    dest: impl AsRef<Path>,
+    prefix: &str,
+    skip: impl Fn(&Path) -> bool,
+) -> GrindstoneResult<Vec<PathBuf>> {
+    let mut zip = ZipArchive::new(File::open(archive)?)?;
+    let mut extracted = Vec::new();
+
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i)?;
+
+        let name = match entry.enclosed_name() {
+            Some(name) => name.to_path_buf(),
+            None => {
+                trace!("Ignoring unsafe archive entry {}", entry.name());
+                continue;
+            }
+        };
+
+        let relative = match name.strip_prefix(prefix) {
+            Ok(relative) if !relative.as_os_str().is_empty() => relative.to_path_buf(),
+            _ => continue,
+        };
+
+        if skip(&relative) {
+            trace!("Skipping archive entry {}", entry.name());
+            continue;
+        }
+
+        let path = dest.as_ref().join(relative);
+
+        if entry.is_dir() {
+            fs::create_dir_all(&path)?;
+            continue;
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        trace!("Extracting {}", path.to_string_lossy());
+        io::copy(&mut entry, &mut File::create(&path)?)?;
+        extracted.push(path);
+    }
+
+    Ok(extracted)
+}
+
+/// Largest entry read in memory, the size written in the archive is not trusted.
+const MAX_ENTRY_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Read a single entry of a zip archive, `None` if the archive does not contain it.
+pub fn read_entry(archive: impl AsRef<Path>, name: &str) -> GrindstoneResult<Option<Vec<u8>>> {
+    let mut zip = ZipArchive::new(File::open(archive)?)?;
+    read_zip_entry(&mut zip, name)
+}
+
+/// Read a single entry of an opened zip archive, `None` if the archive does not contain it.
+///
+/// Entries larger than 64 MiB are rejected.
+pub fn read_zip_entry<R: Read + Seek>(
+    zip: &mut ZipArchive<R>,
+    name: &str,
+) -> GrindstoneResult<Option<Vec<u8>>> {
+    let entry = match zip.by_name(name) {
+        Ok(entry) => entry,
+        Err(ZipError::FileNotFound) => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    read_limited(entry, name, MAX_ENTRY_SIZE).map(Some)
+}
+
+fn read_limited(reader: impl Read, name: &str, limit: u64) -> GrindstoneResult<Vec<u8>> {
+    let mut content = Vec::new();
+    reader.take(limit + 1).read_to_end(&mut content)?;
+
+    if content.len() as u64 > limit {
+        return Err(GrindstoneError::InvalidArchive(format!(
+            "entry {} is larger than {} bytes",
+            name, limit
+        )));
+    }
+
+    Ok(content)
+}
+
+/// Build the name of an entry, always using `/` as separator.
+fn archive_name(prefix: &str, relative: &Path) -> String {
+    let mut parts = Vec::new();
+
+    if !prefix.is_empty() {
+        parts.push(prefix.trim_end_matches('/').to_string());
+    }
+
+    parts.extend(
+        relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string()),
+    );
+
+    parts.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::fs::test_folder;
+
+    use super::*;
+
+    #[test]
+    fn zip_and_extract() {
+        let folder = test_folder("archive-roundtrip");
+        let source = folder.join("source");
+        fs::create_dir_all(source.join("sub")).unwrap();
+        fs::write(source.join("a.txt"), "a").unwrap();
+        fs::write(source.join("sub").join("b.txt"), "b").unwrap();
+        fs::write(source.join("skipped.txt"), "skipped").unwrap();
+
+        let archive = folder.join("archive.zip");
+        zip_folder(&source, &archive, "prefix", |path| {
+            path == Path::new("skipped.txt")
+        })
+        .unwrap();
+
+        let dest = folder.join("dest");
+        let mut extracted = extract_zip(&archive, &dest, "prefix", |path| {
+            path == Path::new("sub/b.txt")
+        })
+        .unwrap();
+        extracted.sort();
+
+        assert_eq!(extracted, vec![dest.join("a.txt")]);
+        assert_eq!(fs::read_to_string(dest.join("a.txt")).unwrap(), "a");
+        assert!(!dest.join("sub").join("b.txt").exists());
+        assert_eq!(
+            read_entry(&archive, "prefix/a.txt").unwrap(),
+            Some(b"a".to_vec())
+        );
+        assert_eq!(read_entry(&archive, "prefix/skipped.txt").unwrap(), None);
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn entry_size_limit() {
+        assert_eq!(read_limited(&b"1234"[..], "a", 4).unwrap(), b"1234");
+        assert!(matches!(
+            read_limited(&b"12345"[..], "a", 4),
+            Err(GrindstoneError::InvalidArchive(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_loop() {
+        let folder = test_folder("archive-symlink");
+        let source = folder.join("source");
+        fs::create_dir_all(source.join("sub")).unwrap();
+        fs::write(source.join("sub").join("a.txt"), "a").unwrap();
+        std::os::unix::fs::symlink(&source, source.join("sub").join("loop")).unwrap();
+
+        let archive = folder.join("archive.zip");
+        zip_folder(&source, &archive, "", |_| false).unwrap();
+
+        let zip = ZipArchive::new(File::open(&archive).unwrap()).unwrap();
+        let mut names: Vec<_> = zip.file_names().collect();
+        names.sort();
+        assert_eq!(names, vec!["sub/", "sub/a.txt"]);
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
@@ -0,0 +1,216 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::{
+    header::{HeaderMap, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+    RequestBuilder,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::GrindstoneResult;
+
+use super::{fs::write_atomic, sha1::get_sha1_bytes};
+
+/// Responses of metadata requests saved on disk, revalidated with their `ETag` or `Last-Modified` header.
+#[derive(Clone, Debug)]
+pub struct MetadataCache {
+    folder: PathBuf,
+    max_age: Duration,
+}
+
+/// A response saved in the cache.
+pub struct CachedResponse {
+    meta: CacheMeta,
+    pub body: Vec<u8>,
+}
+
+/// Validators and age of a saved response.
+#[derive(Deserialize, Serialize)]
+struct CacheMeta {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    /// Time of the last download or revalidation, in seconds since the Unix epoch.
+    fetched_at: u64,
+}
+
+impl MetadataCache {
+    /// * `max_age` - Time during which a saved response is used without asking the server
+    pub fn new(folder: PathBuf, max_age: Duration) -> Self {
+        Self { folder, max_age }
+    }
+
+    /// Saved response of a URL, `None` if there is none or it cannot be read.
+    pub fn get(&self, url: &str) -> Option<CachedResponse> {
+        let (body_path, meta_path) = self.paths(url);
+
+        let meta = fs::read(meta_path)
+            .ok()
+            .and_then(|meta| serde_json::from_slice::<CacheMeta>(&meta).ok())
+            .filter(|meta| meta.url == url)?;
+        let body = fs::read(body_path).ok()?;
+
+        Some(CachedResponse { meta, body })
+    }
+
+    /// Check if a saved response can be used without asking the server.
+    pub fn is_fresh(&self, cached: &CachedResponse) -> bool {
+        now().saturating_sub(cached.meta.fetched_at) < self.max_age.as_secs()
+    }
+
+    /// Save a response with its validators.
+    pub fn put(&self, url: &str, headers: &HeaderMap, body: &[u8]) -> GrindstoneResult<()> {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(String::from)
+        };
+
+        let meta = CacheMeta {
+            url: url.to_string(),
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+            fetched_at: now(),
+        };
+
+        let (body_path, meta_path) = self.paths(url);
+        fs::create_dir_all(&self.folder)?;
+        write_atomic(body_path, body)?;
+        write_atomic(meta_path, serde_json::to_vec(&meta)?)
+    }
+
+    /// Mark a saved response as fresh again, after the server answered it did not change.
+    pub fn refresh(&self, url: &str, cached: &mut CachedResponse) -> GrindstoneResult<()> {
+        cached.meta.fetched_at = now();
+
+        let (_, meta_path) = self.paths(url);
+        write_atomic(meta_path, serde_json::to_vec(&cached.meta)?)
+    }
+
+    /// Paths of the body and metadata files of a URL.
+    fn paths(&self, url: &str) -> (PathBuf, PathBuf) {
+        let key = hex::encode(get_sha1_bytes(url.as_bytes()));
+
+        (
+            self.folder.join(format!("{}.json", key)),
+            self.folder.join(format!("{}.meta.json", key)),
+        )
+    }
+}
+
+impl CachedResponse {
+    /// Ask the server to only send the response if it changed.
+    pub fn revalidate(&self, mut request: RequestBuilder) -> RequestBuilder {
+        if let Some(etag) = &self.meta.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &self.meta.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+
+        request
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+
+    use crate::utils::fs::test_folder;
+
+    use super::*;
+
+    const URL: &str = "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json";
+
+    fn headers() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(ETAG, HeaderValue::from_static("\"abc\""));
+        headers.insert(
+            LAST_MODIFIED,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+
+        headers
+    }
+
+    #[test]
+    fn put_and_get() {
+        let folder = test_folder("cache");
+        let cache = MetadataCache::new(folder.clone(), Duration::from_secs(60));
+
+        assert!(cache.get(URL).is_none());
+        cache.put(URL, &headers(), b"{}").unwrap();
+
+        let cached = cache.get(URL).unwrap();
+        assert_eq!(cached.body, b"{}");
+        assert!(cache.is_fresh(&cached));
+        assert!(cache.get("https://example.com").is_none());
+
+        // Unreadable metadata is a cache miss
+        let (_, meta_path) = cache.paths(URL);
+        fs::write(&meta_path, b"not json").unwrap();
+        assert!(cache.get(URL).is_none());
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn refresh_stale_response() {
+        let folder = test_folder("cache-refresh");
+        let cache = MetadataCache::new(folder.clone(), Duration::from_secs(60));
+
+        cache.put(URL, &HeaderMap::new(), b"{}").unwrap();
+        let mut cached = cache.get(URL).unwrap();
+        cached.meta.fetched_at = 0;
+        assert!(!cache.is_fresh(&cached));
+
+        cache.refresh(URL, &mut cached).unwrap();
+        assert!(cache.is_fresh(&cache.get(URL).unwrap()));
+        assert!(!MetadataCache::new(folder.clone(), Duration::ZERO).is_fresh(&cached));
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn revalidate_headers() {
+        let folder = test_folder("cache-revalidate");
+        let cache = MetadataCache::new(folder.clone(), Duration::from_secs(60));
+        let client = reqwest::Client::new();
+
+        cache.put(URL, &headers(), b"{}").unwrap();
+        let request = cache
+            .get(URL)
+            .unwrap()
+            .revalidate(client.get(URL))
+            .build()
+            .unwrap();
+        assert_eq!(request.headers()[IF_NONE_MATCH], "\"abc\"");
+        assert_eq!(
+            request.headers()[IF_MODIFIED_SINCE],
+            "Wed, 21 Oct 2015 07:28:00 GMT"
+        );
+
+        // Without validators, the request is unchanged
+        cache.put(URL, &HeaderMap::new(), b"{}").unwrap();
+        let request = cache
+            .get(URL)
+            .unwrap()
+            .revalidate(client.get(URL))
+            .build()
+            .unwrap();
+        assert!(request.headers().is_empty());
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
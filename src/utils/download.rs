@@ -1,105 +1,812 @@
-use log::trace;
+use futures::StreamExt;
+use log::{debug, trace};
+use reqwest::{
+    header::{CONTENT_RANGE, RANGE},
+    StatusCode,
+};
+use serde::de::DeserializeOwned;
+use sha1::{Digest, Sha1};
 use std::{
-    io::Cursor,
+    future::Future,
+    io::ErrorKind,
+    panic,
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+use tokio::{
+    fs::{self, create_dir_all, File, OpenOptions},
+    io::AsyncWriteExt,
+    task,
+    time::{sleep, timeout, Duration},
+};
+
+use crate::{
+    config::{HttpSettings, RetryPolicy},
+    errors::{FailedDownload, GrindstoneError, GrindstoneResult},
+    event::{CallbackEvent, CallbackFn, DownloadProgress, EventType},
 };
-use tokio::fs::{create_dir_all, File};
-use tokio::io;
 
-use crate::errors::{GrindstoneError, GrindstoneResult};
+use super::{cache::MetadataCache, sha1::get_sha1};
 
-use super::sha1::get_sha1;
+/// Bytes of a file between two progress events.
+const PROGRESS_INTERVAL: u64 = 256 * 1024;
 
+/// A file to download.
 #[derive(Clone, Debug)]
 pub struct Download {
+    /// URL of the file.
     pub url: String,
+    /// Path where the file is saved.
     pub file: PathBuf,
+    /// Expected SHA1 of the file, checked after the download.
     pub sha1: Option<Vec<u8>>,
+    /// Expected size of the file in bytes.
+    pub size: Option<u64>,
+    /// Other URLs of the same file, tried in order when `url` fails.
+    pub alternate_urls: Vec<String>,
+}
+
+/// Byte counters of a batch of downloads, reported through the event callback.
+///
+/// Cloning it shares the counters, so it can be moved into parallel tasks.
+#[derive(Clone)]
+pub struct DownloadTracker {
+    callback: CallbackFn,
+    state: Arc<TrackerState>,
 }
 
-pub async fn download_file<S: Into<String>>(
-    client: &reqwest::Client,
-    url: S,
-    dest: impl AsRef<Path>,
+struct TrackerState {
+    total_files: usize,
+    finished_files: AtomicUsize,
+    downloaded_bytes: AtomicU64,
+    total_bytes: AtomicU64,
+}
+
+impl DownloadTracker {
+    /// * `total_bytes` - Known size of the batch, files of unknown size are added when they start
+    pub fn new(callback: CallbackFn, total_files: usize, total_bytes: u64) -> Self {
+        Self {
+            callback,
+            state: Arc::new(TrackerState {
+                total_files,
+                finished_files: AtomicUsize::new(0),
+                downloaded_bytes: AtomicU64::new(0),
+                total_bytes: AtomicU64::new(total_bytes),
+            }),
+        }
+    }
+
+    /// Tracker of the given downloads, using their known sizes.
+    pub fn for_downloads(callback: CallbackFn, downloads: &[Download]) -> Self {
+        let total_bytes = downloads.iter().filter_map(|d| d.size).sum();
+
+        Self::new(callback, downloads.len(), total_bytes)
+    }
+
+    /// A file that is already present is not downloaded, its size is removed from the batch.
+    fn skip(&self, url: &str, file: &Path, size: Option<u64>) {
+        if let Some(size) = size {
+            self.remove_total(size);
+        }
+
+        self.state.finished_files.fetch_add(1, Ordering::Relaxed);
+        self.report(url, file, 0, 0);
+    }
+
+    /// Bytes resumed from a previous transfer are removed from the batch, like present files.
+    fn start(&self, size: Option<u64>, content_length: Option<u64>, transfer: &mut Transfer) {
+        match (size, content_length) {
+            (Some(_), _) => {
+                self.remove_total(transfer.resumed);
+                transfer.uncounted_total = transfer.resumed;
+            }
+            (None, Some(length)) => {
+                self.state.total_bytes.fetch_add(length, Ordering::Relaxed);
+                transfer.counted_total = length;
+            }
+            (None, None) => {}
+        }
+    }
+
+    fn advance(&self, bytes: u64) {
+        self.state
+            .downloaded_bytes
+            .fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Remove the bytes of a failed transfer, they are downloaded again on retry.
+    fn rewind(&self, transfer: &Transfer) {
+        self.state
+            .downloaded_bytes
+            .fetch_sub(transfer.downloaded, Ordering::Relaxed);
+        self.remove_total(transfer.counted_total);
+        self.state
+            .total_bytes
+            .fetch_add(transfer.uncounted_total, Ordering::Relaxed);
+    }
+
+    fn finish(&self, url: &str, file: &Path, transfer: &Transfer) {
+        self.state.finished_files.fetch_add(1, Ordering::Relaxed);
+        self.report(url, file, transfer.position(), transfer.total);
+    }
+
+    fn remove_total(&self, bytes: u64) {
+        let _ = self
+            .state
+            .total_bytes
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |t| {
+                Some(t.saturating_sub(bytes))
+            });
+    }
+
+    fn report(&self, url: &str, file: &Path, downloaded: u64, total: u64) {
+        let progress = DownloadProgress {
+            url: url.to_string(),
+            file: file.to_path_buf(),
+            current_file: self.state.finished_files.load(Ordering::Relaxed),
+            total_files: self.state.total_files,
+            downloaded_bytes: downloaded,
+            total_bytes: total,
+            batch_downloaded_bytes: self.state.downloaded_bytes.load(Ordering::Relaxed),
+            batch_total_bytes: self.state.total_bytes.load(Ordering::Relaxed),
+        };
+
+        (self.callback)(CallbackEvent {
+            event_type: EventType::Download(progress),
+            message: format!("Downloading {}", url),
+        });
+    }
+}
+
+/// Bytes of a single transfer, including what it changed in the tracker.
+#[derive(Default)]
+struct Transfer {
+    /// Bytes kept from a previous transfer.
+    resumed: u64,
+    /// Bytes received by this transfer.
+    downloaded: u64,
+    /// Size of the file, 0 if unknown.
+    total: u64,
+    counted_total: u64,
+    uncounted_total: u64,
+}
+
+impl Transfer {
+    fn position(&self) -> u64 {
+        self.resumed + self.downloaded
+    }
+}
+
+/// Path of the partial file of a download, next to its final path.
+fn part_path(dest: &Path) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+
+    dest.with_file_name(name)
+}
+
+/// Request a file from `offset`, if the server supports ranges.
+///
+/// Returns the response and the offset it actually starts at.
+async fn request_from(
+    http: &HttpSettings,
+    url: &str,
+    offset: u64,
+) -> GrindstoneResult<(reqwest::Response, u64)> {
+    if offset > 0 {
+        trace!("Resuming download from byte {}", offset);
+
+        let request = http
+            .client
+            .get(url)
+            .header(RANGE, format!("bytes={}-", offset))
+            .send();
+        let response = timed(http.read_timeout, request).await??;
+
+        let start = response
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|r| r.to_str().ok())
+            .and_then(|r| r.strip_prefix("bytes "))
+            .and_then(|r| r.split_once('-'))
+            .and_then(|(start, _)| start.parse::<u64>().ok());
+
+        match response.status() {
+            StatusCode::PARTIAL_CONTENT if start == Some(offset) => return Ok((response, offset)),
+            StatusCode::PARTIAL_CONTENT | StatusCode::RANGE_NOT_SATISFIABLE => {
+                trace!("Download cannot be resumed, restarting it");
+            }
+            _ => return Ok((response.error_for_status()?, 0)),
+        }
+    }
+
+    let response = timed(http.read_timeout, http.client.get(url).send()).await??;
+
+    Ok((response.error_for_status()?, 0))
+}
+
+/// Get a document.
+pub async fn fetch(http: &HttpSettings, url: &str) -> GrindstoneResult<Vec<u8>> {
+    trace!("Fetching {}", url);
+
+    let response = timed(http.read_timeout, http.client.get(url).send())
+        .await??
+        .error_for_status()?;
+
+    Ok(timed(http.read_timeout, response.bytes()).await??.to_vec())
+}
+
+/// Send an API request and read its JSON response, retrying transient failures.
+/// * `build` - Build the request, called again for each attempt
+pub async fn request_json<T: DeserializeOwned>(
+    http: &HttpSettings,
+    retry: &RetryPolicy,
+    build: impl Fn() -> reqwest::RequestBuilder,
+) -> GrindstoneResult<T> {
+    let mut attempt = 0;
+
+    loop {
+        attempt += 1;
+
+        let res: GrindstoneResult<T> = async {
+            let response = timed(http.read_timeout, build().send())
+                .await??
+                .error_for_status()?;
+            let body = timed(http.read_timeout, response.bytes()).await??;
+
+            Ok(serde_json::from_slice(&body)?)
+        }
+        .await;
+
+        match res {
+            Err(err) if attempt < retry.attempts && is_retryable(&err) => {
+                let delay = retry.backoff(attempt);
+                debug!(
+                    "Request failed ({}), retrying in {} ms",
+                    err,
+                    delay.as_millis()
+                );
+                sleep(delay).await;
+            }
+            res => return res,
+        }
+    }
+}
+
+/// Get a document through the metadata cache, revalidating the saved response if there is one.
+/// * `key` - URL the response is saved under
+/// * `url` - URL requested, on a mirror or endpoint
+pub async fn fetch_cached(
+    http: &HttpSettings,
+    cache: &MetadataCache,
+    key: &str,
+    url: &str,
+) -> GrindstoneResult<Vec<u8>> {
+    let mut cached = cache.get(key);
+
+    let mut request = http.client.get(url);
+    if let Some(cached) = &cached {
+        trace!("Revalidating cached {}", url);
+        request = cached.revalidate(request);
+    } else {
+        trace!("Fetching {}", url);
+    }
+
+    let response = timed(http.read_timeout, request.send()).await??;
+
+    if let (StatusCode::NOT_MODIFIED, Some(cached)) = (response.status(), &mut cached) {
+        trace!("Cached {} is up to date", url);
+        cache.refresh(key, cached)?;
+
+        return Ok(std::mem::take(&mut cached.body));
+    }
+
+    let response = response.error_for_status()?;
+    let headers = response.headers().clone();
+    let body = timed(http.read_timeout, response.bytes()).await??;
+
+    cache.put(key, &headers, &body)?;
+
+    Ok(body.to_vec())
+}
+
+/// Wait for a network operation, failing if it takes longer than the read timeout.
+async fn timed<T>(
+    read_timeout: Option<Duration>,
+    operation: impl Future<Output = T>,
+) -> GrindstoneResult<T> {
+    match read_timeout {
+        Some(read_timeout) => timeout(read_timeout, operation)
+            .await
+            .map_err(|_| GrindstoneError::Timeout),
+        None => Ok(operation.await),
+    }
+}
+
+/// Download a file into its `.part` file, resuming a previous transfer if possible.
+/// The file is moved to its final path once its size and checksum are verified.
+async fn download_part(
+    http: &HttpSettings,
+    download: &Download,
+    tracker: Option<&DownloadTracker>,
+    transfer: &mut Transfer,
 ) -> GrindstoneResult<()> {
+    let url = download.url.as_str();
+    let dest = download.file.as_path();
+    let size = download.size;
+
     // Create parent folder
-    if let Some(parent) = dest.as_ref().parent() {
+    if let Some(parent) = dest.parent() {
         trace!("Creating parent folder");
         create_dir_all(parent).await?;
     }
 
-    let url = url.into();
     trace!("Downloading file: {}", url);
 
-    let response = client.get(url).send().await?.error_for_status()?;
+    let part = part_path(dest);
+    let mut offset = match fs::metadata(&part).await {
+        Ok(metadata) => metadata.len(),
+        Err(_) => 0,
+    };
+    // Without size nor checksum, nothing tells if the partial file belongs to this download
+    if size.is_some_and(|size| offset > size) || (size.is_none() && download.sha1.is_none()) {
+        offset = 0;
+    }
 
-    let mut file = File::create(&dest).await?;
-    let mut content = Cursor::new(response.bytes().await?);
-    let _total_size = io::copy(&mut content, &mut file).await?;
+    // A complete partial file only needs to be verified
+    let response = match size == Some(offset) && offset > 0 {
+        true => None,
+        false => {
+            let (response, start) = request_from(http, url, offset).await?;
+            offset = start;
+            Some(response)
+        }
+    };
+
+    let content_length = response.as_ref().and_then(|r| r.content_length());
+    transfer.resumed = offset;
+    transfer.total = size
+        .or(content_length.map(|l| l + offset))
+        .unwrap_or_default();
+    if let Some(tracker) = tracker {
+        tracker.start(size, content_length, transfer);
+    }
+
+    let (mut hasher, mut file) = match offset {
+        0 => (Sha1::new(), File::create(&part).await?),
+        _ => {
+            let path = part.clone();
+            let hasher = blocking(move || {
+                let mut hasher = Sha1::new();
+                std::io::copy(&mut std::fs::File::open(path)?, &mut hasher)?;
+                Ok(hasher)
+            })
+            .await?;
+
+            (hasher, OpenOptions::new().append(true).open(&part).await?)
+        }
+    };
+
+    if let Some(response) = response {
+        let mut stream = response.bytes_stream();
+        let mut reported = 0;
+
+        while let Some(chunk) = timed(http.read_timeout, stream.next()).await? {
+            let chunk = chunk?;
+            http.bandwidth.consume(chunk.len() as u64).await;
+
+            file.write_all(&chunk).await?;
+            hasher.update(&chunk);
+            transfer.downloaded += chunk.len() as u64;
+
+            if let Some(tracker) = tracker {
+                tracker.advance(chunk.len() as u64);
+
+                if transfer.downloaded - reported >= PROGRESS_INTERVAL {
+                    tracker.report(url, dest, transfer.position(), transfer.total);
+                    reported = transfer.downloaded;
+                }
+            }
+        }
+    }
 
     file.sync_all().await?;
     drop(file);
 
+    // A complete file with wrong content cannot be resumed
+    let length = transfer.position();
+    if let Some(size) = size.filter(|size| *size != length) {
+        fs::remove_file(&part).await?;
+        return Err(GrindstoneError::SizeMismatch(size, length));
+    }
+    let local_sha = hasher.finalize();
+    if download
+        .sha1
+        .as_deref()
+        .is_some_and(|sha1| sha1 != local_sha.as_slice())
+    {
+        fs::remove_file(&part).await?;
+        return Err(GrindstoneError::ChecksumMismatch);
+    }
+
+    fs::rename(&part, dest).await?;
+
     Ok(())
 }
 
-pub async fn download_file_check<S: Into<String>>(
-    client: &reqwest::Client,
-    url: S,
-    dest: impl AsRef<Path>,
-    remote_sha: Option<Vec<u8>>,
-) -> GrindstoneResult<String> {
-    let url = url.into();
+/// Run blocking file work, like hashing, off the async workers.
+async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> GrindstoneResult<T> + Send + 'static,
+) -> GrindstoneResult<T> {
+    match task::spawn_blocking(work).await {
+        Ok(result) => result,
+        Err(err) => panic::resume_unwind(err.into_panic()),
+    }
+}
+
+/// Errors that may not happen again on another attempt.
+fn is_retryable(err: &GrindstoneError) -> bool {
+    match err {
+        GrindstoneError::ChecksumMismatch
+        | GrindstoneError::SizeMismatch(_, _)
+        | GrindstoneError::Timeout => true,
+        GrindstoneError::Reqwest(err) => match err.status() {
+            Some(status) => {
+                status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            }
+            None => err.is_timeout() || err.is_connect() || err.is_request() || err.is_body(),
+        },
+        GrindstoneError::IO(err) => matches!(
+            err.kind(),
+            ErrorKind::ConnectionReset
+                | ErrorKind::ConnectionAborted
+                | ErrorKind::BrokenPipe
+                | ErrorKind::TimedOut
+                | ErrorKind::UnexpectedEof
+                | ErrorKind::Interrupted
+        ),
+        _ => false,
+    }
+}
+
+/// Download a file, retrying transient failures.
+async fn download_with_retry(
+    http: &HttpSettings,
+    download: &Download,
+    tracker: Option<&DownloadTracker>,
+    retry: &RetryPolicy,
+) -> GrindstoneResult<()> {
+    let mut attempt = 0;
+
+    loop {
+        attempt += 1;
+
+        let mut transfer = Transfer::default();
+        let res = download_part(http, download, tracker, &mut transfer).await;
+
+        match res {
+            Ok(()) => {
+                if let Some(tracker) = tracker {
+                    tracker.finish(&download.url, &download.file, &transfer);
+                }
+
+                return Ok(());
+            }
+            Err(err) => {
+                if let Some(tracker) = tracker {
+                    tracker.rewind(&transfer);
+                }
+
+                if attempt >= retry.attempts || !is_retryable(&err) {
+                    return Err(err);
+                }
+
+                let delay = retry.backoff(attempt);
+                debug!(
+                    "Download of {} failed ({}), retrying in {} ms",
+                    download.url,
+                    err,
+                    delay.as_millis()
+                );
+                sleep(delay).await;
+            }
+        }
+    }
+}
+
+/// Checked download of a file of a batch, reporting its bytes to the tracker.
+pub async fn download_tracked(
+    http: &HttpSettings,
+    download: Download,
+    tracker: &DownloadTracker,
+    retry: &RetryPolicy,
+) -> Result<String, FailedDownload> {
+    let res = check_and_download(http, &download, Some(tracker), retry).await;
+
+    match res {
+        Ok(()) => Ok(download.url),
+        Err(err) => Err(FailedDownload {
+            url: download.url,
+            file: download.file,
+            error: Box::new(err),
+        }),
+    }
+}
+
+/// Fail with every download of a batch that did not succeed.
+pub fn ensure_downloaded(failed: Vec<FailedDownload>) -> GrindstoneResult<()> {
+    match failed.is_empty() {
+        true => Ok(()),
+        false => Err(GrindstoneError::DownloadFailed(failed)),
+    }
+}
+
+async fn check_and_download(
+    http: &HttpSettings,
+    download: &Download,
+    tracker: Option<&DownloadTracker>,
+    retry: &RetryPolicy,
+) -> GrindstoneResult<()> {
+    let url = download.url.as_str();
+    let dest = download.file.as_path();
+
     trace!("Checked download of file: {}", url);
 
-    if dest.as_ref().exists() {
+    if dest.exists() {
         trace!("File already exists");
 
-        match &remote_sha {
-            None => {
-                return Ok(url);
+        // The size is checked first, it is cheap and catches files without checksum
+        let size_matches = match download.size {
+            None => true,
+            Some(size) => fs::metadata(dest).await.is_ok_and(|m| m.len() == size),
+        };
+
+        let valid = size_matches
+            && match &download.sha1 {
+                None => true,
+                Some(remote_sha) => {
+                    let path = dest.to_path_buf();
+                    remote_sha == &blocking(move || get_sha1(path)).await?
+                }
+            };
+
+        if valid {
+            trace!("Existing file is correct");
+
+            if let Some(tracker) = tracker {
+                tracker.skip(url, dest, download.size);
             }
-            Some(remote_sha) => {
-                let local_sha = get_sha1(&dest)?;
 
-                if remote_sha == &local_sha {
-                    trace!("Existing file is correct");
+            return Ok(());
+        }
+
+        trace!("Existing file does not match size or checksum");
+    }
+
+    download_with_retry(http, download, tracker, retry).await
+}
+
+/// Serve fixed responses over HTTP for tests, returning the base URL of the server.
+/// Ranges starting inside the body of a successful response are answered with the rest of it.
+/// * `routes` - Path without query, status code and body of each response, other paths answer 404
+#[cfg(test)]
+pub async fn test_server(routes: Vec<(&'static str, u16, Vec<u8>)>) -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let routes = Arc::new(routes);
+
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let routes = routes.clone();
 
-                    return Ok(url);
-                } else {
-                    trace!("Existing file does not match checksum");
+            tokio::spawn(async move {
+                let mut raw = Vec::new();
+                let mut buf = [0; 1024];
+                let head_end = loop {
+                    if let Some(end) = raw.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break end + 4;
+                    }
+                    match socket.read(&mut buf).await {
+                        Ok(0) | Err(_) => return,
+                        Ok(n) => raw.extend_from_slice(&buf[..n]),
+                    }
+                };
+                let request = String::from_utf8_lossy(&raw[..head_end]).to_lowercase();
 
-                    download_file(&client, &url, &dest).await?;
+                // The body is read, closing with unread data would reset the connection
+                let length = request
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length:"))
+                    .and_then(|length| length.trim().parse::<usize>().ok())
+                    .unwrap_or_default();
+                while raw.len() < head_end + length {
+                    match socket.read(&mut buf).await {
+                        Ok(0) | Err(_) => return,
+                        Ok(n) => raw.extend_from_slice(&buf[..n]),
+                    }
                 }
-            }
+
+                let path = request.split([' ', '?']).nth(1).unwrap_or_default();
+                let (mut status, mut body) = routes
+                    .iter()
+                    .find(|(route, _, _)| route.to_lowercase() == path)
+                    .map(|(_, status, body)| (*status, body.as_slice()))
+                    .unwrap_or((404, b""));
+
+                // Successful responses honor the start of a range
+                let mut content_range = String::new();
+                let start = request
+                    .lines()
+                    .find_map(|line| line.strip_prefix("range: bytes="))
+                    .and_then(|range| range.trim().trim_end_matches('-').parse::<usize>().ok());
+                if let Some(start) = start.filter(|s| status == 200 && *s < body.len()) {
+                    content_range = format!(
+                        "Content-Range: bytes {}-{}/{}\r\n",
+                        start,
+                        body.len() - 1,
+                        body.len()
+                    );
+                    status = 206;
+                    body = &body[start..];
+                }
+
+                let head = format!(
+                    "HTTP/1.1 {} Test\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n",
+                    status,
+                    body.len(),
+                    content_range
+                );
+                let _ = socket.write_all(head.as_bytes()).await;
+                let _ = socket.write_all(body).await;
+            });
         }
-    } else {
-        download_file(&client, &url, &dest).await?;
+    });
+
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        download::BandwidthLimiter,
+        utils::{fs::test_folder, sha1::get_sha1_bytes},
+    };
+
+    use super::*;
+
+    #[test]
+    fn retryable_errors() {
+        assert!(is_retryable(&GrindstoneError::ChecksumMismatch));
+        assert!(is_retryable(&GrindstoneError::Timeout));
+        assert!(is_retryable(&GrindstoneError::IO(
+            ErrorKind::ConnectionReset.into()
+        )));
+
+        assert!(!is_retryable(&GrindstoneError::IO(
+            ErrorKind::PermissionDenied.into()
+        )));
+        assert!(!is_retryable(&GrindstoneError::Offline(
+            "manifest".to_string()
+        )));
     }
 
-    if let Some(remote_sha) = &remote_sha {
-        let local_sha = get_sha1(&dest)?;
-        if remote_sha != &local_sha {
-            return Err(GrindstoneError::ChecksumMismatch);
+    fn http() -> HttpSettings {
+        HttpSettings {
+            client: reqwest::Client::new(),
+            max_parallel_downloads: 4,
+            read_timeout: None,
+            bandwidth: BandwidthLimiter::default(),
         }
     }
 
-    Ok(url)
-}
+    #[tokio::test]
+    async fn existing_file_size_is_checked() {
+        let folder = test_folder("check-size");
+        let url = test_server(vec![("/file", 200, b"content".to_vec())]).await;
+        let download = Download {
+            url: format!("{}/file", url),
+            file: folder.join("file"),
+            sha1: None,
+            size: Some(7),
+            alternate_urls: Vec::new(),
+        };
 
-/// Progress of an ongoing download.
-#[derive(Clone, Debug)]
-pub struct DownloadProgress {
-    /// The URL of the download.
-    pub url: String,
-    /// The path where the file is saved.
-    pub file: PathBuf,
-    /// Current file index.
-    pub current_file: usize,
-    /// Number of files that are being downloaded.
-    pub total_files: usize,
-    /// Bytes that already got downloaded.
-    pub downloaded_bytes: u64,
-    /// Total bytes of the file.
-    pub total_bytes: u64,
+        // Right size, kept without any request
+        std::fs::write(&download.file, b"CONTENT").unwrap();
+        check_and_download(&http(), &download, None, &RetryPolicy::none())
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&download.file).unwrap(), b"CONTENT");
+
+        // Truncated file, downloaded again
+        std::fs::write(&download.file, b"cont").unwrap();
+        check_and_download(&http(), &download, None, &RetryPolicy::none())
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&download.file).unwrap(), b"content");
+
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[tokio::test]
+    async fn fetch_from_cache() {
+        let folder = test_folder("fetch-cached");
+        let cache = MetadataCache::new(folder.clone(), Duration::from_secs(60));
+        let url = test_server(vec![
+            ("/manifest.json", 200, b"{\"latest\": 1}".to_vec()),
+            ("/unchanged.json", 304, Vec::new()),
+        ])
+        .await;
+
+        let manifest = format!("{}/manifest.json", url);
+        let body = fetch_cached(&http(), &cache, &manifest, &manifest)
+            .await
+            .unwrap();
+        assert_eq!(body, b"{\"latest\": 1}");
+        assert_eq!(cache.get(&manifest).unwrap().body, body);
+
+        // Not modified on a mirror, the response cached for the official URL is used
+        let unchanged = format!("{}/unchanged.json", url);
+        let body = fetch_cached(&http(), &cache, &manifest, &unchanged)
+            .await
+            .unwrap();
+        assert_eq!(body, b"{\"latest\": 1}");
+
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[tokio::test]
+    async fn resume_partial_download() {
+        let folder = test_folder("resume");
+        let url = test_server(vec![("/file", 200, b"content".to_vec())]).await;
+        let download = |sha1: Option<Vec<u8>>| Download {
+            url: format!("{}/file", url),
+            file: folder.join("file"),
+            sha1,
+            size: Some(7),
+            alternate_urls: Vec::new(),
+        };
+        let part = part_path(&folder.join("file"));
+
+        // Only the missing bytes are requested
+        std::fs::write(&part, b"CONT").unwrap();
+        check_and_download(&http(), &download(None), None, &RetryPolicy::none())
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(folder.join("file")).unwrap(), b"CONTent");
+        assert!(!part.exists());
+
+        // A corrupted partial file is downloaded again on the next attempt
+        let retry = RetryPolicy {
+            attempts: 2,
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+        };
+        std::fs::remove_file(folder.join("file")).unwrap();
+        std::fs::write(&part, b"CONT").unwrap();
+        let sha1 = get_sha1_bytes(b"content");
+        check_and_download(&http(), &download(Some(sha1)), None, &retry)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(folder.join("file")).unwrap(), b"content");
+
+        // A partial file cannot be checked without size nor checksum, it is discarded
+        std::fs::remove_file(folder.join("file")).unwrap();
+        std::fs::write(&part, b"STALE").unwrap();
+        let unchecked = Download {
+            size: None,
+            ..download(None)
+        };
+        check_and_download(&http(), &unchecked, None, &RetryPolicy::none())
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(folder.join("file")).unwrap(), b"content");
+
+        std::fs::remove_dir_all(folder).unwrap();
+    }
 }
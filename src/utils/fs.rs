@@ -0,0 +1,234 @@
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Component, Path, PathBuf},
+};
+
+use log::trace;
+
+use crate::errors::GrindstoneResult;
+
+/// Recursively copy the content of a folder.
+///
+/// * `source` - Folder to copy
+/// * `dest` - Destination folder, created if needed
+/// * `skip` - Returns `true` for the paths (relative to `source`) that must not be copied
+pub fn copy_folder(
+    source: impl AsRef<Path>,
+    dest: impl AsRef<Path>,
+    skip: impl Fn(&Path) -> bool,
+) -> GrindstoneResult<()> {
+    let source = source.as_ref();
+    let dest = dest.as_ref();
+
+    fs::create_dir_all(dest)?;
+
+    let mut folders = vec![source.to_path_buf()];
+    while let Some(folder) = folders.pop() {
+        for entry in fs::read_dir(&folder)? {
+            let entry = entry?;
+            let path = entry.path();
+            let relative = path.strip_prefix(source).unwrap_or(&path);
+
+            if skip(relative) {
+                continue;
+            }
+
+            let target = dest.join(relative);
+            let file_type = entry.file_type()?;
+
+            if file_type.is_dir() {
+                fs::create_dir_all(&target)?;
+                folders.push(path);
+            } else if file_type.is_file() {
+                trace!("Copying {}", relative.to_string_lossy());
+                fs::copy(&path, &target)?;
+            } else if file_type.is_symlink() {
+                trace!("Copying link {}", relative.to_string_lossy());
+                copy_symlink(&path, &target)?;
+            } else {
+                trace!("Skipping special file {}", relative.to_string_lossy());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Create a link pointing to the same target as an existing one.
+fn copy_symlink(link: &Path, dest: &Path) -> GrindstoneResult<()> {
+    let target = fs::read_link(link)?;
+
+    cfg_if::cfg_if! {
+        if #[cfg(unix)] {
+            std::os::unix::fs::symlink(target, dest)?;
+        } else if #[cfg(target_os = "windows")] {
+            let res = if link.is_dir() {
+                std::os::windows::fs::symlink_dir(&target, dest)
+            } else {
+                std::os::windows::fs::symlink_file(&target, dest)
+            };
+
+            // Creating links needs the developer mode or admin rights, copy the target instead
+            if let Err(err) = res {
+                trace!("Could not create link, copying its target: {}", err);
+
+                if link.is_dir() {
+                    copy_folder(link, dest, |_| false)?;
+                } else if link.is_file() {
+                    fs::copy(link, dest)?;
+                }
+            }
+        } else {
+            compile_error!("Unknown platform {}", env::consts::OS)
+        }
+    }
+
+    Ok(())
+}
+
+/// Convert a path given by an archive or a pack into a relative path.
+///
+/// Returns `None` for absolute paths and paths leaving the destination folder.
+pub fn safe_relative_path(path: &str) -> Option<PathBuf> {
+    let path = Path::new(path);
+
+    let safe = path.components().count() > 0
+        && path.components().all(|c| matches!(c, Component::Normal(_)));
+
+    safe.then(|| path.to_path_buf())
+}
+
+/// Write a file atomically: the content goes to a temporary file next to it,
+/// renamed once complete, so `path` never holds a partially written file.
+pub fn write_atomic(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> GrindstoneResult<()> {
+    write_atomic_with(path, |writer| Ok(writer.write_all(contents.as_ref())?))
+}
+
+/// Like [`write_atomic`], with the content written by a function.
+pub fn write_atomic_with(
+    path: impl AsRef<Path>,
+    write: impl FnOnce(&mut BufWriter<File>) -> GrindstoneResult<()>,
+) -> GrindstoneResult<()> {
+    let path = path.as_ref();
+    let temp = temp_path(path);
+
+    let res = (|| {
+        let mut writer = BufWriter::new(File::create(&temp)?);
+        write(&mut writer)?;
+        writer
+            .into_inner()
+            .map_err(|e| e.into_error())?
+            .sync_all()?;
+
+        trace!("Moving {} into place", temp.to_string_lossy());
+        fs::rename(&temp, path)?;
+
+        Ok(())
+    })();
+
+    if res.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+
+    res
+}
+
+/// Unique temporary path next to a file, on the same file system so it can be renamed.
+pub fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+
+    path.with_file_name(format!("{}.{:08x}.tmp", name, fastrand::u32(..)))
+}
+
+/// Create an empty folder for a test, unique to each call.
+#[cfg(test)]
+pub fn test_folder(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("grindstone-{}-{:08x}", name, fastrand::u32(..)));
+    fs::create_dir_all(&path).unwrap();
+
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::GrindstoneError;
+
+    use super::*;
+
+    #[test]
+    fn copy_folder_content() {
+        let folder = test_folder("copy-folder");
+        let source = folder.join("source");
+        fs::create_dir_all(source.join("config/sub")).unwrap();
+        fs::write(source.join("options.txt"), "fov:1.0").unwrap();
+        fs::write(source.join("config/sub/mod.toml"), "a").unwrap();
+        fs::write(source.join(".lock"), "").unwrap();
+
+        let dest = folder.join("dest");
+        copy_folder(&source, &dest, |path| path == Path::new(".lock")).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(dest.join("options.txt")).unwrap(),
+            "fov:1.0"
+        );
+        assert!(dest.join("config/sub/mod.toml").is_file());
+        assert!(!dest.join(".lock").exists());
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_folder_links() {
+        let folder = test_folder("copy-links");
+        let source = folder.join("source");
+        fs::create_dir_all(source.join("shared")).unwrap();
+        fs::write(source.join("shared/options.txt"), "").unwrap();
+        std::os::unix::fs::symlink("shared/options.txt", source.join("options.txt")).unwrap();
+        std::os::unix::fs::symlink("shared", source.join("linked")).unwrap();
+
+        let dest = folder.join("dest");
+        copy_folder(&source, &dest, |_| false).unwrap();
+
+        assert_eq!(
+            fs::read_link(dest.join("options.txt")).unwrap(),
+            Path::new("shared/options.txt")
+        );
+        assert_eq!(
+            fs::read_link(dest.join("linked")).unwrap(),
+            Path::new("shared")
+        );
+        assert!(dest.join("options.txt").is_file());
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn relative_paths() {
+        assert_eq!(
+            safe_relative_path("mods/a.jar"),
+            Some(PathBuf::from("mods/a.jar"))
+        );
+
+        for path in ["", "/etc/passwd", "../a", "mods/../../a", "./a"] {
+            assert_eq!(safe_relative_path(path), None, "{}", path);
+        }
+    }
+
+    #[test]
+    fn atomic_write_replaces_file() {
+        let folder = test_folder("write-atomic");
+        let path = folder.join("file.json");
+
+        write_atomic(&path, "old").unwrap();
+        write_atomic(&path, "new").unwrap();
+        assert!(write_atomic_with(&path, |_| Err(GrindstoneError::Timeout)).is_err());
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        // No temporary file is left behind
+        assert_eq!(fs::read_dir(&folder).unwrap().count(), 1);
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
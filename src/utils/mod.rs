@@ -1,4 +1,7 @@
+pub mod archive;
+pub mod cache;
 pub mod download;
 pub mod either;
+pub mod fs;
 pub mod os;
 pub mod sha1;
@@ -12,3 +12,7 @@ pub fn get_sha1(file: impl AsRef<Path>) -> GrindstoneResult<Vec<u8>> {
 
     Ok(hash.to_vec())
 }
+
+pub fn get_sha1_bytes(data: &[u8]) -> Vec<u8> {
+    Sha1::digest(data).to_vec()
+}